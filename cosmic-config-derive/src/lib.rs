@@ -2,17 +2,22 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{self};
 
-#[proc_macro_derive(CosmicConfigEntry, attributes(version, id))]
+#[proc_macro_derive(
+    CosmicConfigEntry,
+    attributes(version, id, format, migration, validate)
+)]
 pub fn cosmic_config_entry_derive(input: TokenStream) -> TokenStream {
     // Construct a representation of Rust code as a syntax tree
     // that we can manipulate
-    let ast = syn::parse(input).unwrap();
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
 
-    // Build the trait implementation
+    // Build the trait implementation, or point at the attribute which is malformed
     impl_cosmic_config_entry_macro(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
+fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let attributes = &ast.attrs;
     let version = attributes
         .iter()
@@ -35,6 +40,96 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
         })
         .unwrap_or(0);
 
+    // `#[format = "json"]` stores every key of the entry in the given format.
+    let format = attributes
+        .iter()
+        .find(|attr| attr.path().is_ident("format"))
+        .map(|attr| {
+            let syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(ref lit_str),
+                        ..
+                    }),
+                ..
+            }) = attr.meta
+            else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected `#[format = \"ron\" | \"json\" | \"toml\"]`",
+                ));
+            };
+            let variant = match lit_str.value().as_str() {
+                "ron" => quote!(Ron),
+                "json" => quote!(Json),
+                "toml" => quote!(Toml),
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        lit_str,
+                        format!("unknown config format `{other}`"),
+                    ));
+                }
+            };
+            Ok(quote! {
+                let config = &config.clone().with_format(cosmic_config::Format::#variant);
+            })
+        })
+        .transpose()?
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    // Each `#[migration(from = N, with = path)]` registers an upgrade step from version N.
+    let migrations = attributes
+        .iter()
+        .filter(|attr| attr.path().is_ident("migration"))
+        .map(|attr| {
+            let mut from = None;
+            let mut with = None;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("from") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    from = Some(lit.base10_parse::<u64>()?);
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    with = Some(meta.value()?.parse::<syn::Path>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `from` or `with`"))
+                }
+            })?;
+            let from = from.ok_or_else(|| {
+                syn::Error::new_spanned(attr, "migration requires `from = <version>`")
+            })?;
+            let with = with.ok_or_else(|| {
+                syn::Error::new_spanned(attr, "migration requires `with = <function>`")
+            })?;
+            Ok(quote! {
+                .step(#from, #with)
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let migrations_fn = (!migrations.is_empty()).then(|| {
+        // Migrated keys are written in the entry's format.
+        let migrate_entry_fn = (!format.is_empty()).then(|| {
+            quote! {
+                fn migrate_entry(config: &cosmic_config::Config) -> Result<Option<cosmic_config::MigrationReport>, cosmic_config::Error> {
+                    #(#format)*
+                    config.migrate(&Self::migrations())
+                }
+            }
+        });
+
+        quote! {
+            fn migrations() -> cosmic_config::Migrations {
+                cosmic_config::Migrations::new()
+                    #(#migrations)*
+            }
+
+            #migrate_entry_fn
+        }
+    });
+
     let name = &ast.ident;
 
     // Get the fields of the struct
@@ -48,30 +143,76 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
 
     let write_each_config_field = fields.iter().map(|field| {
         let field_name = &field.ident;
+        // Keys locked by the administrator are left untouched.
         quote! {
-            cosmic_config::ConfigSet::set(&tx, stringify!(#field_name), &self.#field_name)?;
+            if !config.is_locked(stringify!(#field_name)) {
+                cosmic_config::ConfigSet::set(&tx, stringify!(#field_name), &self.#field_name)?;
+            }
         }
     });
 
-    let get_each_config_field = fields.iter().map(|field| {
+    let field_validations = fields
+        .iter()
+        .map(field_validations)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Checks from the `#[validate(...)]` attributes of each field, which evaluate to a
+    // `Result<(), String>` for the `value` in scope.
+    let validations = field_validations
+        .iter()
+        .map(|(checks, _)| {
+            (!checks.is_empty()).then(|| {
+                quote! {
+                    Ok::<(), String>(()) #(.and_then(|()| #checks))*
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let get_each_config_field = fields.iter().zip(&validations).map(|(field, validation)| {
         let field_name = &field.ident;
         let field_type = &field.ty;
-        quote! {
-            match cosmic_config::ConfigGet::get::<#field_type>(config, stringify!(#field_name)) {
-                Ok(#field_name) => default.#field_name = #field_name,
-                Err(why) if matches!(why, cosmic_config::Error::NoConfigDirectory) => (),
-                Err(e) => errors.push(e),
-            }
+        match validation {
+            Some(validation) => quote! {
+                match cosmic_config::ConfigGet::get::<#field_type>(config, stringify!(#field_name)) {
+                    Ok(value) => match #validation {
+                        Ok(()) => default.#field_name = value,
+                        Err(why) => errors.push(cosmic_config::Error::Invalid(stringify!(#field_name).to_owned(), why)),
+                    },
+                    Err(why) if matches!(why, cosmic_config::Error::NoConfigDirectory) => (),
+                    Err(e) => errors.push(e),
+                }
+            },
+            None => quote! {
+                match cosmic_config::ConfigGet::get::<#field_type>(config, stringify!(#field_name)) {
+                    Ok(#field_name) => default.#field_name = #field_name,
+                    Err(why) if matches!(why, cosmic_config::Error::NoConfigDirectory) => (),
+                    Err(e) => errors.push(e),
+                }
+            },
         }
     });
 
-    let update_each_config_field = fields.iter().map(|field| {
+    let update_each_config_field = fields.iter().zip(&validations).map(|(field, validation)| {
         let field_name = &field.ident;
         let field_type = &field.ty;
+        // Invalid values reset the field to its default.
+        let validate = validation.as_ref().map(|validation| {
+            quote! {
+                let value = match #validation {
+                    Ok(()) => value,
+                    Err(why) => {
+                        errors.push(cosmic_config::Error::Invalid(stringify!(#field_name).to_owned(), why));
+                        Self::default().#field_name
+                    }
+                };
+            }
+        });
         quote! {
             stringify!(#field_name) => {
                 match cosmic_config::ConfigGet::get::<#field_type>(config, stringify!(#field_name)) {
                     Ok(value) => {
+                        #validate
                         if self.#field_name != value {
                             keys.push(stringify!(#field_name));
                         }
@@ -85,16 +226,28 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
         }
     });
 
-    let setters = fields.iter().filter_map(|field| {
+    let setters = fields.iter().zip(&validations).filter_map(|(field, validation)| {
         let field_name = &field.ident.as_ref()?;
         let field_type = &field.ty;
         let setter_name = quote::format_ident!("set_{}", field_name);
         let doc = format!("Sets [`{name}::{field_name}`] and writes to [`cosmic_config::Config`] if changed");
+        let validate = validation.as_ref().map(|validation| {
+            quote! {
+                if let Err(why) = #validation {
+                    return Err(cosmic_config::Error::Invalid(stringify!(#field_name).to_owned(), why));
+                }
+            }
+        });
         Some(quote! {
             #[doc = #doc]
             ///
             /// Returns `Ok(true)` when the field's value has changed and was written to disk
             pub fn #setter_name(&mut self, config: &cosmic_config::Config, value: #field_type) -> Result<bool, cosmic_config::Error> {
+                #(#format)*
+                if config.is_locked(stringify!(#field_name)) {
+                    return Err(cosmic_config::Error::Locked(stringify!(#field_name).to_owned()));
+                }
+                #validate
                 if self.#field_name != value {
                     self.#field_name = value;
                     cosmic_config::ConfigSet::set(config, stringify!(#field_name), &self.#field_name)?;
@@ -106,17 +259,40 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
         })
     });
 
+    let schema_keys = fields.iter().zip(&field_validations).map(|(field, (_, schema))| {
+        let field_name = &field.ident;
+        let field_type = &field.ty;
+        let doc = field_doc(field).map(|doc| quote!(.doc(#doc)));
+        quote! {
+            cosmic_config::schema::Key::new::<#field_type>(stringify!(#field_name), &default.#field_name)
+                #doc
+                #(#schema)*
+        }
+    });
+
     let generate = quote! {
         impl CosmicConfigEntry for #name {
             const VERSION: u64 = #version;
 
+            #migrations_fn
+
+            fn schema() -> cosmic_config::schema::Schema {
+                let default = Self::default();
+                cosmic_config::schema::Schema {
+                    version: #version,
+                    keys: vec![#(#schema_keys),*],
+                }
+            }
+
             fn write_entry(&self, config: &cosmic_config::Config) -> Result<(), cosmic_config::Error> {
+                #(#format)*
                 let tx = config.transaction();
                 #(#write_each_config_field)*
                 tx.commit()
             }
 
             fn get_entry(config: &cosmic_config::Config) -> Result<Self, (Vec<cosmic_config::Error>, Self)> {
+                #(#format)*
                 let mut default = Self::default();
                 let mut errors = Vec::new();
 
@@ -130,6 +306,7 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
             }
 
             fn update_keys<T: AsRef<str>>(&mut self, config: &cosmic_config::Config, changed_keys: &[T]) -> (Vec<cosmic_config::Error>, Vec<&'static str>){
+                #(#format)*
                 let mut keys = Vec::with_capacity(changed_keys.len());
                 let mut errors = Vec::new();
                 for key in changed_keys.iter() {
@@ -147,5 +324,110 @@ fn impl_cosmic_config_entry_macro(ast: &syn::DeriveInput) -> TokenStream {
         }
     };
 
-    generate.into()
+    Ok(generate)
+}
+
+/// Collects the checks of every `#[validate(...)]` attribute on a field, along with
+/// the calls which describe them on its `cosmic_config::schema::Key`.
+///
+/// Supported checks are `range(min = .., max = ..)`, `one_of(..)`, `pattern = ".."`,
+/// `non_empty` and `with = path`, where `path` is a `fn(&T) -> Result<(), String>`.
+fn field_validations(
+    field: &syn::Field,
+) -> syn::Result<(Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>)> {
+    let field_type = &field.ty;
+    let mut checks = Vec::new();
+    let mut schema = Vec::new();
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("validate"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("range") {
+                let mut min = quote!(None);
+                let mut max = quote!(None);
+                meta.parse_nested_meta(|bound| {
+                    let value: syn::Expr = bound.value()?.parse()?;
+                    if bound.path.is_ident("min") {
+                        min = quote!(Some(#value));
+                    } else if bound.path.is_ident("max") {
+                        max = quote!(Some(#value));
+                    } else {
+                        return Err(bound.error("expected `min` or `max`"));
+                    }
+                    Ok(())
+                })?;
+                checks.push(quote! {
+                    cosmic_config::validation::range(&value, #min, #max)
+                });
+                schema.push(quote! {
+                    .range::<#field_type>(#min, #max)
+                });
+            } else if meta.path.is_ident("one_of") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                let allowed = content
+                    .parse_terminated(<syn::Expr as syn::parse::Parse>::parse, syn::Token![,])?
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                checks.push(quote! {
+                    cosmic_config::validation::one_of(&value, &[#(#allowed),*])
+                });
+                schema.push(quote! {
+                    .options(&[#(#allowed),*])
+                });
+            } else if meta.path.is_ident("pattern") {
+                let pattern: syn::LitStr = meta.value()?.parse()?;
+                checks.push(quote! {
+                    cosmic_config::validation::pattern(&value, #pattern)
+                });
+                schema.push(quote! {
+                    .pattern(#pattern)
+                });
+            } else if meta.path.is_ident("non_empty") {
+                checks.push(quote! {
+                    cosmic_config::validation::non_empty(&value)
+                });
+                schema.push(quote! {
+                    .non_empty()
+                });
+            } else if meta.path.is_ident("with") {
+                let path: syn::Path = meta.value()?.parse()?;
+                checks.push(quote! {
+                    #path(&value)
+                });
+            } else {
+                return Err(
+                    meta.error("expected `range`, `one_of`, `pattern`, `non_empty` or `with`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok((checks, schema))
+}
+
+/// The doc comment of a field, with each line trimmed.
+fn field_doc(field: &syn::Field) -> Option<String> {
+    let lines = field
+        .attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit_str),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => Some(lit_str.value().trim().to_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
 }
@@ -0,0 +1,354 @@
+//! Back up, export and import user config values as a portable RON document.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fs, io};
+
+use crate::migration::{read_keys, version_of};
+use crate::{Config, Error, now};
+
+/// The user-defined keys of one or more configs.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Bundle {
+    /// Seconds since the Unix epoch when the bundle was created.
+    pub created: u64,
+    pub configs: Vec<ConfigBundle>,
+}
+
+/// The user-defined keys of a single config.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConfigBundle {
+    /// Name of the config, such as `com.system76.CosmicTk`.
+    pub name: String,
+    pub version: u64,
+    /// Seconds since the Unix epoch when the config was exported.
+    pub exported: u64,
+    /// Each key with its value, serialized in the format that it is stored in.
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Bundle {
+    /// Create a bundle of configs, timestamped with the current time.
+    pub fn new(configs: Vec<ConfigBundle>) -> Self {
+        Self {
+            created: now(),
+            configs,
+        }
+    }
+
+    /// Read a bundle from a RON document.
+    pub fn from_ron(data: &str) -> Result<Self, Error> {
+        Ok(ron::from_str(data)?)
+    }
+
+    /// Write the bundle to a RON document.
+    pub fn to_ron(&self) -> Result<String, Error> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::new(),
+        )?)
+    }
+}
+
+/// How a bundle should be imported.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImportOptions {
+    /// Report what would be imported without writing anything.
+    pub dry_run: bool,
+    /// Replace keys which already have a different value.
+    pub overwrite: bool,
+}
+
+/// The outcome of importing a [`ConfigBundle`].
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub name: String,
+    /// Keys that were, or in a dry run would be, written.
+    pub imported: Vec<String>,
+    /// Keys whose existing value differs from the bundle, and were not overwritten
+    /// unless [`ImportOptions::overwrite`] was set.
+    pub conflicts: Vec<String>,
+    /// The version of the bundle and the version it was imported to, if they differ.
+    /// Nothing is imported when the versions mismatch.
+    pub version_mismatch: Option<(u64, u64)>,
+    /// Keys that could not be deserialized or written.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl Config {
+    /// Export every key which the user has defined in this config.
+    pub fn export(&self) -> Result<ConfigBundle, Error> {
+        let (name, version) = self.name_and_version().ok_or(Error::NoConfigDirectory)?;
+
+        let keys = if let Some(store) = self.memory.as_ref() {
+            store.entries()
+        } else {
+            let user_path = self.user_path.as_ref().ok_or(Error::NoConfigDirectory)?;
+            let mut keys = BTreeMap::new();
+            read_keys(user_path, user_path, &mut keys)?;
+            keys
+        };
+
+        Ok(ConfigBundle {
+            name,
+            version,
+            exported: now(),
+            keys,
+        })
+    }
+
+    /// Import the keys of a bundle into this config.
+    pub fn import(
+        &self,
+        bundle: &ConfigBundle,
+        options: ImportOptions,
+    ) -> Result<ImportReport, Error> {
+        let mut report = ImportReport {
+            name: bundle.name.clone(),
+            ..ImportReport::default()
+        };
+
+        if let Some((_, version)) = self.name_and_version()
+            && version != bundle.version
+        {
+            report.version_mismatch = Some((bundle.version, version));
+            return Ok(report);
+        }
+
+        let tx = self.transaction();
+
+        for (key, data) in &bundle.keys {
+            // Reject values which are not valid in the key's format.
+            if let Err(why) = self.key_format(key).from_str::<ron::Value>(data) {
+                report.failed.push((key.clone(), why));
+                continue;
+            }
+
+            if self.is_locked(key) {
+                report
+                    .failed
+                    .push((key.clone(), Error::Locked(key.clone())));
+                continue;
+            }
+
+            match self.get_raw(key) {
+                Ok(existing) if existing == *data => continue,
+                Ok(_) => {
+                    report.conflicts.push(key.clone());
+                    if !options.overwrite {
+                        continue;
+                    }
+                }
+                Err(_) => (),
+            }
+
+            if !options.dry_run
+                && let Err(why) = tx.set_raw(key, data.clone())
+            {
+                report.failed.push((key.clone(), why));
+                continue;
+            }
+
+            report.imported.push(key.clone());
+        }
+
+        if !options.dry_run {
+            tx.commit()?;
+        }
+
+        Ok(report)
+    }
+
+    /// The name and version of the config, derived from its location.
+    pub(crate) fn name_and_version(&self) -> Option<(String, u64)> {
+        if let Some(store) = self.memory.as_ref() {
+            return Some((store.name.clone(), store.version));
+        }
+
+        let user_path = self.user_path.as_ref()?;
+        let version = version_of(user_path)?;
+        let name = user_path.parent()?.file_name()?.to_str()?;
+        Some((name.to_owned(), version))
+    }
+}
+
+/// Export every config in the user's cosmic config directory.
+pub fn export_all() -> Result<Bundle, Error> {
+    let config_dir = crate::get_config_dir().ok_or(Error::NoConfigDirectory)?;
+    export_all_from(&config_dir)
+}
+
+/// Export every config under `<root>/cosmic`.
+pub fn export_all_from(root: &Path) -> Result<Bundle, Error> {
+    let mut configs = Vec::new();
+
+    for (name, versions) in config_versions(root)? {
+        for version in versions {
+            let config = Config::with_custom_path(&name, version, root.to_owned())?;
+            let bundle = config.export()?;
+            if !bundle.keys.is_empty() {
+                configs.push(bundle);
+            }
+        }
+    }
+
+    Ok(Bundle::new(configs))
+}
+
+/// Import every config of a bundle into the user's cosmic config directory.
+pub fn import_all(bundle: &Bundle, options: ImportOptions) -> Result<Vec<ImportReport>, Error> {
+    let config_dir = crate::get_config_dir().ok_or(Error::NoConfigDirectory)?;
+    import_all_to(&config_dir, bundle, options)
+}
+
+/// Import every config of a bundle under `<root>/cosmic`.
+///
+/// A config whose newest version on this system differs from the bundle is
+/// reported as a version mismatch, and not imported.
+pub fn import_all_to(
+    root: &Path,
+    bundle: &Bundle,
+    options: ImportOptions,
+) -> Result<Vec<ImportReport>, Error> {
+    let versions = config_versions(root)?;
+    let mut reports = Vec::with_capacity(bundle.configs.len());
+
+    for config_bundle in &bundle.configs {
+        if let Some(&newest) = versions
+            .get(&config_bundle.name)
+            .and_then(|versions| versions.last())
+            && newest != config_bundle.version
+        {
+            reports.push(ImportReport {
+                name: config_bundle.name.clone(),
+                version_mismatch: Some((config_bundle.version, newest)),
+                ..ImportReport::default()
+            });
+            continue;
+        }
+
+        let config = if options.dry_run {
+            // Avoid creating the config directory in a dry run.
+            let mut user_path = root.join("cosmic");
+            user_path.push(crate::sanitize_name(&config_bundle.name)?);
+            user_path.push(format!("v{}", config_bundle.version));
+            Config {
+                system_path: None,
+                user_path: Some(user_path),
+                mandatory_path: None,
+                format: crate::Format::default(),
+                memory: None,
+                history: None,
+            }
+        } else {
+            Config::with_custom_path(&config_bundle.name, config_bundle.version, root.to_owned())?
+        };
+
+        reports.push(config.import(config_bundle, options)?);
+    }
+
+    Ok(reports)
+}
+
+/// The versions of each config under `<root>/cosmic`, in ascending order.
+fn config_versions(root: &Path) -> Result<BTreeMap<String, Vec<u64>>, Error> {
+    let mut configs = BTreeMap::new();
+
+    let entries = match fs::read_dir(root.join("cosmic")) {
+        Ok(entries) => entries,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(configs),
+        Err(why) => return Err(why.into()),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        if !path.is_dir() {
+            continue;
+        }
+
+        let mut versions = Vec::new();
+        for entry in fs::read_dir(&path)? {
+            let path = entry?.path();
+            if path.is_dir()
+                && let Some(version) = version_of(&path)
+            {
+                versions.push(version);
+            }
+        }
+
+        if !versions.is_empty() {
+            versions.sort_unstable();
+            configs.insert(name.to_owned(), versions);
+        }
+    }
+
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigGet, ConfigSet};
+
+    #[test]
+    fn export_and_import() {
+        let source = tempfile::tempdir().unwrap();
+        let a = Config::with_custom_path("com.system76.A", 1, source.path().into()).unwrap();
+        a.set("size", 4_u32).unwrap();
+        a.set("name", "cosmic").unwrap();
+        let b = Config::with_custom_path("com.system76.B", 2, source.path().into()).unwrap();
+        b.set("dark", true).unwrap();
+
+        let bundle = export_all_from(source.path()).unwrap();
+        let bundle = Bundle::from_ron(&bundle.to_ron().unwrap()).unwrap();
+        assert_eq!(bundle.configs.len(), 2);
+        assert_eq!(bundle.configs[0].name, "com.system76.A");
+        assert_eq!(bundle.configs[1].version, 2);
+
+        let target = tempfile::tempdir().unwrap();
+        let reports = import_all_to(target.path(), &bundle, ImportOptions::default()).unwrap();
+        assert_eq!(reports[0].imported, ["name", "size"]);
+        assert_eq!(reports[1].imported, ["dark"]);
+
+        let a = Config::with_custom_path("com.system76.A", 1, target.path().into()).unwrap();
+        assert_eq!(a.get_local::<u32>("size").unwrap(), 4);
+    }
+
+    #[test]
+    fn conflicts_and_failures() {
+        let root = tempfile::tempdir().unwrap();
+        let config = Config::with_custom_path("com.system76.C", 1, root.path().into()).unwrap();
+        config.set("size", 4_u32).unwrap();
+        config.set("name", "cosmic").unwrap();
+
+        let mut bundle = config.export().unwrap();
+        bundle.keys.insert("size".into(), "8".into());
+        bundle.keys.insert("broken".into(), "(unclosed".into());
+        bundle.keys.insert("new".into(), "true".into());
+
+        let options = ImportOptions {
+            dry_run: true,
+            overwrite: true,
+        };
+        let report = config.import(&bundle, options).unwrap();
+        assert_eq!(report.conflicts, ["size"]);
+        assert_eq!(report.imported, ["new", "size"]);
+        assert_eq!(report.failed[0].0, "broken");
+        assert_eq!(config.get_local::<u32>("size").unwrap(), 4);
+
+        let report = config.import(&bundle, ImportOptions::default()).unwrap();
+        assert_eq!(report.imported, ["new"]);
+        assert_eq!(config.get_local::<u32>("size").unwrap(), 4);
+        assert!(config.get_local::<bool>("new").unwrap());
+
+        bundle.version = 2;
+        let report = config.import(&bundle, ImportOptions::default()).unwrap();
+        assert_eq!(report.version_mismatch, Some((2, 1)));
+        assert!(report.imported.is_empty());
+    }
+}
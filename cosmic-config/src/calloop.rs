@@ -6,7 +6,7 @@ use crate::{Config, Error};
 
 pub struct ConfigWatchSource {
     channel: channel::Channel<(Config, Vec<String>)>,
-    _watcher: notify::RecommendedWatcher,
+    _watcher: crate::ConfigWatcher,
 }
 
 impl ConfigWatchSource {
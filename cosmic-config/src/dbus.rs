@@ -101,6 +101,8 @@ pub fn watcher_subscription<T: CosmicConfigEntry + Send + Sync + Default + 'stat
                         unreachable!();
                     };
 
+                    crate::migration::migrate_entry_logged::<T>(&cosmic_config, config_id);
+
                     let mut attempts = 0;
 
                     loop {
@@ -0,0 +1,135 @@
+//! Serialization formats for the values of config keys.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::path::Path;
+
+use crate::Error;
+
+/// The format in which the value of a key is stored.
+///
+/// A [`Config`](crate::Config) stores keys in its own format, which defaults to RON.
+/// Keys with a `.ron`, `.json` or `.toml` extension are always stored in the
+/// format that their extension names.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Format {
+    #[default]
+    Ron,
+    /// Requires the `json` feature.
+    Json,
+    /// Requires the `toml` feature. TOML documents are tables, so only keys
+    /// whose values serialize to a struct or map can be stored.
+    Toml,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ron => "RON",
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+        })
+    }
+}
+
+impl Format {
+    /// The format named by a file extension, if any.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "ron" => Some(Self::Ron),
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// The file extension for this format.
+    #[inline]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ron => "ron",
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// The format of a key, which is named by its extension or otherwise `self`.
+    pub fn for_key(self, key: &str) -> Self {
+        Path::new(key)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(self)
+    }
+
+    /// Deserialize a value stored in this format.
+    pub fn from_str<T: DeserializeOwned>(self, data: &str) -> Result<T, Error> {
+        match self {
+            Self::Ron => Ok(ron::from_str(data)?),
+
+            #[cfg(feature = "json")]
+            Self::Json => Ok(serde_json::from_str(data)?),
+
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::from_str(data)?),
+
+            #[allow(unreachable_patterns)]
+            format => Err(Error::UnsupportedFormat(format)),
+        }
+    }
+
+    /// Serialize a value in this format.
+    pub fn to_string<T: Serialize>(self, value: &T) -> Result<String, Error> {
+        match self {
+            Self::Ron => Ok(ron::ser::to_string_pretty(
+                value,
+                ron::ser::PrettyConfig::new(),
+            )?),
+
+            #[cfg(feature = "json")]
+            Self::Json => Ok(serde_json::to_string_pretty(value)?),
+
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::to_string_pretty(value)?),
+
+            #[allow(unreachable_patterns)]
+            format => Err(Error::UnsupportedFormat(format)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_key() {
+        assert_eq!(Format::Ron.for_key("theme"), Format::Ron);
+        assert_eq!(Format::Json.for_key("theme"), Format::Json);
+        assert_eq!(Format::Ron.for_key("theme.json"), Format::Json);
+        assert_eq!(Format::Json.for_key("theme.toml"), Format::Toml);
+        assert_eq!(Format::Json.for_key("theme.ron"), Format::Ron);
+        assert_eq!(Format::Json.for_key("theme.txt"), Format::Json);
+    }
+
+    #[cfg(all(feature = "json", feature = "toml"))]
+    #[test]
+    fn round_trip() {
+        use std::collections::BTreeMap;
+
+        let value = BTreeMap::from([(String::from("size"), 4_u32)]);
+        for format in [Format::Ron, Format::Json, Format::Toml] {
+            let data = format.to_string(&value).unwrap();
+            assert_eq!(
+                format.from_str::<BTreeMap<String, u32>>(&data).unwrap(),
+                value
+            );
+        }
+
+        assert!(matches!(
+            Format::Json.from_str::<u32>("\"text\""),
+            Err(Error::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,342 @@
+//! A journal of previous config values, for reverting changes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
+use std::{fs, io};
+
+use crate::{Config, Error, now};
+
+/// How much history is kept for a config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HistoryLimit {
+    /// The number of transactions to keep.
+    pub max_transactions: Option<usize>,
+    /// How long a transaction is kept for.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for HistoryLimit {
+    fn default() -> Self {
+        Self {
+            max_transactions: Some(50),
+            max_age: None,
+        }
+    }
+}
+
+/// A committed transaction in the history of a config.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Transaction {
+    pub revision: u64,
+    /// Seconds since the Unix epoch when the transaction was committed.
+    pub time: u64,
+    pub changes: Vec<Change>,
+}
+
+/// A key changed by a transaction, with its serialized values.
+///
+/// A value of `None` means that the key was not defined by the user.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Change {
+    pub key: String,
+    pub previous: Option<String>,
+    pub value: Option<String>,
+}
+
+/// A change of a single key from [`Config::history`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyRevision {
+    pub revision: u64,
+    pub time: u64,
+    pub previous: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Journal {
+    next_revision: u64,
+    transactions: Vec<Transaction>,
+}
+
+/// Live journal locks by path, so that every config recording to a journal shares its lock.
+static LOCKS: LazyLock<Mutex<HashMap<PathBuf, Weak<Mutex<()>>>>> = LazyLock::new(Mutex::default);
+
+/// The history journal of a config, stored as a RON file.
+#[derive(Debug)]
+pub(crate) struct History {
+    path: PathBuf,
+    limit: HistoryLimit,
+    /// Serializes access to the journal file.
+    lock: Arc<Mutex<()>>,
+}
+
+impl History {
+    fn load(&self) -> Result<Journal, Error> {
+        match fs::read_to_string(&self.path) {
+            Ok(data) => Ok(ron::from_str(&data)?),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(Journal::default()),
+            Err(why) => Err(why.into()),
+        }
+    }
+
+    fn save(&self, journal: &Journal) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let data = ron::ser::to_string_pretty(journal, ron::ser::PrettyConfig::new())?;
+        atomicwrites::AtomicFile::new(&self.path, atomicwrites::OverwriteBehavior::AllowOverwrite)
+            .write(|file| file.write_all(data.as_bytes()))?;
+        Ok(())
+    }
+
+    /// Appends a committed transaction to the journal.
+    pub(crate) fn record(
+        &self,
+        updates: Vec<(String, Option<String>)>,
+        previous: Vec<Option<String>>,
+    ) -> Result<(), Error> {
+        let changes = updates
+            .into_iter()
+            .zip(previous)
+            .filter(|((_, value), previous)| value != previous)
+            .map(|((key, value), previous)| Change {
+                key,
+                previous,
+                value,
+            })
+            .collect::<Vec<_>>();
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let _lock = self.lock.lock().unwrap();
+        let mut journal = self.load()?;
+        let time = now();
+
+        journal.transactions.push(Transaction {
+            revision: journal.next_revision,
+            time,
+            changes,
+        });
+        journal.next_revision += 1;
+
+        if let Some(max_age) = self.limit.max_age {
+            let oldest = time.saturating_sub(max_age.as_secs());
+            journal
+                .transactions
+                .retain(|transaction| transaction.time >= oldest);
+        }
+
+        if let Some(max) = self.limit.max_transactions {
+            let excess = journal.transactions.len().saturating_sub(max);
+            journal.transactions.drain(..excess);
+        }
+
+        self.save(&journal)
+    }
+}
+
+impl Config {
+    /// Record the previous values of every committed transaction in the state directory.
+    pub fn with_history(self, limit: HistoryLimit) -> Result<Self, Error> {
+        let (name, version) = self.name_and_version().ok_or(Error::NoConfigDirectory)?;
+        let mut path = crate::get_state_dir().ok_or(Error::NoConfigDirectory)?;
+        path.push("cosmic-history");
+        path.push(crate::sanitize_name(&name)?);
+        path.push(format!("v{version}.ron"));
+        Ok(self.with_history_path(path, limit))
+    }
+
+    /// Record the previous values of every committed transaction in the given file.
+    #[must_use]
+    pub fn with_history_path(mut self, path: PathBuf, limit: HistoryLimit) -> Self {
+        let lock = {
+            let mut locks = LOCKS.lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(&path).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::default();
+                    locks.insert(path.clone(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+
+        self.history = Some(Arc::new(History { path, limit, lock }));
+        self
+    }
+
+    /// The recorded transactions, from oldest to newest.
+    pub fn transactions(&self) -> Result<Vec<Transaction>, Error> {
+        let history = self.history.as_ref().ok_or(Error::NotFound)?;
+        let _lock = history.lock.lock().unwrap();
+        Ok(history.load()?.transactions)
+    }
+
+    /// The recorded changes of a key, from oldest to newest.
+    pub fn history(&self, key: &str) -> Result<Vec<KeyRevision>, Error> {
+        Ok(self
+            .transactions()?
+            .into_iter()
+            .filter_map(|transaction| {
+                let change = transaction
+                    .changes
+                    .into_iter()
+                    .find(|change| change.key == key)?;
+                Some(KeyRevision {
+                    revision: transaction.revision,
+                    time: transaction.time,
+                    previous: change.previous,
+                    value: change.value,
+                })
+            })
+            .collect())
+    }
+
+    /// Reverts the most recent transaction, and removes it from the history.
+    ///
+    /// Returns the revision that was undone, if any.
+    pub fn undo_last_transaction(&self) -> Result<Option<u64>, Error> {
+        let history = self.history.as_ref().ok_or(Error::NotFound)?;
+
+        // The journal stays locked until the revert is written, so that a failed revert
+        // keeps its transaction, and no other transaction is recorded in between.
+        let _lock = history.lock.lock().unwrap();
+        let mut journal = history.load()?;
+        let Some(transaction) = journal.transactions.pop() else {
+            return Ok(None);
+        };
+
+        let mut tx = self.transaction();
+        tx.record = false;
+        for change in transaction.changes.iter().rev() {
+            match &change.previous {
+                Some(previous) => tx.set_raw(&change.key, previous.clone())?,
+                None => tx.remove(&change.key)?,
+            }
+        }
+        tx.commit()?;
+        history.save(&journal)?;
+
+        Ok(Some(transaction.revision))
+    }
+
+    /// Sets a key to the value it was given by a recorded revision.
+    ///
+    /// The restore is itself recorded as a new transaction.
+    pub fn restore(&self, key: &str, revision: u64) -> Result<(), Error> {
+        let change = self
+            .history(key)?
+            .into_iter()
+            .find(|change| change.revision == revision)
+            .ok_or(Error::NotFound)?;
+
+        let tx = self.transaction();
+        match change.value {
+            Some(value) => tx.set_raw(key, value)?,
+            None => tx.remove(key)?,
+        }
+        tx.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigGet, ConfigSet};
+
+    fn config(root: &std::path::Path, limit: HistoryLimit) -> Config {
+        Config::with_custom_path("com.system76.HistoryTest", 1, root.to_owned())
+            .unwrap()
+            .with_history_path(root.join("history.ron"), limit)
+    }
+
+    #[test]
+    fn undo_and_restore() {
+        let root = tempfile::tempdir().unwrap();
+        let config = config(root.path(), HistoryLimit::default());
+
+        config.set("size", 1_u32).unwrap();
+        config.set("size", 2_u32).unwrap();
+
+        let tx = config.transaction();
+        tx.set("size", 3_u32).unwrap();
+        tx.set("name", "cosmic").unwrap();
+        tx.commit().unwrap();
+
+        let history = config.history("size").unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].previous, None);
+        assert_eq!(history[2].value.as_deref(), Some("3"));
+
+        assert_eq!(config.undo_last_transaction().unwrap(), Some(2));
+        assert_eq!(config.get_local::<u32>("size").unwrap(), 2);
+        assert!(matches!(
+            config.get_local::<String>("name"),
+            Err(Error::NotFound)
+        ));
+
+        config.restore("size", 0).unwrap();
+        assert_eq!(config.get_local::<u32>("size").unwrap(), 1);
+        assert_eq!(config.history("size").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn limits() {
+        let root = tempfile::tempdir().unwrap();
+        let config = config(
+            root.path(),
+            HistoryLimit {
+                max_transactions: Some(2),
+                max_age: None,
+            },
+        );
+
+        for size in 0..5_u32 {
+            config.set("size", size).unwrap();
+        }
+
+        // Unchanged values are not recorded.
+        config.set("size", 4_u32).unwrap();
+
+        let revisions = config
+            .transactions()
+            .unwrap()
+            .iter()
+            .map(|transaction| transaction.revision)
+            .collect::<Vec<_>>();
+        assert_eq!(revisions, [3, 4]);
+    }
+
+    #[test]
+    fn shared_journal() {
+        let root = tempfile::tempdir().unwrap();
+        let first = config(root.path(), HistoryLimit::default());
+        let second = config(root.path(), HistoryLimit::default());
+
+        let lock = |config: &Config| config.history.as_ref().unwrap().lock.clone();
+        assert!(Arc::ptr_eq(&lock(&first), &lock(&second)));
+
+        first.set("size", 1_u32).unwrap();
+        second.set("size", 2_u32).unwrap();
+        assert_eq!(first.history("size").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn history_failure_keeps_commit() {
+        let root = tempfile::tempdir().unwrap();
+        // The journal cannot be written where a directory is.
+        fs::create_dir(root.path().join("history.ron")).unwrap();
+        let config = config(root.path(), HistoryLimit::default());
+
+        config.set("size", 1_u32).unwrap();
+        assert_eq!(config.get_local::<u32>("size").unwrap(), 1);
+    }
+}
@@ -1,13 +1,14 @@
 //! Integrations for cosmic-config — the cosmic configuration system.
 
+use notify::Watcher;
 use notify::event::{EventKind, ModifyKind, RenameMode};
-use notify::{RecommendedWatcher, Watcher};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use std::{env, fmt, fs};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fmt, fs, io};
 
 /// Get the config directory, with Flatpak sandbox support.
 /// In Flatpak, HOST_XDG_CONFIG_HOME points to the real user config directory,
@@ -74,6 +75,8 @@ pub use subscription::*;
 
 #[cfg(all(feature = "dbus", feature = "subscription"))]
 pub mod dbus;
+#[cfg(feature = "dbus")]
+pub mod settings_daemon;
 
 #[cfg(feature = "macro")]
 pub use cosmic_config_derive;
@@ -81,6 +84,26 @@ pub use cosmic_config_derive;
 #[cfg(feature = "calloop")]
 pub mod calloop;
 
+mod format;
+pub use format::Format;
+
+mod memory;
+
+pub mod bundle;
+
+pub mod history;
+pub use history::HistoryLimit;
+
+mod watch;
+pub use watch::{ConfigWatcher, KeyUpdate, KeyWatcher, WatchOptions};
+
+pub mod migration;
+pub use migration::{MigrationReport, Migrations};
+
+pub mod schema;
+
+pub mod validation;
+
 #[derive(Debug)]
 pub enum Error {
     AtomicWrites(atomicwrites::Error<std::io::Error>),
@@ -92,6 +115,15 @@ pub enum Error {
     Ron(ron::Error),
     RonSpanned(ron::error::SpannedError),
     GetKey(String, std::io::Error),
+    Invalid(String, String),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    #[cfg(feature = "toml")]
+    TomlDe(toml::de::Error),
+    #[cfg(feature = "toml")]
+    TomlSer(toml::ser::Error),
+    UnsupportedFormat(Format),
+    Locked(String),
 }
 
 impl fmt::Display for Error {
@@ -107,6 +139,17 @@ impl fmt::Display for Error {
             Self::Ron(err) => err.fmt(f),
             Self::RonSpanned(err) => err.fmt(f),
             Self::GetKey(key, err) => write!(f, "failed to get key '{}': {}", key, err),
+            Self::Invalid(key, why) => write!(f, "invalid value for key '{}': {}", key, why),
+            #[cfg(feature = "json")]
+            Self::Json(err) => write!(f, "JSON: {}", err),
+            #[cfg(feature = "toml")]
+            Self::TomlDe(err) => write!(f, "TOML: {}", err),
+            #[cfg(feature = "toml")]
+            Self::TomlSer(err) => write!(f, "TOML: {}", err),
+            Self::Locked(key) => write!(f, "config key '{}' is locked by the administrator", key),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "{} format support is not enabled", format)
+            }
         }
     }
 }
@@ -153,6 +196,27 @@ impl From<ron::error::SpannedError> for Error {
     }
 }
 
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(f: serde_json::Error) -> Self {
+        Self::Json(f)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for Error {
+    fn from(f: toml::de::Error) -> Self {
+        Self::TomlDe(f)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for Error {
+    fn from(f: toml::ser::Error) -> Self {
+        Self::TomlSer(f)
+    }
+}
+
 pub trait ConfigGet {
     /// Get a configuration value
     ///
@@ -175,6 +239,40 @@ pub trait ConfigSet {
 pub struct Config {
     system_path: Option<PathBuf>,
     user_path: Option<PathBuf>,
+    mandatory_path: Option<PathBuf>,
+    format: Format,
+    memory: Option<Arc<memory::Store>>,
+    history: Option<Arc<history::History>>,
+}
+
+/// Get the directory of administrator-enforced values for a config path.
+///
+/// Mandatory values are read from `cosmic/mandatory` in the first of the
+/// `XDG_CONFIG_DIRS` which has the config, such as `/etc/xdg`. The user's
+/// `XDG_CONFIG_HOME` is not searched, since the user may write to it.
+fn mandatory_path(path: &Path) -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        let path = Path::new("cosmic/mandatory").join(path);
+        xdg::BaseDirectories::new()
+            .get_config_dirs()
+            .into_iter()
+            .map(|dir| dir.join(&path))
+            .find(|dir| dir.is_dir())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
 }
 
 /// Check that the name is relative and doesn't contain . or ..
@@ -195,7 +293,7 @@ impl Config {
     pub fn system(name: &str, version: u64) -> Result<Self, Error> {
         let path = sanitize_name(name)?.join(format!("v{version}"));
         #[cfg(unix)]
-        let system_path = xdg::BaseDirectories::with_prefix("cosmic").find_data_file(path);
+        let system_path = xdg::BaseDirectories::with_prefix("cosmic").find_data_file(&path);
 
         #[cfg(windows)]
         let system_path =
@@ -203,8 +301,12 @@ impl Config {
                 .map(|x| x.join("COSMIC").join(&path));
 
         Ok(Self {
+            mandatory_path: mandatory_path(&path),
             system_path,
             user_path: None,
+            format: Format::default(),
+            memory: None,
+            history: None,
         })
     }
 
@@ -212,6 +314,11 @@ impl Config {
     // Use folder at XDG config/name for config storage, return Config if successful
     //TODO: fallbacks for flatpak (HOST_XDG_CONFIG_HOME, xdg-desktop settings proxy)
     pub fn new(name: &str, version: u64) -> Result<Self, Error> {
+        #[cfg(any(test, feature = "in-memory"))]
+        if let Some(config) = memory::lookup(name, version, false) {
+            return Ok(config);
+        }
+
         // Look for [name]/v[version]
         let path = sanitize_name(name)?.join(format!("v{}", version));
 
@@ -224,6 +331,9 @@ impl Config {
             known_folders::get_known_folder_path(known_folders::KnownFolder::ProgramFilesCommon)
                 .map(|x| x.join("COSMIC").join(&path));
 
+        // Values enforced by the administrator, which take precedence over the user's
+        let mandatory_path = mandatory_path(&path);
+
         // Get libcosmic user configuration directory
         let mut user_path = get_config_dir().ok_or(Error::NoConfigDirectory)?;
         user_path.push("cosmic");
@@ -236,6 +346,10 @@ impl Config {
         Ok(Self {
             system_path,
             user_path: Some(user_path),
+            mandatory_path,
+            format: Format::default(),
+            memory: None,
+            history: None,
         })
     }
 
@@ -254,6 +368,10 @@ impl Config {
         Ok(Self {
             system_path: None,
             user_path: Some(user_path),
+            mandatory_path: None,
+            format: Format::default(),
+            memory: None,
+            history: None,
         })
     }
 
@@ -263,6 +381,11 @@ impl Config {
     // Use folder at XDG config/name for config storage, return Config if successful
     //TODO: fallbacks for flatpak (HOST_XDG_CONFIG_HOME, xdg-desktop settings proxy)
     pub fn new_state(name: &str, version: u64) -> Result<Self, Error> {
+        #[cfg(any(test, feature = "in-memory"))]
+        if let Some(config) = memory::lookup(name, version, true) {
+            return Ok(config);
+        }
+
         // Look for [name]/v[version]
         let path = sanitize_name(name)?.join(format!("v{}", version));
 
@@ -276,6 +399,10 @@ impl Config {
         Ok(Self {
             system_path: None,
             user_path: Some(user_path),
+            mandatory_path: None,
+            format: Format::default(),
+            memory: None,
+            history: None,
         })
     }
 
@@ -294,15 +421,69 @@ impl Config {
         Ok(Self {
             system_path: None,
             user_path: Some(user_path),
+            mandatory_path: None,
+            format: Format::default(),
+            memory: None,
+            history: None,
         })
     }
 
+    /// Read administrator-enforced values from the given directory instead.
+    #[inline]
+    #[must_use]
+    pub fn with_mandatory_path(mut self, mandatory_path: PathBuf) -> Self {
+        self.mandatory_path = Some(mandatory_path);
+        self
+    }
+
+    /// Whether the key has a mandatory value set by the administrator, which
+    /// users are not permitted to override.
+    pub fn is_locked(&self, key: &str) -> bool {
+        self.mandatory_key_path(key)
+            .is_ok_and(|mandatory_path| mandatory_path.is_file())
+    }
+
+    /// Get the mandatory value of a key set by the administrator.
+    pub fn get_mandatory<T: DeserializeOwned>(&self, key: &str) -> Result<T, Error> {
+        match self.mandatory_key_path(key) {
+            Ok(mandatory_path) if mandatory_path.is_file() => {
+                let data = fs::read_to_string(mandatory_path)
+                    .map_err(|err| Error::GetKey(key.to_string(), err))?;
+
+                self.key_format(key).from_str(&data)
+            }
+
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    /// Store keys in the given format, except for keys with a format extension.
+    #[inline]
+    #[must_use]
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// The format that keys are stored in by default.
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// The format that the given key is stored in.
+    #[inline]
+    pub fn key_format(&self, key: &str) -> Format {
+        self.format.for_key(key)
+    }
+
     // Start a transaction (to set multiple configs at the same time)
     #[inline]
     pub fn transaction(&self) -> ConfigTransaction<'_> {
         ConfigTransaction {
             config: self,
             updates: Mutex::new(Vec::new()),
+            record: true,
         }
     }
 
@@ -310,17 +491,33 @@ impl Config {
     // This may end up being an mpsc channel instead of a function
     // See EventHandler in the notify crate: https://docs.rs/notify/latest/notify/trait.EventHandler.html
     // Having a callback allows for any application abstraction to be used
-    pub fn watch<F>(&self, f: F) -> Result<RecommendedWatcher, Error>
+    pub fn watch<F>(&self, f: F) -> Result<ConfigWatcher, Error>
     // Argument is an array of all keys that changed in that specific transaction
     //TODO: simplify F requirements
     where
         F: Fn(&Self, &[String]) + Send + Sync + 'static,
     {
+        self.watch_with_errors(f, |_| ())
+    }
+
+    /// Watch keys for changes, like [`Config::watch`], and pass any errors from
+    /// the file watcher to `on_error`.
+    pub fn watch_with_errors<F, E>(&self, f: F, on_error: E) -> Result<ConfigWatcher, Error>
+    where
+        F: Fn(&Self, &[String]) + Send + Sync + 'static,
+        E: Fn(Error) + Send + Sync + 'static,
+    {
+        if let Some(store) = self.memory.as_ref() {
+            return Ok(store.watch(Arc::new(f)));
+        }
+
         let watch_config = self.clone();
         let Some(user_path) = self.user_path.as_ref() else {
             return Err(Error::NoConfigDirectory);
         };
         let user_path_clone = user_path.clone();
+        let mandatory_path = self.mandatory_path.clone().filter(|path| path.is_dir());
+        let mandatory_path_clone = mandatory_path.clone();
         let mut watcher =
             notify::recommended_watcher(move |event_res: Result<notify::Event, notify::Error>| {
                 match event_res {
@@ -337,18 +534,25 @@ impl Config {
 
                         let mut keys = Vec::new();
                         for path in &event.paths {
-                            match path.strip_prefix(&user_path_clone) {
+                            let key_path = path.strip_prefix(&user_path_clone).or_else(|err| {
+                                mandatory_path_clone
+                                    .as_ref()
+                                    .map_or(Err(err), |mandatory| path.strip_prefix(mandatory))
+                            });
+                            match key_path {
                                 Ok(key_path) => {
                                     if let Some(key) = key_path.to_str() {
-                                        // Skip any .atomicwrite temporary files
-                                        if key.starts_with(".atomicwrite") {
+                                        // Skip any .atomicwrite temporary files and the migration marker
+                                        if key.starts_with(".atomicwrite")
+                                            || key == migration::MARKER
+                                        {
                                             continue;
                                         }
                                         keys.push(key.to_string());
                                     }
                                 }
                                 Err(_err) => {
-                                    //TODO: handle errors
+                                    on_error(Error::InvalidName(path.display().to_string()));
                                 }
                             }
                         }
@@ -356,13 +560,17 @@ impl Config {
                             f(&watch_config, &keys);
                         }
                     }
-                    Err(_err) => {
-                        //TODO: handle errors
+                    Err(err) => {
+                        on_error(Error::Notify(err));
                     }
                 }
             })?;
         watcher.watch(user_path, notify::RecursiveMode::Recursive)?;
-        Ok(watcher)
+        // Keys are locked and unlocked by the administrator as their mandatory values change
+        if let Some(mandatory_path) = mandatory_path.as_ref() {
+            watcher.watch(mandatory_path, notify::RecursiveMode::Recursive)?;
+        }
+        Ok(ConfigWatcher::notify(watcher))
     }
 
     fn default_path(&self, key: &str) -> Result<PathBuf, Error> {
@@ -373,6 +581,27 @@ impl Config {
         Ok(system_path.join(sanitize_name(key)?))
     }
 
+    fn mandatory_key_path(&self, key: &str) -> Result<PathBuf, Error> {
+        let Some(mandatory_path) = self.mandatory_path.as_ref() else {
+            return Err(Error::NotFound);
+        };
+
+        Ok(mandatory_path.join(sanitize_name(key)?))
+    }
+
+    /// The serialized value of a key in the user's config.
+    pub(crate) fn get_raw(&self, key: &str) -> Result<String, Error> {
+        if let Some(store) = self.memory.as_ref() {
+            return store.get(key).ok_or(Error::NotFound);
+        }
+
+        match fs::read_to_string(self.key_path(key)?) {
+            Ok(data) => Ok(data),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Err(Error::NotFound),
+            Err(why) => Err(why.into()),
+        }
+    }
+
     /// Get the path of the key in the user's local config directory.
     fn key_path(&self, key: &str) -> Result<PathBuf, Error> {
         let Some(user_path) = self.user_path.as_ref() else {
@@ -386,6 +615,11 @@ impl Config {
 impl ConfigGet for Config {
     //TODO: check for transaction
     fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T, Error> {
+        match self.get_mandatory(key) {
+            Err(Error::NotFound) => (),
+            result => return result,
+        }
+
         match self.get_local(key) {
             Ok(value) => Ok(value),
             Err(Error::NotFound) => self.get_system_default(key),
@@ -394,6 +628,11 @@ impl ConfigGet for Config {
     }
 
     fn get_local<T: DeserializeOwned>(&self, key: &str) -> Result<T, Error> {
+        if let Some(store) = self.memory.as_ref() {
+            let data = store.get(key).ok_or(Error::NotFound)?;
+            return self.key_format(key).from_str(&data);
+        }
+
         // If key path exists
         match self.key_path(key) {
             Ok(key_path) if key_path.is_file() => {
@@ -401,7 +640,7 @@ impl ConfigGet for Config {
                 let data = fs::read_to_string(key_path)
                     .map_err(|err| Error::GetKey(key.to_string(), err))?;
 
-                Ok(ron::from_str(&data)?)
+                self.key_format(key).from_str(&data)
             }
 
             _ => Err(Error::NotFound),
@@ -413,7 +652,7 @@ impl ConfigGet for Config {
         let default_path = self.default_path(key)?;
         let data =
             fs::read_to_string(default_path).map_err(|err| Error::GetKey(key.to_string(), err))?;
-        Ok(ron::from_str(&data)?)
+        self.key_format(key).from_str(&data)
     }
 }
 
@@ -431,47 +670,129 @@ impl ConfigSet for Config {
 pub struct ConfigTransaction<'a> {
     config: &'a Config,
     //TODO: use map?
-    updates: Mutex<Vec<(PathBuf, String)>>,
+    /// Keys with their new serialized value, or `None` if the key is removed.
+    updates: Mutex<Vec<(String, Option<String>)>>,
+    /// Whether the transaction is recorded in the config's history.
+    record: bool,
 }
 
 impl ConfigTransaction<'_> {
     /// Apply all pending changes from ConfigTransaction
     //TODO: apply all changes at once
     pub fn commit(self) -> Result<(), Error> {
-        let mut updates = self.updates.lock().unwrap();
-        for (key_path, data) in updates.drain(..) {
-            atomicwrites::AtomicFile::new(
-                key_path,
-                atomicwrites::OverwriteBehavior::AllowOverwrite,
-            )
-            .write(|file| file.write_all(data.as_bytes()))?;
+        let updates = std::mem::take(&mut *self.updates.lock().unwrap());
+
+        // Values being replaced are read before they are overwritten
+        let history = self.config.history.as_ref().filter(|_| self.record);
+        let previous = history.map(|_| {
+            updates
+                .iter()
+                .map(|(key, _)| self.config.get_raw(key).ok())
+                .collect::<Vec<_>>()
+        });
+
+        if let Some(store) = self.config.memory.as_ref() {
+            store.commit(self.config, &updates);
+        } else {
+            for (key, data) in &updates {
+                let key_path = self.config.key_path(key)?;
+                let Some(data) = data else {
+                    match fs::remove_file(key_path) {
+                        Err(why) if why.kind() != io::ErrorKind::NotFound => return Err(why.into()),
+                        _ => continue,
+                    }
+                };
+                // Nested keys are stored in subdirectories
+                if let Some(parent) = key_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                atomicwrites::AtomicFile::new(
+                    key_path,
+                    atomicwrites::OverwriteBehavior::AllowOverwrite,
+                )
+                .write(|file| file.write_all(data.as_bytes()))?;
+            }
+        }
+
+        // The values are already written, so a journal that cannot be updated only loses
+        // the history of this transaction.
+        if let (Some(history), Some(previous)) = (history, previous)
+            && let Err(why) = history.record(updates, previous)
+        {
+            tracing::error!("failed to record config history: {why}");
         }
+
         Ok(())
     }
-}
 
-// Setting any setting in this way will do one transaction for all settings
-// when commit finishes that transaction
-impl ConfigSet for ConfigTransaction<'_> {
-    fn set<T: Serialize>(&self, key: &str, value: T) -> Result<(), Error> {
+    /// Queue an already serialized value for the key.
+    pub(crate) fn set_raw(&self, key: &str, data: String) -> Result<(), Error> {
         //TODO: sanitize key (no slashes, cannot be . or ..)
-        let key_path = self.config.key_path(key)?;
-        let data = ron::ser::to_string_pretty(&value, ron::ser::PrettyConfig::new())?;
+        if self.config.is_locked(key) {
+            return Err(Error::Locked(key.to_owned()));
+        }
+        if self.config.memory.is_some() {
+            sanitize_name(key)?;
+        } else {
+            self.config.key_path(key)?;
+        }
         //TODO: replace duplicates?
         {
             let mut updates = self.updates.lock().unwrap();
-            updates.push((key_path, data));
+            updates.push((key.to_owned(), Some(data)));
+        }
+        Ok(())
+    }
+
+    /// Queue the removal of a key from the user's config.
+    pub(crate) fn remove(&self, key: &str) -> Result<(), Error> {
+        if self.config.is_locked(key) {
+            return Err(Error::Locked(key.to_owned()));
         }
+        sanitize_name(key)?;
+        self.updates.lock().unwrap().push((key.to_owned(), None));
         Ok(())
     }
 }
 
+// Setting any setting in this way will do one transaction for all settings
+// when commit finishes that transaction
+impl ConfigSet for ConfigTransaction<'_> {
+    fn set<T: Serialize>(&self, key: &str, value: T) -> Result<(), Error> {
+        let data = self.config.key_format(key).to_string(&value)?;
+        self.set_raw(key, data)
+    }
+}
+
 pub trait CosmicConfigEntry
 where
     Self: Sized,
 {
     const VERSION: u64;
 
+    /// Upgrade steps from earlier versions of this config.
+    #[inline]
+    fn migrations() -> Migrations {
+        Migrations::new()
+    }
+
+    /// Runs the [`CosmicConfigEntry::migrations`] of this config, if it has not been migrated yet.
+    fn migrate_entry(config: &Config) -> Result<Option<MigrationReport>, crate::Error> {
+        let migrations = Self::migrations();
+        if migrations.is_empty() {
+            return Ok(None);
+        }
+        config.migrate(&migrations)
+    }
+
+    /// A description of each key, for generating settings pages.
+    fn schema() -> schema::Schema {
+        schema::Schema {
+            version: Self::VERSION,
+            keys: Vec::new(),
+        }
+    }
+
     fn write_entry(&self, config: &Config) -> Result<(), crate::Error>;
     fn get_entry(config: &Config) -> Result<Self, (Vec<crate::Error>, Self)>;
     /// Returns the keys that were updated
@@ -488,3 +809,59 @@ pub struct Update<T> {
     pub keys: Vec<&'static str>,
     pub config: T,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mandatory_values() {
+        let root = tempfile::tempdir().unwrap();
+        let mandatory = root.path().join("mandatory");
+        fs::create_dir_all(&mandatory).unwrap();
+        fs::write(mandatory.join("locked"), "true").unwrap();
+
+        let config = Config::with_custom_path("com.system76.MandatoryTest", 1, root.path().into())
+            .unwrap()
+            .with_mandatory_path(mandatory);
+
+        assert!(config.is_locked("locked"));
+        assert!(!config.is_locked("unlocked"));
+
+        assert!(matches!(config.set("locked", false), Err(Error::Locked(_))));
+        assert!(config.get::<bool>("locked").unwrap());
+
+        config.set("unlocked", 1_u32).unwrap();
+        assert_eq!(config.get::<u32>("unlocked").unwrap(), 1);
+
+        let tx = config.transaction();
+        assert!(matches!(tx.set("locked", false), Err(Error::Locked(_))));
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn watch_mandatory_values() {
+        let root = tempfile::tempdir().unwrap();
+        let mandatory = root.path().join("mandatory");
+        fs::create_dir_all(&mandatory).unwrap();
+
+        let config = Config::with_custom_path("com.system76.MandatoryWatch", 1, root.path().into())
+            .unwrap()
+            .with_mandatory_path(mandatory.clone());
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        let _watcher = config
+            .watch(move |_config, keys| {
+                let _ = sender.lock().unwrap().send(keys.to_vec());
+            })
+            .unwrap();
+
+        fs::write(mandatory.join("locked"), "true").unwrap();
+        let keys = receiver
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(keys, ["locked"]);
+        assert!(config.is_locked("locked"));
+    }
+}
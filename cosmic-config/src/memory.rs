@@ -0,0 +1,248 @@
+//! An in-memory config backend for testing, which never touches the filesystem.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+
+use crate::{Config, ConfigWatcher, Error, Format, sanitize_name};
+
+type Callback = Arc<dyn Fn(&Config, &[String]) + Send + Sync>;
+
+/// Stores by name, version, and whether they hold state rather than config.
+type Stores = HashMap<(String, u64, bool), Weak<Store>>;
+
+/// Live in-memory stores, so that configs created by name share their values.
+static STORES: LazyLock<Mutex<Stores>> = LazyLock::new(Mutex::default);
+
+/// Values and watchers shared by every clone of an in-memory [`Config`].
+pub(crate) struct Store {
+    pub(crate) name: String,
+    pub(crate) version: u64,
+    values: Mutex<BTreeMap<String, String>>,
+    /// Each callback is dropped once the watcher that owns its token is dropped.
+    callbacks: Mutex<Vec<(Weak<()>, Callback)>>,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .field("values", &self.values)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Store {
+    fn new(name: String, version: u64) -> Self {
+        Self {
+            name,
+            version,
+            values: Mutex::default(),
+            callbacks: Mutex::default(),
+        }
+    }
+
+    /// A copy of every key and its serialized value.
+    pub(crate) fn entries(&self) -> BTreeMap<String, String> {
+        self.values.lock().unwrap().clone()
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    /// Applies the updates of a transaction, then notifies every watcher before returning.
+    pub(crate) fn commit(&self, config: &Config, updates: &[(String, Option<String>)]) {
+        if updates.is_empty() {
+            return;
+        }
+
+        let mut keys = Vec::with_capacity(updates.len());
+        {
+            let mut values = self.values.lock().unwrap();
+            for (key, data) in updates {
+                match data {
+                    Some(data) => values.insert(key.clone(), data.clone()),
+                    None => values.remove(key),
+                };
+                keys.push(key.clone());
+            }
+        }
+
+        let callbacks = {
+            let mut callbacks = self.callbacks.lock().unwrap();
+            callbacks.retain(|(token, _)| token.strong_count() > 0);
+            callbacks
+                .iter()
+                .map(|(_, callback)| callback.clone())
+                .collect::<Vec<_>>()
+        };
+
+        for callback in callbacks {
+            callback(config, &keys);
+        }
+    }
+
+    /// Registers a callback which lives for as long as the returned watcher.
+    pub(crate) fn watch(&self, callback: Callback) -> ConfigWatcher {
+        let token = Arc::new(());
+        self.callbacks
+            .lock()
+            .unwrap()
+            .push((Arc::downgrade(&token), callback));
+        ConfigWatcher::memory(token)
+    }
+}
+
+/// Finds the store of a live in-memory config, or of live in-memory state.
+#[cfg(any(test, feature = "in-memory"))]
+pub(crate) fn lookup(name: &str, version: u64, state: bool) -> Option<Config> {
+    let stores = STORES.lock().unwrap();
+    let store = stores.get(&(name.to_owned(), version, state))?.upgrade()?;
+    Some(Config::from_store(store))
+}
+
+impl Config {
+    /// Get an in-memory config for the given name and config version, for use in tests.
+    ///
+    /// Values are shared by every in-memory config of the same name and version
+    /// for as long as any of them are alive. With the `in-memory` feature, and in
+    /// the tests of this crate, [`Config::new`] returns that config instead of
+    /// reading from disk. Watch callbacks are called before a transaction's commit
+    /// returns.
+    pub fn in_memory(name: &str, version: u64) -> Result<Self, Error> {
+        Self::memory_store(name, version, false)
+    }
+
+    /// Get in-memory state for the given name and config version, for use in tests.
+    ///
+    /// This is kept apart from the [`Config::in_memory`] config of the same name,
+    /// and is what [`Config::new_state`] returns with the `in-memory` feature.
+    pub fn in_memory_state(name: &str, version: u64) -> Result<Self, Error> {
+        Self::memory_store(name, version, true)
+    }
+
+    fn memory_store(name: &str, version: u64, state: bool) -> Result<Self, Error> {
+        sanitize_name(name)?;
+
+        let mut stores = STORES.lock().unwrap();
+        stores.retain(|_, store| store.strong_count() > 0);
+
+        let key = (name.to_owned(), version, state);
+        let store = match stores.get(&key).and_then(Weak::upgrade) {
+            Some(store) => store,
+            None => {
+                let store = Arc::new(Store::new(key.0.clone(), version));
+                stores.insert(key, Arc::downgrade(&store));
+                store
+            }
+        };
+
+        Ok(Self::from_store(store))
+    }
+
+    /// Whether this config is held in memory.
+    #[inline]
+    pub fn is_in_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    fn from_store(store: Arc<Store>) -> Self {
+        Self {
+            system_path: None,
+            user_path: None,
+            mandatory_path: None,
+            format: Format::default(),
+            memory: Some(store),
+            history: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigGet, ConfigSet};
+
+    #[test]
+    fn get_and_set() {
+        let config = Config::in_memory("com.system76.MemoryTest.GetSet", 1).unwrap();
+        assert!(matches!(
+            config.get_local::<u32>("size"),
+            Err(Error::NotFound)
+        ));
+
+        config.set("size", 4_u32).unwrap();
+        assert_eq!(config.get::<u32>("size").unwrap(), 4);
+
+        let tx = config.transaction();
+        tx.set("size", 8_u32).unwrap();
+        tx.set("name", "cosmic").unwrap();
+        assert_eq!(config.get::<u32>("size").unwrap(), 4);
+        tx.commit().unwrap();
+
+        assert_eq!(config.get::<u32>("size").unwrap(), 8);
+        assert_eq!(config.get::<String>("name").unwrap(), "cosmic");
+    }
+
+    #[test]
+    fn shared_by_name() {
+        let config = Config::in_memory("com.system76.MemoryTest.Shared", 1).unwrap();
+        config.set("size", 4_u32).unwrap();
+
+        let other = Config::new("com.system76.MemoryTest.Shared", 1).unwrap();
+        assert!(other.is_in_memory());
+        assert_eq!(other.get::<u32>("size").unwrap(), 4);
+
+        let version = Config::in_memory("com.system76.MemoryTest.Shared", 2).unwrap();
+        assert!(matches!(
+            version.get_local::<u32>("size"),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn state_apart_from_config() {
+        let config = Config::in_memory("com.system76.MemoryTest.State", 1).unwrap();
+        config.set("size", 4_u32).unwrap();
+        let state = Config::in_memory_state("com.system76.MemoryTest.State", 1).unwrap();
+        state.set("size", 8_u32).unwrap();
+
+        let other = Config::new("com.system76.MemoryTest.State", 1).unwrap();
+        assert_eq!(other.get::<u32>("size").unwrap(), 4);
+        let other = Config::new_state("com.system76.MemoryTest.State", 1).unwrap();
+        assert!(other.is_in_memory());
+        assert_eq!(other.get::<u32>("size").unwrap(), 8);
+    }
+
+    #[test]
+    fn watch_is_synchronous() {
+        let config = Config::in_memory("com.system76.MemoryTest.Watch", 1).unwrap();
+        let changed = Arc::new(Mutex::new(Vec::new()));
+
+        let watcher = config
+            .watch({
+                let changed = changed.clone();
+                move |config, keys| {
+                    for key in keys {
+                        let value = config.get::<u32>(key).unwrap();
+                        changed.lock().unwrap().push((key.clone(), value));
+                    }
+                }
+            })
+            .unwrap();
+
+        let tx = config.transaction();
+        tx.set("a", 1_u32).unwrap();
+        tx.set("b", 2_u32).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            *changed.lock().unwrap(),
+            [(String::from("a"), 1), (String::from("b"), 2)]
+        );
+
+        drop(watcher);
+    }
+}
@@ -0,0 +1,450 @@
+//! Carry config values forward when the version of a config is bumped.
+//!
+//! Each config version is stored in its own `v{version}` directory, so a new
+//! version starts out empty. A [`Migrations`] set registers upgrade steps from
+//! one version to the next, which [`Config::migrate`] chains together to bring
+//! the keys of the newest older version into the current one.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+use crate::{Config, Error, Format};
+
+/// Marker file written to a version directory once it has been migrated into.
+pub(crate) const MARKER: &str = ".migrated";
+
+type StepFn = Box<dyn Fn(&mut MigrationStep<'_>) + Send + Sync>;
+
+/// Upgrade steps for a config, keyed by the version they upgrade from.
+#[derive(Default)]
+pub struct Migrations {
+    steps: BTreeMap<u64, StepFn>,
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("steps", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Migrations {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the step which upgrades keys from `from` to `from + 1`.
+    ///
+    /// Keys that the step does not touch are carried over unchanged.
+    #[must_use]
+    pub fn step<F>(mut self, from: u64, step: F) -> Self
+    where
+        F: Fn(&mut MigrationStep<'_>) + Send + Sync + 'static,
+    {
+        self.steps.insert(from, Box::new(step));
+        self
+    }
+
+    /// Whether no steps have been registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// The keys of a config while it is being upgraded by a single step.
+///
+/// Values are held in the serialized form of the config's [`Format`] until the
+/// final version is written.
+pub struct MigrationStep<'a> {
+    from: u64,
+    format: Format,
+    keys: &'a mut BTreeMap<String, String>,
+    report: &'a mut MigrationReport,
+}
+
+impl MigrationStep<'_> {
+    /// The version that this step upgrades from.
+    #[inline]
+    pub fn from_version(&self) -> u64 {
+        self.from
+    }
+
+    /// Whether the key is present.
+    #[inline]
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// The names of all keys present in this step.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// Get the value of a key.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T, Error> {
+        let data = self.keys.get(key).ok_or(Error::NotFound)?;
+        self.format.for_key(key).from_str(data)
+    }
+
+    /// Set the value of a key, adding it if it does not exist.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let data = self.format.for_key(key).to_string(&value)?;
+        self.keys.insert(key.to_owned(), data);
+        Ok(())
+    }
+
+    /// Moves the value of `from` to `to`, replacing any existing value of `to`.
+    ///
+    /// Returns `false` if `from` was not present.
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let Some(data) = self.keys.remove(from) else {
+            return false;
+        };
+        self.keys.insert(to.to_owned(), data);
+        true
+    }
+
+    /// Drops a key so that it is not carried into the new version.
+    ///
+    /// Returns `false` if the key was not present.
+    pub fn remove(&mut self, key: &str) -> bool {
+        if self.keys.remove(key).is_some() {
+            self.report.dropped.push(key.to_owned());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replaces the value of a key with the output of `func`.
+    ///
+    /// If the existing value cannot be read as `T`, or the new value cannot be
+    /// serialized, the key is removed and recorded as failed.
+    pub fn transform<T, U, F>(&mut self, key: &str, func: F)
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+        F: FnOnce(T) -> U,
+    {
+        let result = self
+            .get::<T>(key)
+            .and_then(|value| self.format.for_key(key).to_string(&func(value)));
+
+        match result {
+            Ok(data) => {
+                self.keys.insert(key.to_owned(), data);
+            }
+            Err(Error::NotFound) => (),
+            Err(why) => {
+                self.keys.remove(key);
+                self.report.failed.push((key.to_owned(), why));
+            }
+        }
+    }
+}
+
+/// The outcome of a migration.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// The version that keys were read from.
+    pub from: u64,
+    /// The version that keys were written to.
+    pub to: u64,
+    /// Keys written to the new version.
+    pub migrated: Vec<String>,
+    /// Keys that a step removed.
+    pub dropped: Vec<String>,
+    /// Keys that were not carried over because the new version already defined them.
+    pub skipped: Vec<String>,
+    /// Keys that could not be transformed or written.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl Config {
+    /// Upgrades keys from the newest older version of this config which the
+    /// registered steps can reach, and writes them into this version.
+    ///
+    /// A migration only runs once per version, unless a key failed to migrate, in
+    /// which case it runs again the next time. Returns `Ok(None)` if this version
+    /// has already been migrated, or no older version was found.
+    pub fn migrate(&self, migrations: &Migrations) -> Result<Option<MigrationReport>, Error> {
+        // In-memory configs have no older versions to migrate from.
+        if self.is_in_memory() {
+            return Ok(None);
+        }
+
+        let Some(user_path) = self.user_path.as_ref() else {
+            return Err(Error::NoConfigDirectory);
+        };
+
+        let marker = user_path.join(MARKER);
+        if marker.exists() {
+            return Ok(None);
+        }
+
+        let Some(to) = version_of(user_path) else {
+            return Ok(None);
+        };
+
+        // Walk back through consecutive steps until an existing version is found.
+        let mut from = to;
+        let source = loop {
+            let Some(prev) = from.checked_sub(1) else {
+                return Ok(None);
+            };
+
+            if !migrations.steps.contains_key(&prev) {
+                return Ok(None);
+            }
+
+            from = prev;
+            let path = user_path.with_file_name(format!("v{from}"));
+            if path.is_dir() {
+                break path;
+            }
+        };
+
+        let mut keys = BTreeMap::new();
+        read_keys(&source, &source, &mut keys)?;
+
+        let mut report = MigrationReport {
+            from,
+            to,
+            ..MigrationReport::default()
+        };
+
+        for (&version, step) in migrations.steps.range(from..to) {
+            step(&mut MigrationStep {
+                from: version,
+                format: self.format,
+                keys: &mut keys,
+                report: &mut report,
+            });
+        }
+
+        for (key, data) in keys {
+            let key_path = match self.key_path(&key) {
+                Ok(key_path) => key_path,
+                Err(why) => {
+                    report.failed.push((key, why));
+                    continue;
+                }
+            };
+
+            if key_path.exists() {
+                report.skipped.push(key);
+                continue;
+            }
+
+            if let Some(parent) = key_path.parent()
+                && let Err(why) = fs::create_dir_all(parent)
+            {
+                report.failed.push((key, why.into()));
+                continue;
+            }
+
+            match atomicwrites::AtomicFile::new(
+                key_path,
+                atomicwrites::OverwriteBehavior::AllowOverwrite,
+            )
+            .write(|file| file.write_all(data.as_bytes()))
+            {
+                Ok(()) => report.migrated.push(key),
+                Err(why) => report.failed.push((key, why.into())),
+            }
+        }
+
+        if report.failed.is_empty() {
+            fs::write(marker, from.to_string())?;
+        }
+
+        Ok(Some(report))
+    }
+}
+
+/// Runs the migrations of a config entry, logging any that fail.
+#[cfg(feature = "subscription")]
+pub(crate) fn migrate_entry_logged<T: crate::CosmicConfigEntry>(config: &Config, config_id: &str) {
+    match T::migrate_entry(config) {
+        Ok(Some(report)) => {
+            for (key, why) in &report.failed {
+                tracing::error!("failed to migrate {config_id} key {key}: {why}");
+            }
+        }
+        Ok(None) => (),
+        Err(why) => tracing::error!("failed to migrate {config_id}: {why}"),
+    }
+}
+
+/// Parses the version from a `v{version}` directory.
+pub(crate) fn version_of(path: &Path) -> Option<u64> {
+    path.file_name()?.to_str()?.strip_prefix('v')?.parse().ok()
+}
+
+/// Collects the serialized value of every key below `dir`.
+pub(crate) fn read_keys(
+    root: &Path,
+    dir: &Path,
+    keys: &mut BTreeMap<String, String>,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+
+        let Ok(key) = path.strip_prefix(root) else {
+            continue;
+        };
+
+        let Some(key) = key.to_str() else {
+            continue;
+        };
+
+        // Skip the migration marker and any .atomicwrite temporary files
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'))
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            read_keys(root, &path, keys)?;
+        } else {
+            keys.insert(key.to_owned(), fs::read_to_string(&path)?);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigGet, ConfigSet};
+
+    const NAME: &str = "com.system76.MigrationTest";
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .step(1, |step| {
+                step.rename("dark", "is_dark");
+                step.transform("size", |size: u32| size * 2);
+            })
+            .step(2, |step| {
+                step.remove("legacy");
+                step.transform("is_dark", |is_dark: bool| {
+                    String::from(if is_dark { "dark" } else { "light" })
+                });
+            })
+    }
+
+    #[test]
+    fn chained_upgrade() {
+        let root = tempfile::tempdir().unwrap();
+
+        let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+        v1.set("dark", true).unwrap();
+        v1.set("size", 4_u32).unwrap();
+        v1.set("legacy", "unused").unwrap();
+        v1.set("name", "cosmic").unwrap();
+
+        let v3 = Config::with_custom_path(NAME, 3, root.path().to_owned()).unwrap();
+        let report = v3.migrate(&migrations()).unwrap().unwrap();
+
+        assert_eq!(report.from, 1);
+        assert_eq!(report.to, 3);
+        assert_eq!(report.migrated, ["is_dark", "name", "size"]);
+        assert_eq!(report.dropped, ["legacy"]);
+        assert!(report.failed.is_empty());
+
+        assert_eq!(v3.get::<String>("is_dark").unwrap(), "dark");
+        assert_eq!(v3.get::<u32>("size").unwrap(), 8);
+        assert_eq!(v3.get::<String>("name").unwrap(), "cosmic");
+        assert!(matches!(
+            v3.get_local::<String>("legacy"),
+            Err(Error::NotFound)
+        ));
+
+        // Intermediate versions are not written.
+        assert!(!root.path().join("cosmic").join(NAME).join("v2").exists());
+    }
+
+    #[test]
+    fn prefers_newest_version() {
+        let root = tempfile::tempdir().unwrap();
+
+        let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+        v1.set("dark", false).unwrap();
+
+        let v2 = Config::with_custom_path(NAME, 2, root.path().to_owned()).unwrap();
+        v2.set("is_dark", true).unwrap();
+
+        let v3 = Config::with_custom_path(NAME, 3, root.path().to_owned()).unwrap();
+        let report = v3.migrate(&migrations()).unwrap().unwrap();
+
+        assert_eq!(report.from, 2);
+        assert_eq!(v3.get::<String>("is_dark").unwrap(), "dark");
+    }
+
+    #[test]
+    fn runs_once() {
+        let root = tempfile::tempdir().unwrap();
+
+        let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+        v1.set("size", 1_u32).unwrap();
+
+        let v2 = Config::with_custom_path(NAME, 2, root.path().to_owned()).unwrap();
+        assert!(v2.migrate(&migrations()).unwrap().is_some());
+        assert_eq!(v2.get::<u32>("size").unwrap(), 2);
+
+        v1.set("size", 10_u32).unwrap();
+        assert!(v2.migrate(&migrations()).unwrap().is_none());
+        assert_eq!(v2.get::<u32>("size").unwrap(), 2);
+    }
+
+    #[test]
+    fn failed_and_existing_keys() {
+        let root = tempfile::tempdir().unwrap();
+
+        let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+        v1.set("size", "not a number").unwrap();
+        v1.set("name", "old").unwrap();
+
+        let v2 = Config::with_custom_path(NAME, 2, root.path().to_owned()).unwrap();
+        v2.set("name", "new").unwrap();
+
+        let report = v2.migrate(&migrations()).unwrap().unwrap();
+        assert!(report.migrated.is_empty());
+        assert!(report.dropped.is_empty());
+        assert_eq!(report.skipped, ["name"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "size");
+        assert_eq!(v2.get::<String>("name").unwrap(), "new");
+
+        // The migration runs again until no key fails.
+        v1.set("size", 1_u32).unwrap();
+        let report = v2.migrate(&migrations()).unwrap().unwrap();
+        assert_eq!(report.migrated, ["size"]);
+        assert!(report.failed.is_empty());
+        assert!(v2.migrate(&migrations()).unwrap().is_none());
+    }
+
+    #[test]
+    fn no_reachable_version() {
+        let root = tempfile::tempdir().unwrap();
+
+        let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+        v1.set("size", 1_u32).unwrap();
+
+        let v3 = Config::with_custom_path(NAME, 3, root.path().to_owned()).unwrap();
+        let only_second = Migrations::new().step(2, |_| ());
+        assert!(v3.migrate(&only_second).unwrap().is_none());
+    }
+}
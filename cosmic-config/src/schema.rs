@@ -0,0 +1,559 @@
+//! Runtime descriptions of the keys of a [`CosmicConfigEntry`](crate::CosmicConfigEntry),
+//! from which settings pages can be generated.
+
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{Config, ConfigGet, ConfigSet, ConfigTransaction, Error, validation};
+
+/// The keys of a config entry, generated by `#[derive(CosmicConfigEntry)]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    pub version: u64,
+    pub keys: Vec<Key>,
+}
+
+impl Schema {
+    /// The key with the given name.
+    pub fn key(&self, name: &str) -> Option<&Key> {
+        self.keys.iter().find(|key| key.name == name)
+    }
+
+    /// Check a value against its key, and write it to the config in a transaction.
+    pub fn set(&self, config: &Config, name: &str, value: &Value) -> Result<(), Error> {
+        let key = self.key(name).ok_or(Error::NotFound)?;
+        let tx = config.transaction();
+        tx.set_value(key, value)?;
+        tx.commit()
+    }
+}
+
+/// The shape of a key's type, as seen by serde.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Bool,
+    /// An integer, with the bounds of its type as far as they fit an `i64`.
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    /// A float, with the bounds of its type.
+    Float {
+        min: f64,
+        max: f64,
+    },
+    Text,
+    /// An enum, with the names of its variants. Only unit variants can be
+    /// read and written as a [`Value`].
+    Enum {
+        name: &'static str,
+        variants: &'static [&'static str],
+    },
+    Optional(Box<Shape>),
+    List,
+    Map,
+    /// A struct, with the names of its fields.
+    Struct {
+        name: &'static str,
+        fields: &'static [&'static str],
+    },
+    /// A type which does not describe itself, such as `ron::Value`.
+    Unknown,
+}
+
+impl Shape {
+    /// The shape of `T`, found by inspecting how it deserializes.
+    pub fn of<T: DeserializeOwned>() -> Self {
+        let mut shape = Shape::Unknown;
+        let _ = T::deserialize(Probe(&mut shape));
+        shape
+    }
+}
+
+/// A key of a config entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Key {
+    pub name: &'static str,
+    /// The doc comment of the field.
+    pub doc: Option<&'static str>,
+    pub shape: Shape,
+    /// The default value, if its shape is supported by [`Value`].
+    pub default: Option<Value>,
+    /// Bounds from `#[validate(range(..))]`.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Allowed values from `#[validate(one_of(..))]`.
+    pub options: Vec<Value>,
+    /// Pattern from `#[validate(pattern = "..")]`.
+    pub pattern: Option<&'static str>,
+    /// Set by `#[validate(non_empty)]`.
+    pub non_empty: bool,
+}
+
+impl Key {
+    pub fn new<T: Serialize + DeserializeOwned>(name: &'static str, default: &T) -> Self {
+        let shape = Shape::of::<T>();
+        Self {
+            name,
+            doc: None,
+            default: Value::from_serialize(&shape, default),
+            shape,
+            min: None,
+            max: None,
+            options: Vec::new(),
+            pattern: None,
+            non_empty: false,
+        }
+    }
+
+    #[must_use]
+    pub fn doc(mut self, doc: &'static str) -> Self {
+        self.doc = Some(doc);
+        self
+    }
+
+    #[must_use]
+    pub fn range<T: Serialize>(mut self, min: Option<T>, max: Option<T>) -> Self {
+        self.min = min.as_ref().and_then(number);
+        self.max = max.as_ref().and_then(number);
+        self
+    }
+
+    #[must_use]
+    pub fn options<T: Serialize>(mut self, options: &[T]) -> Self {
+        self.options = options
+            .iter()
+            .filter_map(|option| Value::from_serialize(&self.shape, option))
+            .collect();
+        self
+    }
+
+    #[must_use]
+    pub fn pattern(mut self, pattern: &'static str) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    #[must_use]
+    pub fn non_empty(mut self) -> Self {
+        self.non_empty = true;
+        self
+    }
+
+    /// The first line of the doc comment, or else the name of the key.
+    pub fn title(&self) -> Cow<'static, str> {
+        match self.doc.and_then(|doc| doc.lines().next()) {
+            Some(title) if !title.is_empty() => Cow::Borrowed(title.trim_end_matches('.')),
+            _ => Cow::Owned(self.name.replace('_', " ")),
+        }
+    }
+
+    /// The doc comment after its first line, if any.
+    pub fn description(&self) -> Option<&'static str> {
+        let (_, description) = self.doc?.split_once('\n')?;
+        let description = description.trim();
+        (!description.is_empty()).then_some(description)
+    }
+
+    /// Whether the value fits the shape of the key and passes its checks.
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        match (&self.shape, value) {
+            (Shape::Bool, Value::Bool(_)) | (Shape::Text, Value::Text(_)) => (),
+            (Shape::Integer { min, max }, Value::Integer(value)) => {
+                validation::range(value, Some(*min), Some(*max))?;
+            }
+            (Shape::Float { min, max }, Value::Float(value)) => {
+                validation::range(value, Some(*min), Some(*max))?;
+            }
+            (Shape::Enum { variants, .. }, Value::Variant(variant)) => {
+                validation::one_of(&variant.as_str(), variants)?;
+            }
+            (shape, value) => return Err(format!("{value:?} does not fit {shape:?}")),
+        }
+
+        if let Some(number) = value.as_f64() {
+            validation::range(&number, self.min, self.max)?;
+        }
+
+        if !self.options.is_empty() {
+            validation::one_of(value, &self.options)?;
+        }
+
+        if let Value::Text(text) = value {
+            if let Some(pattern) = self.pattern {
+                validation::pattern(text, pattern)?;
+            }
+            if self.non_empty {
+                validation::non_empty(text)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The value of a key whose [`Shape`] is a scalar or a unit enum.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    /// The name of an enum variant.
+    Variant(String),
+}
+
+impl Value {
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Integer(value) => Some(value as f64),
+            Self::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn from_serialize<T: Serialize>(shape: &Shape, value: &T) -> Option<Self> {
+        let data = ron::to_string(value).ok()?;
+        Some(match shape {
+            Shape::Bool => Self::Bool(ron::from_str(&data).ok()?),
+            Shape::Integer { .. } => Self::Integer(ron::from_str(&data).ok()?),
+            Shape::Float { .. } => Self::Float(ron::from_str(&data).ok()?),
+            Shape::Text => Self::Text(ron::from_str(&data).ok()?),
+            Shape::Enum { .. } => Self::Variant(ron::from_str::<VariantName>(&data).ok()?.0),
+            _ => return None,
+        })
+    }
+}
+
+impl Config {
+    /// Get the value of a key described by a schema.
+    pub fn get_value(&self, key: &Key) -> Result<Value, Error> {
+        Ok(match key.shape {
+            Shape::Bool => Value::Bool(self.get(key.name)?),
+            Shape::Integer { .. } => Value::Integer(self.get(key.name)?),
+            Shape::Float { .. } => Value::Float(self.get(key.name)?),
+            Shape::Text => Value::Text(self.get(key.name)?),
+            Shape::Enum { .. } => Value::Variant(self.get::<VariantName>(key.name)?.0),
+            ref shape => {
+                return Err(Error::Invalid(
+                    key.name.to_owned(),
+                    format!("{shape:?} has no value"),
+                ));
+            }
+        })
+    }
+}
+
+impl ConfigTransaction<'_> {
+    /// Check a value against its key, and set it.
+    pub fn set_value(&self, key: &Key, value: &Value) -> Result<(), Error> {
+        key.check(value)
+            .map_err(|why| Error::Invalid(key.name.to_owned(), why))?;
+
+        match (value, &key.shape) {
+            (Value::Bool(value), _) => self.set(key.name, value),
+            (Value::Integer(value), _) => self.set(key.name, value),
+            (Value::Float(value), _) => self.set(key.name, value),
+            (Value::Text(value), _) => self.set(key.name, value),
+            (Value::Variant(variant), Shape::Enum { name, variants }) => {
+                let index = variants
+                    .iter()
+                    .position(|name| name == variant)
+                    .unwrap_or_default();
+                self.set(
+                    key.name,
+                    UnitVariant {
+                        name,
+                        index: index as u32,
+                        variant: variants[index],
+                    },
+                )
+            }
+            (Value::Variant(_), _) => unreachable!("checked by Key::check"),
+        }
+    }
+}
+
+/// Serializes as a unit variant of an enum.
+struct UnitVariant {
+    name: &'static str,
+    index: u32,
+    variant: &'static str,
+}
+
+impl Serialize for UnitVariant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_variant(self.name, self.index, self.variant)
+    }
+}
+
+/// Deserializes the name of a unit variant of any enum.
+struct VariantName(String);
+
+impl<'de> Deserialize<'de> for VariantName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_enum("", &[], VariantVisitor)
+    }
+}
+
+struct VariantVisitor;
+
+impl<'de> Visitor<'de> for VariantVisitor {
+    type Value = VariantName;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a unit variant")
+    }
+
+    fn visit_str<E: de::Error>(self, variant: &str) -> Result<Self::Value, E> {
+        Ok(VariantName(variant.to_owned()))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (variant, access) = data.variant_seed(Identifier)?;
+        de::VariantAccess::unit_variant(access)?;
+        Ok(variant)
+    }
+}
+
+/// Deserializes the name of a variant as an identifier, as derived enums do.
+struct Identifier;
+
+impl<'de> de::DeserializeSeed<'de> for Identifier {
+    type Value = VariantName;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<VariantName, D::Error> {
+        deserializer.deserialize_identifier(VariantVisitor)
+    }
+}
+
+/// The value of a serializable number.
+fn number<T: Serialize>(value: &T) -> Option<f64> {
+    ron::to_string(value).ok()?.parse().ok()
+}
+
+/// A deserializer which records the shape that a type asks for, and then fails.
+struct Probe<'a>(&'a mut Shape);
+
+#[derive(Debug)]
+struct Stop;
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("shape recorded")
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl de::Error for Stop {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Stop
+    }
+}
+
+macro_rules! probe {
+    ($($method:ident => $shape:expr,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Stop> {
+            *self.0 = $shape;
+            Err(Stop)
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Probe<'_> {
+    type Error = Stop;
+
+    probe! {
+        deserialize_any => Shape::Unknown,
+        deserialize_bool => Shape::Bool,
+        deserialize_i8 => Shape::Integer { min: i8::MIN.into(), max: i8::MAX.into() },
+        deserialize_i16 => Shape::Integer { min: i16::MIN.into(), max: i16::MAX.into() },
+        deserialize_i32 => Shape::Integer { min: i32::MIN.into(), max: i32::MAX.into() },
+        deserialize_i64 => Shape::Integer { min: i64::MIN, max: i64::MAX },
+        deserialize_i128 => Shape::Integer { min: i64::MIN, max: i64::MAX },
+        deserialize_u8 => Shape::Integer { min: 0, max: u8::MAX.into() },
+        deserialize_u16 => Shape::Integer { min: 0, max: u16::MAX.into() },
+        deserialize_u32 => Shape::Integer { min: 0, max: u32::MAX.into() },
+        deserialize_u64 => Shape::Integer { min: 0, max: i64::MAX },
+        deserialize_u128 => Shape::Integer { min: 0, max: i64::MAX },
+        deserialize_f32 => Shape::Float { min: f32::MIN.into(), max: f32::MAX.into() },
+        deserialize_f64 => Shape::Float { min: f64::MIN, max: f64::MAX },
+        deserialize_char => Shape::Text,
+        deserialize_str => Shape::Text,
+        deserialize_string => Shape::Text,
+        deserialize_bytes => Shape::List,
+        deserialize_byte_buf => Shape::List,
+        deserialize_unit => Shape::Unknown,
+        deserialize_seq => Shape::List,
+        deserialize_map => Shape::Map,
+        deserialize_identifier => Shape::Text,
+        deserialize_ignored_any => Shape::Unknown,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        let mut inner = Shape::Unknown;
+        let _ = visitor.visit_some(Probe(&mut inner));
+        *self.0 = Shape::Optional(Box::new(inner));
+        Err(Stop)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        *self.0 = Shape::Unknown;
+        Err(Stop)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        *self.0 = Shape::List;
+        Err(Stop)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        *self.0 = Shape::List;
+        Err(Stop)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        *self.0 = Shape::Struct { name, fields };
+        Err(Stop)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        *self.0 = Shape::Enum { name, variants };
+        Err(Stop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+    enum Mode {
+        #[default]
+        Light,
+        Dark,
+    }
+
+    #[test]
+    fn shapes() {
+        assert_eq!(Shape::of::<bool>(), Shape::Bool);
+        assert_eq!(Shape::of::<u8>(), Shape::Integer { min: 0, max: 255 });
+        assert_eq!(
+            Shape::of::<i64>(),
+            Shape::Integer {
+                min: i64::MIN,
+                max: i64::MAX
+            }
+        );
+        assert_eq!(
+            Shape::of::<f32>(),
+            Shape::Float {
+                min: f32::MIN.into(),
+                max: f32::MAX.into()
+            }
+        );
+        assert_eq!(Shape::of::<String>(), Shape::Text);
+        assert_eq!(Shape::of::<Vec<u32>>(), Shape::List);
+        assert_eq!(Shape::of::<BTreeMap<String, u32>>(), Shape::Map);
+        assert_eq!(
+            Shape::of::<Option<bool>>(),
+            Shape::Optional(Box::new(Shape::Bool))
+        );
+        assert_eq!(
+            Shape::of::<Mode>(),
+            Shape::Enum {
+                name: "Mode",
+                variants: &["Light", "Dark"]
+            }
+        );
+    }
+
+    #[test]
+    fn keys() {
+        let key = Key::new("size", &4_u32)
+            .doc("Size of icons.\n\nIn logical pixels.")
+            .range(Some(1_u32), Some(8));
+        assert_eq!(key.title(), "Size of icons");
+        assert_eq!(key.description(), Some("In logical pixels."));
+        assert_eq!(key.default, Some(Value::Integer(4)));
+        assert!(key.check(&Value::Integer(8)).is_ok());
+        assert!(key.check(&Value::Integer(9)).is_err());
+        assert!(key.check(&Value::Float(2.0)).is_err());
+
+        // Without a range, the bounds of the type still apply.
+        let key = Key::new("count", &0_u8);
+        assert_eq!((key.min, key.max), (None, None));
+        assert!(key.check(&Value::Integer(255)).is_ok());
+        assert!(key.check(&Value::Integer(256)).is_err());
+        assert!(key.check(&Value::Integer(-1)).is_err());
+
+        let key = Key::new("mode", &Mode::Light);
+        assert_eq!(key.title(), "mode");
+        assert_eq!(key.default, Some(Value::Variant("Light".into())));
+        assert!(key.check(&Value::Variant("Dim".into())).is_err());
+
+        let key = Key::new("accent", &String::from("blue")).options(&["blue", "red"]);
+        assert_eq!(
+            key.options,
+            [Value::Text("blue".into()), Value::Text("red".into())]
+        );
+        assert!(key.check(&Value::Text("green".into())).is_err());
+    }
+
+    #[test]
+    fn get_and_set_values() {
+        let config = Config::in_memory("com.system76.SchemaTest", 1).unwrap();
+        let schema = Schema {
+            version: 1,
+            keys: vec![Key::new("mode", &Mode::Light), Key::new("size", &4_u32)],
+        };
+
+        schema
+            .set(&config, "mode", &Value::Variant("Dark".into()))
+            .unwrap();
+        schema.set(&config, "size", &Value::Integer(6)).unwrap();
+        assert!(schema.set(&config, "size", &Value::Bool(true)).is_err());
+
+        assert_eq!(config.get::<Mode>("mode").unwrap(), Mode::Dark);
+        assert_eq!(config.get::<u32>("size").unwrap(), 6);
+        assert_eq!(
+            config.get_value(&schema.keys[0]).unwrap(),
+            Value::Variant("Dark".into())
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! A reference implementation of the `com.system76.CosmicSettingsDaemon` D-Bus service.
+//!
+//! It serves the same watch requests as `cosmic-settings-daemon`, backed by
+//! [`Config::watch`], so that the D-Bus watcher path can be tested on a private
+//! bus and embedded by headless tools.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use iced_futures::futures::StreamExt;
+use iced_futures::futures::channel::mpsc;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::{Config, ConfigWatcher};
+
+/// The well-known name of the settings daemon.
+pub const NAME: &str = "com.system76.CosmicSettingsDaemon";
+
+/// The object path of the settings daemon.
+pub const PATH: &str = "/com/system76/CosmicSettingsDaemon";
+
+/// A change of a key, to be emitted from the object of its config.
+struct Changed {
+    path: OwnedObjectPath,
+    id: String,
+    key: String,
+}
+
+/// Serves watch requests for configs, emitting a `Changed` signal from a
+/// `com.system76.CosmicSettingsDaemon.Config` object for each changed key.
+pub struct SettingsDaemon {
+    /// Directory holding `cosmic` configs in place of the user's config and state directories.
+    custom_path: Option<PathBuf>,
+    changes: mpsc::UnboundedSender<Changed>,
+    receiver: Option<mpsc::UnboundedReceiver<Changed>>,
+    watchers: HashMap<OwnedObjectPath, ConfigWatcher>,
+}
+
+impl Default for SettingsDaemon {
+    fn default() -> Self {
+        let (changes, receiver) = mpsc::unbounded();
+        Self {
+            custom_path: None,
+            changes,
+            receiver: Some(receiver),
+            watchers: HashMap::new(),
+        }
+    }
+}
+
+impl SettingsDaemon {
+    /// Serve the configs of the user's config and state directories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve configs and state from `<path>/cosmic`, as [`Config::with_custom_path`] does.
+    pub fn with_custom_path(path: PathBuf) -> Self {
+        Self {
+            custom_path: Some(path),
+            ..Self::default()
+        }
+    }
+
+    /// Register the daemon on the connection, then emit signals for changed keys.
+    ///
+    /// The daemon keeps serving for as long as it is registered, so this only returns
+    /// if it could not be registered, and should be spawned as a task of its own.
+    /// The caller should also request [`NAME`] on the connection, unless clients
+    /// address the daemon by its unique name, or the connection is peer-to-peer.
+    pub async fn serve(mut self, connection: zbus::Connection) -> zbus::Result<()> {
+        let Some(mut receiver) = self.receiver.take() else {
+            return Err(zbus::Error::Failure(String::from(
+                "settings daemon is already served",
+            )));
+        };
+        connection.object_server().at(PATH, self).await?;
+
+        while let Some(Changed { path, id, key }) = receiver.next().await {
+            let emitter = SignalEmitter::from_parts(connection.clone(), path.into());
+            if let Err(why) = ConfigObject::changed(&emitter, &id, &key).await {
+                tracing::error!(?why, "failed to emit change of {id} {key}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serve the user's configs on the session bus, under [`NAME`].
+    pub async fn serve_session() -> zbus::Result<()> {
+        let connection = zbus::Connection::session().await?;
+        connection.request_name(NAME).await?;
+        Self::new().serve(connection).await
+    }
+
+    async fn watch(
+        &mut self,
+        connection: &zbus::Connection,
+        id: &str,
+        version: u64,
+        is_state: bool,
+    ) -> zbus::fdo::Result<(OwnedObjectPath, String)> {
+        let path = object_path(id, version, is_state)?;
+        let name = connection
+            .unique_name()
+            .map_or_else(|| NAME.to_owned(), ToString::to_string);
+
+        if self.watchers.contains_key(&path) {
+            return Ok((path, name));
+        }
+
+        let config = match (&self.custom_path, is_state) {
+            (Some(custom_path), _) => Config::with_custom_path(id, version, custom_path.clone()),
+            (None, false) => Config::new(id, version),
+            (None, true) => Config::new_state(id, version),
+        }
+        .map_err(|why| zbus::fdo::Error::Failed(why.to_string()))?;
+
+        connection
+            .object_server()
+            .at(path.clone(), ConfigObject)
+            .await?;
+
+        let changes = self.changes.clone();
+        let watcher = {
+            let path = path.clone();
+            let id = id.to_owned();
+            config.watch(move |_config, keys| {
+                for key in keys {
+                    let _ = changes.unbounded_send(Changed {
+                        path: path.clone(),
+                        id: id.clone(),
+                        key: key.clone(),
+                    });
+                }
+            })
+        }
+        .map_err(|why| zbus::fdo::Error::Failed(why.to_string()))?;
+
+        self.watchers.insert(path.clone(), watcher);
+        Ok((path, name))
+    }
+}
+
+#[zbus::interface(name = "com.system76.CosmicSettingsDaemon")]
+impl SettingsDaemon {
+    async fn watch_config(
+        &mut self,
+        id: &str,
+        version: u64,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<(OwnedObjectPath, String)> {
+        self.watch(connection, id, version, false).await
+    }
+
+    async fn watch_state(
+        &mut self,
+        id: &str,
+        version: u64,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<(OwnedObjectPath, String)> {
+        self.watch(connection, id, version, true).await
+    }
+}
+
+/// The object of a watched config, which emits its changes.
+struct ConfigObject;
+
+#[zbus::interface(name = "com.system76.CosmicSettingsDaemon.Config")]
+impl ConfigObject {
+    #[zbus(signal)]
+    async fn changed(emitter: &SignalEmitter<'_>, id: &str, key: &str) -> zbus::Result<()>;
+}
+
+/// The object path of a config, such as `/com/system76/CosmicSettingsDaemon/Config/com/system76/CosmicTk/V1`.
+fn object_path(id: &str, version: u64, is_state: bool) -> zbus::fdo::Result<OwnedObjectPath> {
+    let kind = if is_state { "State" } else { "Config" };
+    let id = id
+        .split('.')
+        .map(|element| {
+            element
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    OwnedObjectPath::try_from(format!("{PATH}/{kind}/{id}/V{version}"))
+        .map_err(|why| zbus::fdo::Error::InvalidArgs(why.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbus::Watcher;
+    use crate::{ConfigGet, ConfigSet, CosmicConfigEntry, Update};
+    use cosmic_settings_daemon::CosmicSettingsDaemonProxy;
+    use iced_futures::futures::{Stream, future};
+    use std::time::Duration;
+
+    /// Serves the daemon on one end of a socket pair, and connects to it on the other,
+    /// so that no bus is needed.
+    async fn connect(daemon: SettingsDaemon) -> zbus::Connection {
+        let (server, client) = tokio::net::UnixStream::pair().unwrap();
+        let (server, client) = future::try_join(
+            zbus::connection::Builder::unix_stream(server)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .build(),
+            zbus::connection::Builder::unix_stream(client).p2p().build(),
+        )
+        .await
+        .unwrap();
+
+        tokio::spawn(daemon.serve(server));
+        client
+    }
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+        tokio::time::timeout(Duration::from_secs(10), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Entry {
+        size: u32,
+    }
+
+    impl CosmicConfigEntry for Entry {
+        const VERSION: u64 = 1;
+
+        fn write_entry(&self, config: &Config) -> Result<(), crate::Error> {
+            config.set("size", self.size)
+        }
+
+        fn get_entry(config: &Config) -> Result<Self, (Vec<crate::Error>, Self)> {
+            match config.get("size") {
+                Ok(size) => Ok(Self { size }),
+                Err(why) => Err((vec![why], Self::default())),
+            }
+        }
+
+        fn update_keys<T: AsRef<str>>(
+            &mut self,
+            config: &Config,
+            changed_keys: &[T],
+        ) -> (Vec<crate::Error>, Vec<&'static str>) {
+            match changed_keys.iter().any(|key| key.as_ref() == "size") {
+                true => match config.get("size") {
+                    Ok(size) => {
+                        self.size = size;
+                        (Vec::new(), vec!["size"])
+                    }
+                    Err(why) => (vec![why], Vec::new()),
+                },
+                false => (Vec::new(), Vec::new()),
+            }
+        }
+    }
+
+    #[test]
+    fn watch_config() {
+        let root = tempfile::tempdir().unwrap();
+        let config =
+            Config::with_custom_path("com.system76.DaemonTest", 1, root.path().into()).unwrap();
+
+        runtime().block_on(async {
+            let client = connect(SettingsDaemon::with_custom_path(root.path().into())).await;
+            let proxy = CosmicSettingsDaemonProxy::new(&client).await.unwrap();
+            let watcher = Watcher::new_config(&proxy, "com.system76.DaemonTest", 1)
+                .await
+                .unwrap();
+            let mut changes = watcher.receive_changed().await.unwrap();
+
+            config.set("size", 4_u32).unwrap();
+
+            let change = next(&mut changes).await;
+            let args = change.args().unwrap();
+            assert_eq!(args.id, "com.system76.DaemonTest");
+            assert_eq!(args.key, "size");
+        });
+    }
+
+    #[test]
+    fn watcher_subscription() {
+        // The daemon and the subscription share this config through `Config::new`.
+        let config = Config::in_memory("com.system76.DaemonSubscriptionTest", 1).unwrap();
+        config.set("size", 1_u32).unwrap();
+
+        runtime().block_on(async {
+            let client = connect(SettingsDaemon::new()).await;
+            let proxy = CosmicSettingsDaemonProxy::new(&client).await.unwrap();
+            let subscription = crate::dbus::watcher_subscription::<Entry>(
+                proxy,
+                "com.system76.DaemonSubscriptionTest",
+                false,
+            );
+
+            let mut updates = iced_futures::subscription::into_recipes(subscription)
+                .pop()
+                .unwrap()
+                .stream(iced_futures::futures::stream::pending().boxed());
+
+            assert_eq!(next(&mut updates).await.config.size, 1);
+
+            config.set("size", 2_u32).unwrap();
+            let update: Update<Entry> = next(&mut updates).await;
+            assert_eq!(update.keys, ["size"]);
+            assert_eq!(update.config.size, 2);
+            assert_eq!(config.get::<u32>("size").unwrap(), 2);
+        });
+    }
+}
@@ -1,15 +1,15 @@
 use iced_futures::futures::channel::mpsc;
 use iced_futures::futures::{SinkExt, Stream};
 use iced_futures::stream;
-use notify::RecommendedWatcher;
 use std::borrow::Cow;
 use std::hash::Hash;
 
-use crate::{Config, CosmicConfigEntry};
+use crate::{Config, ConfigWatcher, CosmicConfigEntry, KeyUpdate, WatchOptions};
+use serde::de::DeserializeOwned;
 
 pub enum ConfigState<T> {
     Init(Cow<'static, str>, u64, bool),
-    Waiting(T, RecommendedWatcher, mpsc::Receiver<Vec<String>>, Config),
+    Waiting(T, ConfigWatcher, mpsc::Receiver<Vec<String>>, Config),
     Failed,
 }
 
@@ -75,6 +75,69 @@ pub fn config_state_subscription<
     )
 }
 
+/// Watch a set of keys of a config, yielding their values as `T` when they change.
+///
+/// The current value of each key is yielded first, and bursts of changes are
+/// coalesced according to `options`. Errors from the file watcher are yielded
+/// as [`KeyUpdate::Watcher`](crate::KeyUpdate::Watcher).
+#[cold]
+pub fn keys_subscription<I: 'static + Hash, T: 'static + Send + DeserializeOwned>(
+    id: I,
+    config_id: Cow<'static, str>,
+    config_version: u64,
+    is_state: bool,
+    keys: Vec<Cow<'static, str>>,
+    options: WatchOptions,
+) -> iced_futures::Subscription<KeyUpdate<T>> {
+    iced_futures::Subscription::run_with(
+        (id, config_id, config_version, is_state, keys, options),
+        |(_, config_id, config_version, is_state, keys, options)| {
+            let config_id = config_id.clone();
+            let config_version = *config_version;
+            let is_state = *is_state;
+            let keys = keys.clone();
+            let options = *options;
+
+            stream::channel(
+                100,
+                move |mut output: mpsc::Sender<KeyUpdate<T>>| async move {
+                    use iced_futures::futures::StreamExt;
+                    use iced_futures::futures::future::pending;
+
+                    let (tx, mut rx) = mpsc::unbounded();
+
+                    let config = if is_state {
+                        Config::new_state(&config_id, config_version)
+                    } else {
+                        Config::new(&config_id, config_version)
+                    };
+
+                    let keys = keys.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+                    let watcher = config.and_then(|config| {
+                        config.watch_keys(&keys, options, move |update| {
+                            let _ = tx.unbounded_send(update);
+                        })
+                    });
+
+                    let _watcher = match watcher {
+                        Ok(watcher) => watcher,
+                        Err(why) => {
+                            _ = output.send(KeyUpdate::Watcher(why)).await;
+                            return pending().await;
+                        }
+                    };
+
+                    while let Some(update) = rx.next().await {
+                        if output.send(update).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+            )
+        },
+    )
+}
+
 async fn start_listening<T: 'static + Send + Sync + PartialEq + Clone + CosmicConfigEntry>(
     state: ConfigState<T>,
     output: &mut mpsc::Sender<crate::Update<T>>,
@@ -92,6 +155,7 @@ async fn start_listening<T: 'static + Send + Sync + PartialEq + Clone + CosmicCo
             }) else {
                 return ConfigState::Failed;
             };
+            crate::migration::migrate_entry_logged::<T>(&config, &config_id);
             let Ok(watcher) = config.watch(move |_helper, keys| {
                 let mut tx = tx.clone();
                 let _ = tx.try_send(keys.to_vec());
@@ -140,3 +204,77 @@ async fn start_listening<T: 'static + Send + Sync + PartialEq + Clone + CosmicCo
         ConfigState::Failed => pending().await,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigGet, ConfigSet};
+    use iced_futures::futures::StreamExt;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct Entry {
+        size: u32,
+    }
+
+    impl CosmicConfigEntry for Entry {
+        const VERSION: u64 = 1;
+
+        fn write_entry(&self, config: &Config) -> Result<(), crate::Error> {
+            config.set("size", self.size)
+        }
+
+        fn get_entry(config: &Config) -> Result<Self, (Vec<crate::Error>, Self)> {
+            match config.get("size") {
+                Ok(size) => Ok(Self { size }),
+                Err(why) => Err((vec![why], Self::default())),
+            }
+        }
+
+        fn update_keys<T: AsRef<str>>(
+            &mut self,
+            config: &Config,
+            changed_keys: &[T],
+        ) -> (Vec<crate::Error>, Vec<&'static str>) {
+            match changed_keys.iter().any(|key| key.as_ref() == "size") {
+                true => match config.get("size") {
+                    Ok(size) => {
+                        self.size = size;
+                        (Vec::new(), vec!["size"])
+                    }
+                    Err(why) => (vec![why], Vec::new()),
+                },
+                false => (Vec::new(), Vec::new()),
+            }
+        }
+    }
+
+    #[test]
+    fn listen_in_memory() {
+        const NAME: &str = "com.system76.SubscriptionTest";
+        let config = Config::in_memory(NAME, 1).unwrap();
+        config.set("size", 4_u32).unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (mut output, mut updates) = mpsc::channel(10);
+
+            // The subscription finds the in-memory config by name.
+            let state = ConfigState::Init(Cow::Borrowed(NAME), 1, false);
+            let state = start_listening::<Entry>(state, &mut output).await;
+            let update = updates.next().await.unwrap();
+            assert!(update.keys.is_empty());
+            assert_eq!(update.config, Entry { size: 4 });
+
+            config.set("size", 8_u32).unwrap();
+            let state = start_listening::<Entry>(state, &mut output).await;
+            let update = updates.next().await.unwrap();
+            assert!(update.errors.is_empty());
+            assert_eq!(update.keys, ["size"]);
+            assert_eq!(update.config, Entry { size: 8 });
+
+            assert!(matches!(state, ConfigState::Waiting(..)));
+        });
+    }
+}
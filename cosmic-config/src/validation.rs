@@ -0,0 +1,168 @@
+//! Checks used by the `#[validate(...)]` field attributes of the [`CosmicConfigEntry`](crate::CosmicConfigEntry) derive.
+//!
+//! Each check returns a description of the problem when the value is rejected,
+//! which the derive reports as an [`Error::Invalid`](crate::Error::Invalid) for the key.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::{LazyLock, Mutex};
+
+/// Patterns compiled by [`pattern`], which is called on every read of a key.
+static PATTERNS: LazyLock<Mutex<HashMap<String, regex::Regex>>> = LazyLock::new(Default::default);
+
+/// The value must lie within the inclusive `min` and `max` bounds, where given.
+pub fn range<T: PartialOrd + Debug>(
+    value: &T,
+    min: Option<T>,
+    max: Option<T>,
+) -> Result<(), String> {
+    if let Some(min) = min
+        && *value < min
+    {
+        return Err(format!("{value:?} is less than the minimum of {min:?}"));
+    }
+
+    if let Some(max) = max
+        && *value > max
+    {
+        return Err(format!("{value:?} is greater than the maximum of {max:?}"));
+    }
+
+    Ok(())
+}
+
+/// The value must equal one of the `allowed` values.
+pub fn one_of<T, U>(value: &T, allowed: &[U]) -> Result<(), String>
+where
+    T: PartialEq<U> + Debug,
+    U: Debug,
+{
+    if allowed.iter().any(|allowed| value == allowed) {
+        Ok(())
+    } else {
+        Err(format!("{value:?} is not one of {allowed:?}"))
+    }
+}
+
+/// The value must match the regular expression `pattern`.
+pub fn pattern<T: AsRef<str> + ?Sized>(value: &T, pattern: &str) -> Result<(), String> {
+    let regex = {
+        let mut patterns = PATTERNS.lock().unwrap();
+        match patterns.get(pattern) {
+            Some(regex) => regex.clone(),
+            None => {
+                let regex =
+                    regex::Regex::new(pattern).map_err(|why| format!("invalid pattern: {why}"))?;
+                patterns.insert(pattern.to_owned(), regex.clone());
+                regex
+            }
+        }
+    };
+    let value = value.as_ref();
+
+    if regex.is_match(value) {
+        Ok(())
+    } else {
+        Err(format!("{value:?} does not match the pattern {pattern:?}"))
+    }
+}
+
+/// The value must not be empty.
+pub fn non_empty<T: IsEmpty + ?Sized>(value: &T) -> Result<(), String> {
+    if value.is_empty() {
+        Err(String::from("value must not be empty"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Values which can be checked by [`non_empty`].
+pub trait IsEmpty {
+    fn is_empty(&self) -> bool;
+}
+
+impl IsEmpty for str {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        str::is_empty(self)
+    }
+}
+
+impl IsEmpty for String {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        String::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for [T] {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        <[T]>::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for Vec<T> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for Option<T> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl<K, V> IsEmpty for BTreeMap<K, V> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        BTreeMap::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for BTreeSet<T> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        BTreeSet::is_empty(self)
+    }
+}
+
+impl<K, V, S> IsEmpty for HashMap<K, V, S> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        HashMap::is_empty(self)
+    }
+}
+
+impl<T, S> IsEmpty for HashSet<T, S> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        HashSet::is_empty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks() {
+        assert!(range(&5, Some(1), Some(10)).is_ok());
+        assert!(range(&0, Some(1), None).is_err());
+        assert!(range(&11.0, None, Some(10.0)).is_err());
+
+        assert!(one_of(&String::from("dark"), &["light", "dark"]).is_ok());
+        assert!(one_of(&3, &[1, 2]).is_err());
+
+        assert!(pattern("com.system76.Example", r"^[\w.]+$").is_ok());
+        assert!(pattern("not valid!", r"^[\w.]+$").is_err());
+        assert!(pattern("anything", "(").is_err());
+
+        assert!(non_empty("cosmic").is_ok());
+        assert!(non_empty(&Vec::<u8>::new()).is_err());
+        assert!(non_empty(&None::<u8>).is_err());
+    }
+}
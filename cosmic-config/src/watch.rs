@@ -0,0 +1,313 @@
+//! Typed watching of individual keys, with debouncing and throttling of bursty writes.
+
+use notify::RecommendedWatcher;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Config, ConfigGet, Error};
+
+/// How often the watcher thread checks whether it has been dropped while idle.
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+/// Timing of updates from [`Config::watch_keys`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct WatchOptions {
+    /// Wait until a key has not changed for this long before reading it.
+    pub debounce: Duration,
+    /// Read a key at most once per window, and at least once per window while
+    /// it keeps changing, even if it has not settled for the debounce period.
+    pub throttle: Duration,
+}
+
+/// An update from [`Config::watch_keys`].
+#[derive(Debug)]
+pub enum KeyUpdate<T> {
+    /// The current value of a key.
+    Value(String, T),
+    /// The value of a key could not be read.
+    Failed(String, Error),
+    /// The file watcher reported an error.
+    Watcher(Error),
+}
+
+/// Watches a config until dropped, from [`Config::watch`].
+#[must_use = "the config is only watched until this is dropped"]
+pub struct ConfigWatcher {
+    _watcher: Option<RecommendedWatcher>,
+    /// Keeps the callback of an in-memory config registered.
+    _token: Option<Arc<()>>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn notify(watcher: RecommendedWatcher) -> Self {
+        Self {
+            _watcher: Some(watcher),
+            _token: None,
+        }
+    }
+
+    pub(crate) fn memory(token: Arc<()>) -> Self {
+        Self {
+            _watcher: None,
+            _token: Some(token),
+        }
+    }
+}
+
+impl fmt::Debug for ConfigWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigWatcher").finish_non_exhaustive()
+    }
+}
+
+/// Watches keys until dropped.
+pub struct KeyWatcher {
+    _watcher: ConfigWatcher,
+    events: mpsc::Sender<Event>,
+}
+
+impl Drop for KeyWatcher {
+    fn drop(&mut self) {
+        let _ = self.events.send(Event::Stop);
+    }
+}
+
+enum Event {
+    Changed(Vec<String>),
+    Error(Error),
+    Stop,
+}
+
+impl Config {
+    /// Watch a set of keys, reading their values as `T` when they change.
+    ///
+    /// The current value of each key is sent first. Changes are then coalesced
+    /// according to `options`, and `f` is called from a background thread.
+    pub fn watch_keys<T, F>(
+        &self,
+        keys: &[&str],
+        options: WatchOptions,
+        mut f: F,
+    ) -> Result<KeyWatcher, Error>
+    where
+        T: DeserializeOwned + 'static,
+        F: FnMut(KeyUpdate<T>) + Send + 'static,
+    {
+        let (events, receiver) = mpsc::channel();
+
+        let watcher = self.watch_with_errors(
+            {
+                let events = events.clone();
+                let keys = keys.iter().map(|&key| key.to_owned()).collect::<Vec<_>>();
+                move |_config, changed| {
+                    let changed = changed
+                        .iter()
+                        .filter(|key| keys.contains(key))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if !changed.is_empty() {
+                        let _ = events.send(Event::Changed(changed));
+                    }
+                }
+            },
+            {
+                let events = events.clone();
+                move |why| {
+                    let _ = events.send(Event::Error(why));
+                }
+            },
+        )?;
+
+        let config = self.clone();
+        let keys = keys.iter().map(|&key| key.to_owned()).collect::<Vec<_>>();
+
+        thread::Builder::new()
+            .name("cosmic-config-watch".into())
+            .spawn(move || {
+                let read = |key: String| match config.get::<T>(&key) {
+                    Ok(value) => KeyUpdate::Value(key, value),
+                    Err(why) => KeyUpdate::Failed(key, why),
+                };
+
+                for key in keys {
+                    f(read(key));
+                }
+
+                let mut coalescer = Coalescer::new(options);
+
+                loop {
+                    let timeout = coalescer.deadline().map_or(IDLE_POLL, |deadline| {
+                        deadline.saturating_duration_since(Instant::now())
+                    });
+
+                    match receiver.recv_timeout(timeout) {
+                        Ok(Event::Changed(keys)) => coalescer.push(keys, Instant::now()),
+                        Ok(Event::Error(why)) => f(KeyUpdate::Watcher(why)),
+                        Ok(Event::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                        Err(mpsc::RecvTimeoutError::Timeout) => (),
+                    }
+
+                    for key in coalescer.ready(Instant::now()) {
+                        f(read(key));
+                    }
+                }
+            })?;
+
+        Ok(KeyWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// When the key first changed since it was last read.
+    first: Instant,
+    /// When the key last changed.
+    last: Instant,
+}
+
+/// Decides when changed keys should be read.
+#[derive(Debug)]
+pub(crate) struct Coalescer {
+    options: WatchOptions,
+    pending: BTreeMap<String, Pending>,
+    read: BTreeMap<String, Instant>,
+}
+
+impl Coalescer {
+    pub(crate) fn new(options: WatchOptions) -> Self {
+        Self {
+            options,
+            pending: BTreeMap::new(),
+            read: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, keys: Vec<String>, now: Instant) {
+        for key in keys {
+            self.pending
+                .entry(key)
+                .and_modify(|pending| pending.last = now)
+                .or_insert(Pending {
+                    first: now,
+                    last: now,
+                });
+        }
+    }
+
+    /// When the next pending key becomes ready.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.pending
+            .iter()
+            .map(|(key, pending)| self.ready_at(key, pending))
+            .min()
+    }
+
+    /// Removes and returns the keys which are ready to be read.
+    pub(crate) fn ready(&mut self, now: Instant) -> Vec<String> {
+        let ready = self
+            .pending
+            .iter()
+            .filter(|(key, pending)| self.ready_at(key, pending) <= now)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in &ready {
+            self.pending.remove(key);
+            self.read.insert(key.clone(), now);
+        }
+
+        ready
+    }
+
+    fn ready_at(&self, key: &str, pending: &Pending) -> Instant {
+        let WatchOptions { debounce, throttle } = self.options;
+
+        let settled = pending.last + debounce;
+        let settled = if throttle.is_zero() {
+            settled
+        } else {
+            settled.min(pending.first + throttle)
+        };
+
+        match self.read.get(key) {
+            Some(&read) => settled.max(read + throttle),
+            None => settled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConfigSet;
+    use std::sync::{Arc, Mutex};
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn debounce() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(WatchOptions {
+            debounce: 10 * MS,
+            throttle: Duration::ZERO,
+        });
+
+        coalescer.push(vec!["a".into()], start);
+        coalescer.push(vec!["a".into()], start + 5 * MS);
+        assert_eq!(coalescer.deadline(), Some(start + 15 * MS));
+        assert!(coalescer.ready(start + 14 * MS).is_empty());
+        assert_eq!(coalescer.ready(start + 15 * MS), ["a"]);
+        assert_eq!(coalescer.deadline(), None);
+    }
+
+    #[test]
+    fn throttle() {
+        let start = Instant::now();
+        let mut coalescer = Coalescer::new(WatchOptions {
+            debounce: 10 * MS,
+            throttle: 20 * MS,
+        });
+
+        // A key which keeps changing is still read once per throttle window.
+        for step in 0..=20 {
+            coalescer.push(vec!["a".into()], start + step * 2 * MS);
+        }
+        assert_eq!(coalescer.ready(start + 19 * MS), Vec::<String>::new());
+        assert_eq!(coalescer.ready(start + 20 * MS), ["a"]);
+
+        // It is not read again until the window has passed.
+        coalescer.push(vec!["a".into()], start + 21 * MS);
+        assert_eq!(coalescer.deadline(), Some(start + 40 * MS));
+    }
+
+    #[test]
+    fn watch_keys() {
+        let config = Config::in_memory("com.system76.WatchTest", 1).unwrap();
+        config.set("a", 1_u32).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let sender = Arc::new(Mutex::new(sender));
+        let watcher = config
+            .watch_keys::<u32, _>(&["a", "b"], WatchOptions::default(), move |update| {
+                let _ = sender.lock().unwrap().send(update);
+            })
+            .unwrap();
+
+        let next = || receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(next(), KeyUpdate::Value(key, 1) if key == "a"));
+        assert!(matches!(next(), KeyUpdate::Failed(key, _) if key == "b"));
+
+        config.set("c", 3_u32).unwrap();
+        config.set("b", 2_u32).unwrap();
+        assert!(matches!(next(), KeyUpdate::Value(key, 2) if key == "b"));
+
+        drop(watcher);
+    }
+}
@@ -0,0 +1,225 @@
+//! The attributes of `#[derive(CosmicConfigEntry)]`, against in-memory configs.
+
+#![cfg(feature = "macro")]
+
+use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic_config::{Config, ConfigGet, ConfigSet, CosmicConfigEntry, Error};
+#[cfg(any(feature = "json", feature = "toml"))]
+use serde::{Deserialize, Serialize};
+
+fn is_even(value: &u32) -> Result<(), String> {
+    if value.is_multiple_of(2) {
+        Ok(())
+    } else {
+        Err(format!("{value} is odd"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+struct Validated {
+    #[validate(range(min = 1, max = 8))]
+    size: u32,
+    #[validate(one_of("light", "dark"))]
+    mode: String,
+    #[validate(pattern = r"^[a-z]+$", non_empty)]
+    name: String,
+    #[validate(with = is_even)]
+    columns: u32,
+}
+
+impl Default for Validated {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            mode: String::from("light"),
+            name: String::from("cosmic"),
+            columns: 2,
+        }
+    }
+}
+
+fn invalid_keys(errors: &[Error]) -> Vec<&str> {
+    errors
+        .iter()
+        .filter_map(|error| match error {
+            Error::Invalid(key, _) => Some(key.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn validate_get_entry() {
+    let config = Config::in_memory("com.system76.DeriveValidateGet", 1).unwrap();
+    config.set("size", 6_u32).unwrap();
+    config.set("mode", "dim").unwrap();
+    config.set("name", "").unwrap();
+    config.set("columns", 3_u32).unwrap();
+
+    // Valid values are read, and invalid ones fall back to their default.
+    let (errors, entry) = Validated::get_entry(&config).unwrap_err();
+    assert_eq!(invalid_keys(&errors), ["mode", "name", "columns"]);
+    assert_eq!(
+        entry,
+        Validated {
+            size: 6,
+            ..Validated::default()
+        }
+    );
+}
+
+#[test]
+fn validate_update_keys() {
+    let config = Config::in_memory("com.system76.DeriveValidateUpdate", 1).unwrap();
+    let mut entry = Validated {
+        size: 6,
+        ..Validated::default()
+    };
+
+    config.set("size", 9_u32).unwrap();
+    config.set("columns", 4_u32).unwrap();
+    let (errors, keys) = entry.update_keys(&config, &["size", "columns"]);
+
+    // The invalid key is reset to its default, and reported as changed.
+    assert_eq!(invalid_keys(&errors), ["size"]);
+    assert_eq!(keys, ["size", "columns"]);
+    assert_eq!(entry.size, 4);
+    assert_eq!(entry.columns, 4);
+}
+
+#[test]
+fn validate_setters() {
+    let config = Config::in_memory("com.system76.DeriveValidateSet", 1).unwrap();
+    let mut entry = Validated::default();
+
+    assert!(matches!(
+        entry.set_size(&config, 0),
+        Err(Error::Invalid(key, _)) if key == "size"
+    ));
+    assert!(matches!(
+        entry.set_name(&config, String::from("Not Lowercase")),
+        Err(Error::Invalid(key, _)) if key == "name"
+    ));
+    // Rejected values are neither assigned nor written.
+    assert_eq!(entry, Validated::default());
+    assert!(config.get::<u32>("size").is_err());
+    assert!(config.get::<String>("name").is_err());
+
+    assert!(entry.set_mode(&config, String::from("dark")).unwrap());
+    assert_eq!(config.get::<String>("mode").unwrap(), "dark");
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+struct Window {
+    width: u32,
+    title: String,
+}
+
+#[cfg(feature = "json")]
+#[derive(Clone, Debug, Default, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+#[format = "json"]
+struct JsonEntry {
+    window: Window,
+    enabled: bool,
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn format_json() {
+    let config = Config::in_memory("com.system76.DeriveFormatJson", 1).unwrap();
+    let json = config.clone().with_format(cosmic_config::Format::Json);
+    let mut entry = JsonEntry {
+        window: Window {
+            width: 800,
+            title: String::from("Files"),
+        },
+        enabled: false,
+    };
+
+    // Keys are written as JSON rather than in the RON of the config.
+    entry.write_entry(&config).unwrap();
+    assert_eq!(json.get::<Window>("window").unwrap(), entry.window);
+    assert!(config.get::<Window>("window").is_err());
+    assert_eq!(JsonEntry::get_entry(&config).unwrap(), entry);
+
+    json.set("window", Window::default()).unwrap();
+    let (errors, keys) = entry.update_keys(&config, &["window"]);
+    assert!(errors.is_empty());
+    assert_eq!(keys, ["window"]);
+    assert_eq!(entry.window, Window::default());
+
+    assert!(entry.set_enabled(&config, true).unwrap());
+    assert!(json.get::<bool>("enabled").unwrap());
+}
+
+#[cfg(feature = "toml")]
+#[derive(Clone, Debug, Default, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+#[format = "toml"]
+struct TomlEntry {
+    window: Window,
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn format_toml() {
+    let config = Config::in_memory("com.system76.DeriveFormatToml", 1).unwrap();
+    let toml = config.clone().with_format(cosmic_config::Format::Toml);
+    let mut entry = TomlEntry::default();
+
+    assert!(
+        entry
+            .set_window(
+                &config,
+                Window {
+                    width: 640,
+                    title: String::from("Terminal"),
+                },
+            )
+            .unwrap()
+    );
+    assert_eq!(toml.get::<Window>("window").unwrap(), entry.window);
+    assert!(config.get::<Window>("window").is_err());
+    assert_eq!(TomlEntry::get_entry(&config).unwrap(), entry);
+}
+
+fn upgrade_v1(step: &mut cosmic_config::migration::MigrationStep<'_>) {
+    step.rename("dark", "is_dark");
+    step.transform("size", |size: u32| size * 2);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, CosmicConfigEntry)]
+#[version = 2]
+#[migration(from = 1, with = upgrade_v1)]
+struct Migrated {
+    is_dark: bool,
+    size: u32,
+}
+
+#[test]
+fn migration() {
+    const NAME: &str = "com.system76.DeriveMigration";
+    let root = tempfile::tempdir().unwrap();
+
+    let v1 = Config::with_custom_path(NAME, 1, root.path().to_owned()).unwrap();
+    v1.set("dark", true).unwrap();
+    v1.set("size", 4_u32).unwrap();
+
+    let v2 = Config::with_custom_path(NAME, 2, root.path().to_owned()).unwrap();
+    let report = Migrated::migrate_entry(&v2).unwrap().unwrap();
+    assert_eq!((report.from, report.to), (1, 2));
+    assert_eq!(report.migrated, ["is_dark", "size"]);
+    assert_eq!(
+        Migrated::get_entry(&v2).unwrap(),
+        Migrated {
+            is_dark: true,
+            size: 8,
+        }
+    );
+
+    // An entry is migrated only once.
+    assert!(Migrated::migrate_entry(&v2).unwrap().is_none());
+}
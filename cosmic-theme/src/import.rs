@@ -0,0 +1,555 @@
+//! Import third-party color schemes into a [`ThemeBuilder`].
+//!
+//! Each importer maps the colors of a scheme onto the fields of a builder, and
+//! reports which colors of the scheme it used, and which fields it had to guess.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use palette::{IntoColor, Oklch, Srgb, Srgba};
+use thiserror::Error;
+
+use crate::ThemeBuilder;
+use crate::composite::over;
+
+/// A field of [`ThemeBuilder`] which can be set from a color scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// [`ThemeBuilder::bg_color`]
+    Bg,
+    /// [`ThemeBuilder::primary_container_bg`]
+    PrimaryContainerBg,
+    /// [`ThemeBuilder::accent`]
+    Accent,
+    /// [`ThemeBuilder::success`]
+    Success,
+    /// [`ThemeBuilder::warning`]
+    Warning,
+    /// [`ThemeBuilder::destructive`]
+    Destructive,
+    /// [`ThemeBuilder::neutral_tint`]
+    NeutralTint,
+    /// [`ThemeBuilder::text_tint`]
+    TextTint,
+}
+
+/// A theme builder imported from a color scheme.
+///
+/// Every field is either used or guessed. Guessed colors other than the container
+/// background and neutral tint are left to the defaults of the builder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedTheme {
+    /// The builder, starting from the light or dark theme to match the scheme.
+    pub builder: ThemeBuilder,
+    /// Each field which was set from the scheme, with the name of its color in the scheme.
+    pub used: Vec<(Field, String)>,
+    /// Each field which the scheme has no color for, with the name of the color it was guessed
+    /// from: a color of the scheme, or of the palette of the builder such as `accent_blue`.
+    pub guessed: Vec<(Field, String)>,
+}
+
+/// An error importing a color scheme.
+#[derive(Error, Debug)]
+pub enum ImportError {
+    /// The scheme is not valid JSON.
+    #[error("Json Error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The scheme has no background color, which every other color is relative to.
+    #[error("Missing background color")]
+    MissingBackground,
+}
+
+/// The names of the colors to read each field from, in order of preference.
+type Mapping = [(Field, &'static [&'static str]); 8];
+
+const BASE16: Mapping = [
+    (Field::Bg, &["base00"]),
+    (Field::PrimaryContainerBg, &["base01"]),
+    (Field::Accent, &["base0d"]),
+    (Field::Success, &["base0b"]),
+    (Field::Warning, &["base0a"]),
+    (Field::Destructive, &["base08"]),
+    (Field::NeutralTint, &["base03"]),
+    (Field::TextTint, &["base05"]),
+];
+
+const VS_CODE: Mapping = [
+    (Field::Bg, &["editor.background"]),
+    (
+        Field::PrimaryContainerBg,
+        &[
+            "sideBar.background",
+            "activityBar.background",
+            "panel.background",
+        ],
+    ),
+    (
+        Field::Accent,
+        &[
+            "focusBorder",
+            "button.background",
+            "activityBarBadge.background",
+            "textLink.foreground",
+        ],
+    ),
+    (
+        Field::Success,
+        &[
+            "gitDecoration.addedResourceForeground",
+            "terminal.ansiGreen",
+        ],
+    ),
+    (
+        Field::Warning,
+        &[
+            "editorWarning.foreground",
+            "list.warningForeground",
+            "terminal.ansiYellow",
+        ],
+    ),
+    (
+        Field::Destructive,
+        &[
+            "editorError.foreground",
+            "errorForeground",
+            "terminal.ansiRed",
+        ],
+    ),
+    (
+        Field::NeutralTint,
+        &["editorLineNumber.foreground", "input.background"],
+    ),
+    (Field::TextTint, &["editor.foreground", "foreground"]),
+];
+
+const GTK: Mapping = [
+    (
+        Field::Bg,
+        &["window_bg_color", "theme_bg_color", "bg_color"],
+    ),
+    (
+        Field::PrimaryContainerBg,
+        &[
+            "card_bg_color",
+            "view_bg_color",
+            "theme_base_color",
+            "base_color",
+        ],
+    ),
+    (
+        Field::Accent,
+        &[
+            "accent_bg_color",
+            "accent_color",
+            "theme_selected_bg_color",
+            "selected_bg_color",
+        ],
+    ),
+    (Field::Success, &["success_color", "success_bg_color"]),
+    (Field::Warning, &["warning_color", "warning_bg_color"]),
+    (
+        Field::Destructive,
+        &[
+            "destructive_color",
+            "error_color",
+            "destructive_bg_color",
+            "error_bg_color",
+        ],
+    ),
+    (Field::NeutralTint, &["borders", "headerbar_border_color"]),
+    (
+        Field::TextTint,
+        &["window_fg_color", "theme_fg_color", "fg_color"],
+    ),
+];
+
+/// Import a Base16 or Base24 scheme, in YAML.
+///
+/// Only the subset of YAML which schemes are written in is read: a `key: value`
+/// on each line, at any indentation, so that both the flat `base00: "181818"`
+/// layout and the newer `palette:` layout are supported. Keys and values may be
+/// quoted with `"` or `'`. As in YAML, a `#` outside of quotes starts a comment,
+/// so a color with a leading `#` must be quoted. The extra colors of Base24 are
+/// not used.
+///
+/// # Errors
+///
+/// Returns an `ImportError` if the scheme has no `base00` color.
+pub fn base16(yaml: &str) -> Result<ImportedTheme, ImportError> {
+    let colors = yaml
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim().trim_matches(['"', '\'']).to_ascii_lowercase();
+            let value = value.trim();
+            let value = match value.strip_prefix(['"', '\'']) {
+                Some(quoted) => quoted.split(['"', '\'']).next()?,
+                // An unquoted `#` starts a comment.
+                None => value.split('#').next()?.trim(),
+            };
+            Some((key, parse_color(value)?))
+        })
+        .collect();
+
+    import(&colors, &BASE16, None)
+}
+
+/// Import a VS Code color theme, in JSON.
+///
+/// Comments and trailing commas, which VS Code allows in themes, are ignored.
+///
+/// # Errors
+///
+/// Returns an `ImportError` if the theme is not valid JSON, or has no `editor.background` color.
+pub fn vs_code(json: &str) -> Result<ImportedTheme, ImportError> {
+    let theme: serde_json::Value = serde_json::from_str(&strip_jsonc(json))?;
+
+    let colors = theme
+        .get("colors")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), parse_color(value.as_str()?)?)))
+        .collect();
+
+    let is_dark = match theme.get("type").and_then(serde_json::Value::as_str) {
+        Some("dark" | "hc-black") => Some(true),
+        Some("light" | "hc-light") => Some(false),
+        _ => None,
+    };
+
+    import(&colors, &VS_CODE, is_dark)
+}
+
+/// Import the `@define-color` definitions of a GTK3 or libadwaita stylesheet.
+///
+/// Definitions may refer to earlier colors by name, but GTK color expressions
+/// such as `shade()` and `alpha()` are not evaluated.
+///
+/// # Errors
+///
+/// Returns an `ImportError` if the stylesheet has no window background color.
+pub fn gtk_css(css: &str) -> Result<ImportedTheme, ImportError> {
+    let mut colors = HashMap::new();
+
+    for definition in css.split("@define-color").skip(1) {
+        let Some((definition, _)) = definition.split_once(';') else {
+            continue;
+        };
+        let Some((name, value)) = definition.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value = value.trim();
+        let color = match value.strip_prefix('@') {
+            Some(reference) => colors.get(reference).copied(),
+            None => parse_color(value),
+        };
+        if let Some(color) = color {
+            colors.insert(name.to_owned(), color);
+        }
+    }
+
+    import(&colors, &GTK, None)
+}
+
+/// Map the colors of a scheme onto a builder.
+///
+/// `is_dark` is detected from the background color, unless the scheme declares it.
+fn import(
+    colors: &HashMap<String, Srgba>,
+    mapping: &Mapping,
+    is_dark: Option<bool>,
+) -> Result<ImportedTheme, ImportError> {
+    let mut used = Vec::new();
+    let mut found = HashMap::new();
+    for (field, names) in mapping {
+        if let Some((name, color)) = names
+            .iter()
+            .find_map(|name| Some((*name, *colors.get(*name)?)))
+        {
+            used.push((*field, name.to_owned()));
+            found.insert(*field, color);
+        }
+    }
+
+    let (Some(&bg), Some((_, bg_name))) = (
+        found.get(&Field::Bg),
+        used.iter().find(|(field, _)| *field == Field::Bg),
+    ) else {
+        return Err(ImportError::MissingBackground);
+    };
+
+    let is_dark = is_dark.unwrap_or_else(|| {
+        let oklch: Oklch = bg.into_color();
+        oklch.l < 0.5
+    });
+    let mut builder = if is_dark {
+        ThemeBuilder::dark()
+    } else {
+        ThemeBuilder::light()
+    };
+
+    // Guess the colors which every theme needs from the background.
+    let mut guessed = Vec::new();
+    if let Entry::Vacant(entry) = found.entry(Field::PrimaryContainerBg) {
+        let overlay = if is_dark {
+            Srgba::new(1.0, 1.0, 1.0, 0.08)
+        } else {
+            Srgba::new(0.0, 0.0, 0.0, 0.08)
+        };
+        entry.insert(over(overlay, bg));
+        guessed.push((Field::PrimaryContainerBg, bg_name.clone()));
+    }
+    if let Entry::Vacant(entry) = found.entry(Field::NeutralTint) {
+        entry.insert(bg);
+        guessed.push((Field::NeutralTint, bg_name.clone()));
+    }
+
+    // The builder takes the other colors from its palette, and the text from the background.
+    for (field, name) in [
+        (Field::Accent, "accent_blue"),
+        (Field::Success, "bright_green"),
+        (Field::Warning, "bright_orange"),
+        (Field::Destructive, "bright_red"),
+        (Field::TextTint, bg_name.as_str()),
+    ] {
+        if !found.contains_key(&field) {
+            guessed.push((field, name.to_owned()));
+        }
+    }
+
+    for (field, color) in found {
+        let opaque: Srgb = color.color;
+        builder = match field {
+            Field::Bg => builder.bg_color(color),
+            Field::PrimaryContainerBg => builder.primary_container_bg(color),
+            Field::Accent => builder.accent(opaque),
+            Field::Success => builder.success(opaque),
+            Field::Warning => builder.warning(opaque),
+            Field::Destructive => builder.destructive(opaque),
+            Field::NeutralTint => builder.neutral_tint(opaque),
+            Field::TextTint => builder.text_tint(opaque),
+        };
+    }
+
+    Ok(ImportedTheme {
+        builder,
+        used,
+        guessed,
+    })
+}
+
+/// Parse a CSS color, or a hex color without the leading `#`.
+fn parse_color(value: &str) -> Option<Srgba> {
+    let value = value.trim();
+    let color = if !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit()) {
+        csscolorparser::parse(&format!("#{value}"))
+    } else {
+        csscolorparser::parse(value)
+    }
+    .ok()?;
+    Some(Srgba::new(color.r, color.g, color.b, color.a))
+}
+
+/// Remove the comments and trailing commas of JSON with comments.
+fn strip_jsonc(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (']' | '}', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use palette::rgb::Rgb;
+
+    fn hex(c: Srgb) -> String {
+        let c: Rgb<palette::encoding::Srgb, u8> = c.into_format();
+        format!("{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+    }
+
+    #[test]
+    fn test_base16() {
+        let yaml = r##"
+scheme: "Tomorrow Night"
+author: "Chris Kempson"
+base00: "1d1f21" # background
+base01: "282a2e"
+base02: "373b41"
+base03: '969896'
+base04: "b4b7b4"
+base05: "c5c8c6"
+base08: "cc6666"
+base0A: "f0c674"
+base0B: "b5bd68"
+base0D: "81a2be"
+"##;
+        let imported = base16(yaml).unwrap();
+        assert!(imported.guessed.is_empty());
+        assert_eq!(imported.used.len(), 8);
+        assert!(
+            imported
+                .used
+                .contains(&(Field::Accent, "base0d".to_owned()))
+        );
+
+        let builder = imported.builder;
+        assert_eq!(hex(builder.accent.unwrap()), "81a2be");
+        assert_eq!(hex(builder.bg_color.unwrap().color), "1d1f21");
+        assert!(builder.palette.is_dark());
+
+        // The newer layout nests the colors in a palette.
+        let yaml = "system: \"base24\"\npalette:\n  base00: \"#fafafa\"\n  base0D: \"#4078f2\"\n";
+        let imported = base16(yaml).unwrap();
+        assert!(!imported.builder.palette.is_dark());
+        assert_eq!(
+            imported.guessed,
+            [
+                (Field::PrimaryContainerBg, "base00".to_owned()),
+                (Field::NeutralTint, "base00".to_owned()),
+                (Field::Success, "bright_green".to_owned()),
+                (Field::Warning, "bright_orange".to_owned()),
+                (Field::Destructive, "bright_red".to_owned()),
+                (Field::TextTint, "base00".to_owned()),
+            ]
+        );
+
+        assert!(matches!(
+            base16("base01: \"282a2e\""),
+            Err(ImportError::MissingBackground)
+        ));
+    }
+
+    #[test]
+    fn test_base16_yaml_subset() {
+        let background = |yaml: &str| Some(hex(base16(yaml).ok()?.builder.bg_color?.color));
+        let expected = Some(String::from("1d1f21"));
+
+        // Quoted keys.
+        assert_eq!(background("\"base00\": \"1d1f21\""), expected);
+        assert_eq!(background("'base00': '1d1f21'"), expected);
+
+        // Comments after quoted and unquoted values, and commented out lines.
+        assert_eq!(background("base00: \"1d1f21\" # background"), expected);
+        assert_eq!(background("base00: 1d1f21 # background"), expected);
+        assert_eq!(background("# base00: \"1d1f21\""), None);
+
+        // A leading `#` is only part of a quoted value.
+        assert_eq!(background("base00: \"#1d1f21\""), expected);
+        assert_eq!(background("base00: #1d1f21"), None);
+
+        // Colors nested in a palette.
+        assert_eq!(background("palette:\n  base00: \"1d1f21\"\n"), expected);
+    }
+
+    #[test]
+    fn test_vs_code() {
+        let json = r##"{
+            // A light theme with a dark background.
+            "type": "light",
+            "colors": {
+                "editor.background": "#272822",
+                "editor.foreground": "#f8f8f2", /* text */
+                "button.background": "#75715eff",
+                "terminal.ansiRed": "#f92672",
+            },
+        }"##;
+        let imported = vs_code(json).unwrap();
+        assert!(!imported.builder.palette.is_dark());
+        assert_eq!(hex(imported.builder.accent.unwrap()), "75715e");
+        assert!(
+            imported
+                .used
+                .contains(&(Field::Destructive, "terminal.ansiRed".to_owned()))
+        );
+        assert_eq!(
+            imported.guessed,
+            [
+                (Field::PrimaryContainerBg, "editor.background".to_owned()),
+                (Field::NeutralTint, "editor.background".to_owned()),
+                (Field::Success, "bright_green".to_owned()),
+                (Field::Warning, "bright_orange".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gtk_css() {
+        let css = r#"
+@define-color window_bg_color #242424;
+@define-color view_bg_color rgb(30, 30, 30);
+@define-color accent_bg_color @blue_3;
+@define-color blue_3 #3584e4;
+@define-color accent_color @blue_3;
+@define-color borders alpha(currentColor, 0.15);
+"#;
+        let imported = gtk_css(css).unwrap();
+        assert_eq!(hex(imported.builder.accent.unwrap()), "3584e4");
+        assert_eq!(
+            hex(imported.builder.primary_container_bg.unwrap().color),
+            "1e1e1e"
+        );
+        assert!(
+            imported
+                .used
+                .contains(&(Field::Accent, "accent_color".to_owned()))
+        );
+        assert_eq!(
+            imported.guessed,
+            [
+                (Field::NeutralTint, "window_bg_color".to_owned()),
+                (Field::Success, "bright_green".to_owned()),
+                (Field::Warning, "bright_orange".to_owned()),
+                (Field::Destructive, "bright_red".to_owned()),
+                (Field::TextTint, "window_bg_color".to_owned()),
+            ]
+        );
+    }
+}
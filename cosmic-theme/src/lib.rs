@@ -12,12 +12,26 @@ mod model;
 
 #[cfg(feature = "export")]
 mod output;
+#[cfg(feature = "export")]
+pub use output::design_tokens::{DesignToken, TokenValue};
+#[cfg(feature = "export")]
+pub use output::terminal_output::TerminalPalette;
+
+#[cfg(feature = "import")]
+pub mod import;
+
+#[cfg(feature = "wallpaper")]
+pub mod wallpaper;
 
 /// composite colors in srgb
 pub mod composite;
 /// get color steps
 pub mod steps;
 
+pub mod package;
+#[cfg(feature = "schedule")]
+pub mod schedule;
+
 /// name of cosmic theme
 pub const NAME: &str = "com.system76.CosmicTheme";
 
@@ -0,0 +1,392 @@
+use std::num::NonZeroUsize;
+
+use palette::color_difference::Wcag21RelativeContrast;
+use palette::{Srgba, WithAlpha};
+use serde::{Deserialize, Serialize};
+
+use crate::Theme;
+use crate::composite::over;
+use crate::steps::{color_index, steps};
+
+/// A conformance level for the contrast of text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContrastLevel {
+    /// WCAG 2.x AA for body text, with an APCA lightness contrast of at least 60.
+    #[default]
+    Aa,
+    /// WCAG 2.x AAA for body text, with an APCA lightness contrast of at least 75.
+    Aaa,
+}
+
+impl ContrastLevel {
+    /// The minimum WCAG 2.x contrast ratio.
+    #[must_use]
+    pub fn min_wcag(self) -> f32 {
+        match self {
+            Self::Aa => 4.5,
+            Self::Aaa => 7.0,
+        }
+    }
+
+    /// The minimum absolute APCA lightness contrast.
+    #[must_use]
+    pub fn min_apca(self) -> f32 {
+        match self {
+            Self::Aa => 60.0,
+            Self::Aaa => 75.0,
+        }
+    }
+}
+
+/// The contrast of a foreground color drawn on a background color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContrastPair {
+    /// The name of the pair, such as `on_bg`.
+    pub name: &'static str,
+    /// The foreground color, composited over the background.
+    pub foreground: Srgba,
+    /// The background color, composited over the containers beneath it.
+    pub background: Srgba,
+    /// The WCAG 2.x contrast ratio, from 1 to 21.
+    pub wcag: f32,
+    /// The APCA lightness contrast, which is negative for light text on a dark background.
+    pub apca: f32,
+}
+
+impl ContrastPair {
+    fn new(name: &'static str, foreground: Srgba, background: Srgba) -> Self {
+        let foreground = over(foreground, background);
+        Self {
+            name,
+            foreground,
+            background,
+            wcag: wcag_contrast(foreground, background),
+            apca: apca_contrast(foreground, background),
+        }
+    }
+
+    /// Whether the pair meets both the WCAG and APCA minimums of the level.
+    #[must_use]
+    pub fn passes(&self, level: ContrastLevel) -> bool {
+        self.wcag >= level.min_wcag() && self.apca.abs() >= level.min_apca()
+    }
+}
+
+/// The contrast of every foreground and background pair used by the widgets of a theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastReport {
+    /// Each pair, in the order of [`Theme::contrast_report`].
+    pub pairs: Vec<ContrastPair>,
+}
+
+impl ContrastReport {
+    /// The pairs which do not meet the level.
+    pub fn failures(&self, level: ContrastLevel) -> impl Iterator<Item = &ContrastPair> {
+        self.pairs.iter().filter(move |pair| !pair.passes(level))
+    }
+
+    /// Whether every pair meets the level.
+    #[must_use]
+    pub fn passes(&self, level: ContrastLevel) -> bool {
+        self.failures(level).next().is_none()
+    }
+}
+
+/// A foreground color of a theme, and the background it is drawn on.
+struct Pair {
+    name: &'static str,
+    foreground: Foreground,
+    background: fn(&Theme) -> Srgba,
+}
+
+/// Where a foreground color of a theme is read from and written to.
+enum Foreground {
+    /// A color which is always set, read by the first function and written
+    /// through the second.
+    Color(fn(&Theme) -> Srgba, fn(&mut Theme) -> &mut Srgba),
+    /// The accent text color, which is the accent color unless it is set.
+    AccentText,
+}
+
+impl Foreground {
+    fn get(&self, theme: &Theme) -> Srgba {
+        match self {
+            Self::Color(get, _) => get(theme),
+            Self::AccentText => theme.accent_text_color(),
+        }
+    }
+
+    fn set(&self, theme: &mut Theme, value: Srgba) {
+        match self {
+            Self::Color(_, color) => *color(theme) = value,
+            Self::AccentText => theme.accent_text = Some(value),
+        }
+    }
+}
+
+/// Every pair of colors which the widgets draw text or icons with.
+const PAIRS: &[Pair] = &[
+    Pair {
+        name: "on_bg",
+        foreground: Foreground::Color(|t| t.background.on, |t| &mut t.background.on),
+        background: |t| t.background.base,
+    },
+    Pair {
+        name: "on_bg_component",
+        foreground: Foreground::Color(
+            |t| t.background.component.on,
+            |t| &mut t.background.component.on,
+        ),
+        background: |t| over(t.background.component.base, t.background.base),
+    },
+    Pair {
+        name: "on_primary_container",
+        foreground: Foreground::Color(|t| t.primary.on, |t| &mut t.primary.on),
+        background: |t| over(t.primary.base, t.background.base),
+    },
+    Pair {
+        name: "on_primary_component",
+        foreground: Foreground::Color(|t| t.primary.component.on, |t| &mut t.primary.component.on),
+        background: |t| {
+            over(
+                t.primary.component.base,
+                over(t.primary.base, t.background.base),
+            )
+        },
+    },
+    Pair {
+        name: "on_secondary_container",
+        foreground: Foreground::Color(|t| t.secondary.on, |t| &mut t.secondary.on),
+        background: |t| over(t.secondary.base, t.background.base),
+    },
+    Pair {
+        name: "on_secondary_component",
+        foreground: Foreground::Color(
+            |t| t.secondary.component.on,
+            |t| &mut t.secondary.component.on,
+        ),
+        background: |t| {
+            over(
+                t.secondary.component.base,
+                over(t.secondary.base, t.background.base),
+            )
+        },
+    },
+    Pair {
+        name: "accent_text_on_bg",
+        foreground: Foreground::AccentText,
+        background: |t| t.background.base,
+    },
+    Pair {
+        name: "accent_text_on_primary_container",
+        foreground: Foreground::AccentText,
+        background: |t| over(t.primary.base, t.background.base),
+    },
+    Pair {
+        name: "on_accent",
+        foreground: Foreground::Color(|t| t.accent.on, |t| &mut t.accent.on),
+        background: |t| t.accent.base,
+    },
+    Pair {
+        name: "on_success",
+        foreground: Foreground::Color(|t| t.success.on, |t| &mut t.success.on),
+        background: |t| t.success.base,
+    },
+    Pair {
+        name: "on_warning",
+        foreground: Foreground::Color(|t| t.warning.on, |t| &mut t.warning.on),
+        background: |t| t.warning.base,
+    },
+    Pair {
+        name: "on_destructive",
+        foreground: Foreground::Color(|t| t.destructive.on, |t| &mut t.destructive.on),
+        background: |t| t.destructive.base,
+    },
+    Pair {
+        name: "on_accent_button",
+        foreground: Foreground::Color(|t| t.accent_button.on, |t| &mut t.accent_button.on),
+        background: |t| over(t.accent_button.base, t.background.base),
+    },
+    Pair {
+        name: "on_success_button",
+        foreground: Foreground::Color(|t| t.success_button.on, |t| &mut t.success_button.on),
+        background: |t| over(t.success_button.base, t.background.base),
+    },
+    Pair {
+        name: "on_warning_button",
+        foreground: Foreground::Color(|t| t.warning_button.on, |t| &mut t.warning_button.on),
+        background: |t| over(t.warning_button.base, t.background.base),
+    },
+    Pair {
+        name: "on_destructive_button",
+        foreground: Foreground::Color(
+            |t| t.destructive_button.on,
+            |t| &mut t.destructive_button.on,
+        ),
+        background: |t| over(t.destructive_button.base, t.background.base),
+    },
+    Pair {
+        name: "on_button",
+        foreground: Foreground::Color(|t| t.button.on, |t| &mut t.button.on),
+        background: |t| over(t.button.base, t.background.base),
+    },
+    Pair {
+        name: "on_link_button",
+        foreground: Foreground::Color(|t| t.link_button.on, |t| &mut t.link_button.on),
+        background: |t| t.background.base,
+    },
+    Pair {
+        name: "on_text_button",
+        foreground: Foreground::Color(|t| t.text_button.on, |t| &mut t.text_button.on),
+        background: |t| t.background.base,
+    },
+];
+
+impl Theme {
+    /// Computes the WCAG 2.x and APCA contrast of every pair of colors used by the widgets.
+    #[must_use]
+    #[cold]
+    pub fn contrast_report(&self) -> ContrastReport {
+        let pairs = PAIRS
+            .iter()
+            .map(|pair| {
+                ContrastPair::new(
+                    pair.name,
+                    pair.foreground.get(self),
+                    (pair.background)(self),
+                )
+            })
+            .collect();
+        ContrastReport { pairs }
+    }
+
+    /// Changes the lightness of each foreground color which fails the level, until it passes.
+    ///
+    /// The lightness closest to the original which passes is chosen. If no lightness
+    /// passes, the one with the highest contrast is chosen instead.
+    #[cold]
+    pub fn enforce_contrast(&mut self, level: ContrastLevel) {
+        for pair in PAIRS {
+            let background = (pair.background)(self);
+            let foreground = pair.foreground.get(self);
+            if ContrastPair::new(pair.name, foreground, background).passes(level) {
+                continue;
+            }
+
+            let candidates = steps(foreground, NonZeroUsize::new(100).unwrap());
+            let current = color_index(foreground, candidates.len());
+            let contrast = |c: Srgba| ContrastPair::new(pair.name, c, background);
+
+            // Search outwards from the current lightness.
+            let nearest = (1..candidates.len())
+                .flat_map(|distance| [current.checked_sub(distance), Some(current + distance)])
+                .filter_map(|i| candidates.get(i?))
+                .find(|c| contrast(**c).passes(level));
+
+            let best = nearest.copied().unwrap_or_else(|| {
+                candidates
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| contrast(*a).wcag.total_cmp(&contrast(*b).wcag))
+                    .unwrap_or(foreground)
+            });
+
+            pair.foreground.set(self, best.with_alpha(foreground.alpha));
+        }
+    }
+}
+
+/// The WCAG 2.x contrast ratio of two opaque colors.
+#[must_use]
+pub fn wcag_contrast(a: Srgba, b: Srgba) -> f32 {
+    a.color.relative_contrast(b.color)
+}
+
+/// The APCA lightness contrast of opaque text on an opaque background.
+///
+/// Uses the constants of APCA 0.0.98G. The result is positive for dark text on a
+/// light background, and negative for light text on a dark background.
+#[must_use]
+pub fn apca_contrast(text: Srgba, background: Srgba) -> f32 {
+    let luminance = |c: Srgba| {
+        let y = 0.212_672_9 * c.red.powf(2.4)
+            + 0.715_152_2 * c.green.powf(2.4)
+            + 0.072_175 * c.blue.powf(2.4);
+        // Soft clamp of near black colors.
+        if y < 0.022 {
+            y + (0.022 - y).powf(1.414)
+        } else {
+            y
+        }
+    };
+    let (text, background) = (luminance(text), luminance(background));
+
+    if (background - text).abs() < 0.0005 {
+        return 0.0;
+    }
+
+    if background > text {
+        let s = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+        if s < 0.1 { 0.0 } else { (s - 0.027) * 100.0 }
+    } else {
+        let s = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+        if s > -0.1 { 0.0 } else { (s + 0.027) * 100.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use palette::Srgb;
+
+    use super::*;
+    use crate::ThemeBuilder;
+
+    #[test]
+    fn test_contrast() {
+        let black = Srgba::new(0.0, 0.0, 0.0, 1.0);
+        let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        assert!((wcag_contrast(black, white) - 21.0).abs() < 0.01);
+        assert!((wcag_contrast(white, white) - 1.0).abs() < 0.01);
+        assert!((apca_contrast(black, white) - 106.04).abs() < 0.1);
+        assert!((apca_contrast(white, black) + 107.88).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_enforce_contrast() {
+        let builder = ThemeBuilder::dark().accent(Srgb::new(0.3, 0.3, 0.3));
+        let report = builder.clone().build().contrast_report();
+        assert!(
+            report
+                .failures(ContrastLevel::Aa)
+                .any(|pair| pair.name == "on_accent")
+        );
+
+        let theme = builder.enforce_contrast(ContrastLevel::Aa).build();
+        let report = theme.contrast_report();
+        for pair in &report.pairs {
+            assert!(pair.passes(ContrastLevel::Aa), "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn test_enforce_contrast_keeps_accent_text_unset() {
+        let mut theme = ThemeBuilder::dark().build();
+        theme.accent_text = None;
+        assert!(
+            theme
+                .contrast_report()
+                .pairs
+                .iter()
+                .filter(|pair| pair.name.starts_with("accent_text"))
+                .all(|pair| pair.passes(ContrastLevel::Aa))
+        );
+
+        // The accent text is only set when it has to be corrected.
+        theme.enforce_contrast(ContrastLevel::Aa);
+        assert_eq!(theme.accent_text, None);
+
+        theme.enforce_contrast(ContrastLevel::Aaa);
+        assert!(theme.accent_text.is_some());
+        assert_eq!(theme.accent.base, ThemeBuilder::dark().build().accent.base);
+    }
+}
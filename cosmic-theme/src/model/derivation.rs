@@ -1,4 +1,4 @@
-use palette::{Srgba, WithAlpha};
+use palette::{FromColor, Mix, Oklaba, Srgba, WithAlpha};
 use serde::{Deserialize, Serialize};
 
 use crate::composite::over;
@@ -38,6 +38,18 @@ impl Container {
             small_widget,
         }
     }
+
+    /// Interpolates each color towards the colors of another container in OKLab,
+    /// with `t` from 0 for this container to 1 for the other.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            base: mix_oklab(self.base, other.base, t),
+            component: self.component.interpolate(&other.component, t),
+            divider: mix_oklab(self.divider, other.divider, t),
+            on: mix_oklab(self.on, other.on, t),
+            small_widget: mix_oklab(self.small_widget, other.small_widget, t),
+        }
+    }
 }
 
 /// The colors for a widget of the Cosmic theme
@@ -105,6 +117,25 @@ impl Component {
         self.focus
     }
 
+    /// Interpolates each color towards the colors of another component in OKLab,
+    /// with `t` from 0 for this component to 1 for the other.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            base: mix_oklab(self.base, other.base, t),
+            hover: mix_oklab(self.hover, other.hover, t),
+            pressed: mix_oklab(self.pressed, other.pressed, t),
+            selected: mix_oklab(self.selected, other.selected, t),
+            selected_text: mix_oklab(self.selected_text, other.selected_text, t),
+            focus: mix_oklab(self.focus, other.focus, t),
+            divider: mix_oklab(self.divider, other.divider, t),
+            on: mix_oklab(self.on, other.on, t),
+            disabled: mix_oklab(self.disabled, other.disabled, t),
+            on_disabled: mix_oklab(self.on_disabled, other.on_disabled, t),
+            border: mix_oklab(self.border, other.border, t),
+            disabled_border: mix_oklab(self.disabled_border, other.disabled_border, t),
+        }
+    }
+
     /// helper for producing a component from a base color a neutral and an accent
     pub fn colored_component(
         base: Srgba,
@@ -201,3 +232,9 @@ impl Component {
         }
     }
 }
+
+/// Mixes two colors in OKLab, with `t` from 0 for `a` to 1 for `b`.
+pub(crate) fn mix_oklab(a: Srgba, b: Srgba, t: f32) -> Srgba {
+    let mixed = Oklaba::from_color(a).mix(Oklaba::from_color(b), t.clamp(0.0, 1.0));
+    palette::Clamp::clamp(Srgba::from_color(mixed))
+}
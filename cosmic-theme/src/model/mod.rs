@@ -1,13 +1,17 @@
+pub use contrast::*;
 pub use corner::*;
 pub use cosmic_palette::*;
 pub use derivation::*;
 pub use mode::*;
 pub use spacing::*;
 pub use theme::*;
+pub use vision::*;
 
+mod contrast;
 mod corner;
 mod cosmic_palette;
 mod derivation;
 mod mode;
 mod spacing;
 mod theme;
+mod vision;
@@ -1,8 +1,9 @@
 use crate::composite::over;
+use crate::model::derivation::mix_oklab;
 use crate::steps::{color_index, get_small_widget_color, get_surface_color, get_text, steps};
 use crate::{
-    Component, Container, CornerRadii, CosmicPalette, CosmicPaletteInner, DARK_PALETTE,
-    LIGHT_PALETTE, NAME, Spacing, ThemeMode,
+    Component, Container, ContrastLevel, CornerRadii, CosmicPalette, CosmicPaletteInner,
+    DARK_PALETTE, LIGHT_PALETTE, NAME, Spacing, ThemeMode,
 };
 use cosmic_config::{Config, CosmicConfigEntry};
 use palette::color_difference::Wcag21RelativeContrast;
@@ -725,6 +726,83 @@ impl Theme {
         builder.build()
     }
 
+    /// Interpolates the colors of the containers and components towards another theme in
+    /// OKLab, with `t` from 0 for this theme to 1 for the other.
+    ///
+    /// Every other property is taken from the other theme.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let mut theme = other.clone();
+        for (container, from, to) in [
+            (&mut theme.background, &self.background, &other.background),
+            (&mut theme.primary, &self.primary, &other.primary),
+            (&mut theme.secondary, &self.secondary, &other.secondary),
+        ] {
+            *container = from.interpolate(to, t);
+        }
+        for (component, from, to) in [
+            (&mut theme.accent, &self.accent, &other.accent),
+            (&mut theme.success, &self.success, &other.success),
+            (
+                &mut theme.destructive,
+                &self.destructive,
+                &other.destructive,
+            ),
+            (&mut theme.warning, &self.warning, &other.warning),
+            (
+                &mut theme.accent_button,
+                &self.accent_button,
+                &other.accent_button,
+            ),
+            (
+                &mut theme.success_button,
+                &self.success_button,
+                &other.success_button,
+            ),
+            (
+                &mut theme.destructive_button,
+                &self.destructive_button,
+                &other.destructive_button,
+            ),
+            (
+                &mut theme.warning_button,
+                &self.warning_button,
+                &other.warning_button,
+            ),
+            (
+                &mut theme.icon_button,
+                &self.icon_button,
+                &other.icon_button,
+            ),
+            (
+                &mut theme.link_button,
+                &self.link_button,
+                &other.link_button,
+            ),
+            (
+                &mut theme.list_button,
+                &self.list_button,
+                &other.list_button,
+            ),
+            (
+                &mut theme.text_button,
+                &self.text_button,
+                &other.text_button,
+            ),
+            (&mut theme.button, &self.button, &other.button),
+        ] {
+            *component = from.interpolate(to, t);
+        }
+        theme.shade = mix_oklab(self.shade, other.shade, t);
+        if self.accent_text.is_some() || other.accent_text.is_some() {
+            theme.accent_text = Some(mix_oklab(
+                self.accent_text_color(),
+                other.accent_text_color(),
+                t,
+            ));
+        }
+        theme
+    }
+
     /// choose default color palette based on preferred GTK color scheme
     pub fn gtk_prefer_colorscheme() -> Self {
         let gsettings = "/usr/bin/gsettings";
@@ -810,6 +888,8 @@ pub struct ThemeBuilder {
     pub active_hint: u32,
     /// cosmic-comp custom window hint color
     pub window_hint: Option<Srgb>,
+    /// raise the contrast of text which fails this level
+    pub contrast: Option<ContrastLevel>,
 }
 
 impl Default for ThemeBuilder {
@@ -832,6 +912,7 @@ impl Default for ThemeBuilder {
             gaps: (0, 8),
             active_hint: 3,
             window_hint: None,
+            contrast: None,
         }
     }
 }
@@ -954,6 +1035,13 @@ impl ThemeBuilder {
         self
     }
 
+    #[inline]
+    /// raise the contrast of text which fails the level when building
+    pub fn enforce_contrast(mut self, level: ContrastLevel) -> Self {
+        self.contrast = Some(level);
+        self
+    }
+
     #[allow(clippy::too_many_lines)]
     /// build the theme
     pub fn build(self) -> Theme {
@@ -974,6 +1062,7 @@ impl ThemeBuilder {
             active_hint,
             window_hint,
             is_frosted,
+            contrast,
         } = self;
 
         let is_dark = palette.is_dark();
@@ -1350,6 +1439,9 @@ impl ThemeBuilder {
         };
         theme.spacing = spacing;
         theme.corner_radii = corner_radii;
+        if let Some(level) = contrast {
+            theme.enforce_contrast(level);
+        }
         theme
     }
 
@@ -1365,3 +1457,39 @@ impl ThemeBuilder {
         Config::new(LIGHT_THEME_BUILDER_ID, Self::VERSION)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use palette::FromColor;
+
+    use super::*;
+
+    #[test]
+    fn test_interpolate() {
+        let dark = Theme::dark_default();
+        let light = Theme::light_default();
+        let close = |a: Srgba, b: Srgba| {
+            (a.red - b.red).abs() < 0.001
+                && (a.green - b.green).abs() < 0.001
+                && (a.blue - b.blue).abs() < 0.001
+                && (a.alpha - b.alpha).abs() < 0.001
+        };
+
+        let start = dark.interpolate(&light, 0.0);
+        assert!(close(start.background.base, dark.background.base));
+        assert!(close(start.accent_button.on, dark.accent_button.on));
+        assert!(!start.is_dark);
+
+        let end = dark.interpolate(&light, 1.0);
+        assert!(close(end.background.base, light.background.base));
+        assert!(close(
+            end.primary.component.hover,
+            light.primary.component.hover
+        ));
+
+        let mid = dark.interpolate(&light, 0.5);
+        let lightness = |c: Srgba| -> f32 { Oklcha::from_color(c).l };
+        let (from, to) = (lightness(dark.bg_color()), lightness(light.bg_color()));
+        assert!((lightness(mid.bg_color()) - (from + to) / 2.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,268 @@
+use palette::color_difference::EuclideanDistance;
+use palette::{IntoColor, LinSrgb, Oklab, Srgb, Srgba, WithAlpha};
+use serde::{Deserialize, Serialize};
+
+use crate::composite::over;
+use crate::{Component, Container, CosmicPaletteInner, Theme};
+
+/// The smallest OKLab distance at which two semantic colors are told apart at a glance.
+pub const MIN_SEMANTIC_DISTANCE: f32 = 0.1;
+
+/// A color vision deficiency which can be simulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorVisionDeficiency {
+    /// Absence of the long wavelength cones, which confuses red and green.
+    Protanopia,
+    /// Absence of the medium wavelength cones, which confuses red and green.
+    Deuteranopia,
+    /// Absence of the short wavelength cones, which confuses blue and green, and yellow and violet.
+    Tritanopia,
+    /// Absence of color vision, which leaves only lightness.
+    Achromatopsia,
+}
+
+impl ColorVisionDeficiency {
+    /// Every deficiency, in declaration order.
+    pub const ALL: [Self; 4] = [
+        Self::Protanopia,
+        Self::Deuteranopia,
+        Self::Tritanopia,
+        Self::Achromatopsia,
+    ];
+
+    /// Simulates how the color is seen with the deficiency.
+    ///
+    /// Uses the full severity matrices of Machado, Oliveira and Fernandes (2009), applied in
+    /// linear sRGB. The alpha is left unchanged.
+    #[must_use]
+    pub fn simulate(self, c: Srgba) -> Srgba {
+        let linear: LinSrgb = c.color.into_linear();
+        let [r, g, b] = [linear.red, linear.green, linear.blue];
+        let m = match self {
+            Self::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Self::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Self::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+            Self::Achromatopsia => {
+                let y = [0.212_672_9, 0.715_152_2, 0.072_175];
+                [y, y, y]
+            }
+        };
+        let [r, g, b] = m.map(|row| (row[0] * r + row[1] * g + row[2] * b).clamp(0.0, 1.0));
+        Srgb::from_linear(LinSrgb::new(r, g, b)).with_alpha(c.alpha)
+    }
+}
+
+/// Two semantic colors of a theme which cannot be told apart with a color vision deficiency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Indistinguishable {
+    /// The name of the first color, such as `success`.
+    pub first: &'static str,
+    /// The name of the second color.
+    pub second: &'static str,
+    /// The OKLab distance between the simulated colors.
+    pub distance: f32,
+}
+
+impl Theme {
+    /// Simulates how every color of the theme is seen with the deficiency.
+    #[cold]
+    pub fn simulate(&self, deficiency: ColorVisionDeficiency) -> Self {
+        let f = |c: Srgba| deficiency.simulate(c);
+        let f_rgb = |c: Srgb| f(c.into()).color;
+
+        let mut theme = self.clone();
+        for container in [
+            &mut theme.background,
+            &mut theme.primary,
+            &mut theme.secondary,
+        ] {
+            map_container(container, f);
+        }
+        for component in [
+            &mut theme.accent,
+            &mut theme.success,
+            &mut theme.destructive,
+            &mut theme.warning,
+            &mut theme.accent_button,
+            &mut theme.success_button,
+            &mut theme.destructive_button,
+            &mut theme.warning_button,
+            &mut theme.icon_button,
+            &mut theme.link_button,
+            &mut theme.list_button,
+            &mut theme.text_button,
+            &mut theme.button,
+        ] {
+            map_component(component, f);
+        }
+        map_palette(&mut theme.palette, f);
+        theme.shade = f(theme.shade);
+        theme.accent_text = theme.accent_text.map(f);
+        theme.window_hint = theme.window_hint.map(f_rgb);
+        theme.control_tint = theme.control_tint.map(f_rgb);
+        theme.text_tint = theme.text_tint.map(f_rgb);
+        theme
+    }
+
+    /// The pairs of accent, success, warning and destructive colors which are closer than
+    /// [`MIN_SEMANTIC_DISTANCE`] when seen with the deficiency.
+    #[must_use]
+    #[cold]
+    pub fn indistinguishable(&self, deficiency: ColorVisionDeficiency) -> Vec<Indistinguishable> {
+        let bg = self.background.base;
+        let semantic = [
+            ("accent", self.accent.base),
+            ("success", self.success.base),
+            ("warning", self.warning.base),
+            ("destructive", self.destructive.base),
+        ]
+        .map(|(name, c)| {
+            let lab: Oklab = deficiency.simulate(over(c, bg)).color.into_color();
+            (name, lab)
+        });
+
+        let mut pairs = Vec::new();
+        for (i, (first, a)) in semantic.iter().enumerate() {
+            for (second, b) in &semantic[i + 1..] {
+                let distance = a.distance(*b);
+                if distance < MIN_SEMANTIC_DISTANCE {
+                    pairs.push(Indistinguishable {
+                        first,
+                        second,
+                        distance,
+                    });
+                }
+            }
+        }
+        pairs
+    }
+}
+
+fn map_container(c: &mut Container, f: impl Fn(Srgba) -> Srgba + Copy) {
+    for color in [&mut c.base, &mut c.divider, &mut c.on, &mut c.small_widget] {
+        *color = f(*color);
+    }
+    map_component(&mut c.component, f);
+}
+
+fn map_component(c: &mut Component, f: impl Fn(Srgba) -> Srgba) {
+    for color in [
+        &mut c.base,
+        &mut c.hover,
+        &mut c.pressed,
+        &mut c.selected,
+        &mut c.selected_text,
+        &mut c.focus,
+        &mut c.divider,
+        &mut c.on,
+        &mut c.disabled,
+        &mut c.on_disabled,
+        &mut c.border,
+        &mut c.disabled_border,
+    ] {
+        *color = f(*color);
+    }
+}
+
+fn map_palette(p: &mut CosmicPaletteInner, f: impl Fn(Srgba) -> Srgba) {
+    for color in [
+        &mut p.bright_red,
+        &mut p.bright_green,
+        &mut p.bright_orange,
+        &mut p.gray_1,
+        &mut p.gray_2,
+        &mut p.neutral_0,
+        &mut p.neutral_1,
+        &mut p.neutral_2,
+        &mut p.neutral_3,
+        &mut p.neutral_4,
+        &mut p.neutral_5,
+        &mut p.neutral_6,
+        &mut p.neutral_7,
+        &mut p.neutral_8,
+        &mut p.neutral_9,
+        &mut p.neutral_10,
+        &mut p.accent_blue,
+        &mut p.accent_indigo,
+        &mut p.accent_purple,
+        &mut p.accent_pink,
+        &mut p.accent_red,
+        &mut p.accent_orange,
+        &mut p.accent_yellow,
+        &mut p.accent_green,
+        &mut p.accent_warm_grey,
+        &mut p.ext_warm_grey,
+        &mut p.ext_orange,
+        &mut p.ext_yellow,
+        &mut p.ext_blue,
+        &mut p.ext_purple,
+        &mut p.ext_pink,
+        &mut p.ext_indigo,
+    ] {
+        *color = f(*color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate() {
+        let white = Srgba::new(1.0, 1.0, 1.0, 0.5);
+        for deficiency in ColorVisionDeficiency::ALL {
+            let c = deficiency.simulate(white);
+            assert!((c.red - 1.0).abs() < 0.01, "{deficiency:?} {c:?}");
+            assert!((c.green - 1.0).abs() < 0.01, "{deficiency:?} {c:?}");
+            assert!((c.blue - 1.0).abs() < 0.01, "{deficiency:?} {c:?}");
+            assert_eq!(c.alpha, 0.5);
+        }
+
+        let red = Srgba::new(1.0, 0.0, 0.0, 1.0);
+        let gray = ColorVisionDeficiency::Achromatopsia.simulate(red);
+        assert_eq!(gray.red, gray.green);
+        assert_eq!(gray.green, gray.blue);
+
+        let theme = Theme::dark_default().simulate(ColorVisionDeficiency::Achromatopsia);
+        let accent = theme.accent.base;
+        assert!((accent.red - accent.blue).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_indistinguishable() {
+        let theme = Theme::dark_default();
+        let is_flagged = |deficiency, first, second| {
+            theme
+                .indistinguishable(deficiency)
+                .iter()
+                .any(|pair| pair.first == first && pair.second == second)
+        };
+        assert!(is_flagged(
+            ColorVisionDeficiency::Deuteranopia,
+            "success",
+            "destructive"
+        ));
+        assert!(!is_flagged(
+            ColorVisionDeficiency::Deuteranopia,
+            "accent",
+            "warning"
+        ));
+        assert!(!is_flagged(
+            ColorVisionDeficiency::Tritanopia,
+            "success",
+            "destructive"
+        ));
+    }
+}
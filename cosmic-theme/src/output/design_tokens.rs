@@ -0,0 +1,479 @@
+//! Design tokens for web content, as W3C Design Tokens JSON and CSS custom properties.
+//!
+//! Every token has a path of kebab-case names, which is its group and name in the JSON
+//! and, joined by `-` after a `--cosmic-` prefix, its CSS custom property. The paths are:
+//!
+//! - `background`, `primary` and `secondary`: the `base`, `on`, `divider` and
+//!   `small-widget` colors of each container, and the colors of its `component`.
+//! - `accent`, `success`, `warning`, `destructive`, `accent-button`, `success-button`,
+//!   `warning-button`, `destructive-button`, `icon-button`, `link-button`, `list-button`,
+//!   `text-button` and `button`: the `base`, `hover`, `pressed`, `selected`,
+//!   `selected-text`, `focus`, `divider`, `on`, `disabled`, `on-disabled`, `border` and
+//!   `disabled-border` colors of each component.
+//! - `palette`: each color of the palette, such as `palette.accent-blue` or `palette.neutral-5`.
+//! - `shade`, `accent-text` and `window-hint`.
+//! - `space`: each spacing, from `space.none` to `space.xxxl`, in pixels.
+//! - `radius`: the `top-left`, `top-right`, `bottom-right` and `bottom-left` radius of
+//!   each corner radius, from `radius.0` to `radius.xl`, in pixels.
+//!
+//! The CSS also has a `--cosmic-radius-*` shorthand for each corner radius, which can
+//! be used as the value of `border-radius`.
+
+use crate::{Component, Container, Theme};
+use palette::Srgba;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{OutputError, to_rgb_hex, to_rgba, write_atomic};
+
+/// The names of the corners of a corner radius, in the order of its array.
+const CORNERS: [&str; 4] = ["top-left", "top-right", "bottom-right", "bottom-left"];
+
+/// The value of a design token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenValue {
+    /// A color.
+    Color(Srgba),
+    /// A length in pixels.
+    Dimension(f32),
+}
+
+/// A named value of a [`Theme`], for use outside of COSMIC applications.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesignToken {
+    /// The groups of the token followed by its name, such as `["background", "component", "hover"]`.
+    pub path: Vec<&'static str>,
+    /// The value of the token.
+    pub value: TokenValue,
+}
+
+impl DesignToken {
+    /// The name of the CSS custom property of the token, such as
+    /// `--cosmic-background-component-hover`.
+    #[must_use]
+    pub fn css_name(&self) -> String {
+        format!("--cosmic-{}", self.path.join("-"))
+    }
+
+    /// The token as a W3C design token.
+    fn to_json(&self) -> Value {
+        match self.value {
+            TokenValue::Color(c) => json!({
+                "$type": "color",
+                "$value": {
+                    "colorSpace": "srgb",
+                    "components": [round(c.red), round(c.green), round(c.blue)],
+                    "alpha": round(c.alpha),
+                    "hex": format!("#{}", to_rgb_hex(c)),
+                },
+            }),
+            TokenValue::Dimension(px) => json!({
+                "$type": "dimension",
+                "$value": { "value": px, "unit": "px" },
+            }),
+        }
+    }
+
+    /// The value of the CSS custom property of the token.
+    fn to_css(&self) -> String {
+        match self.value {
+            TokenValue::Color(c) => to_rgba(c),
+            TokenValue::Dimension(px) => format!("{px}px"),
+        }
+    }
+}
+
+impl Theme {
+    /// The design tokens of every color, spacing and corner radius of the theme.
+    ///
+    /// See the module documentation for the paths of the tokens.
+    #[must_use]
+    #[cold]
+    pub fn design_tokens(&self) -> Vec<DesignToken> {
+        let mut tokens = Vec::new();
+        let mut color = |path: Vec<&'static str>, c: Srgba| {
+            tokens.push(DesignToken {
+                path,
+                value: TokenValue::Color(c),
+            });
+        };
+
+        for (name, container) in [
+            ("background", &self.background),
+            ("primary", &self.primary),
+            ("secondary", &self.secondary),
+        ] {
+            for (field, c) in container_colors(container) {
+                color(vec![name, field], c);
+            }
+            for (field, c) in component_colors(&container.component) {
+                color(vec![name, "component", field], c);
+            }
+        }
+
+        for (name, component) in [
+            ("accent", &self.accent),
+            ("success", &self.success),
+            ("warning", &self.warning),
+            ("destructive", &self.destructive),
+            ("accent-button", &self.accent_button),
+            ("success-button", &self.success_button),
+            ("warning-button", &self.warning_button),
+            ("destructive-button", &self.destructive_button),
+            ("icon-button", &self.icon_button),
+            ("link-button", &self.link_button),
+            ("list-button", &self.list_button),
+            ("text-button", &self.text_button),
+            ("button", &self.button),
+        ] {
+            for (field, c) in component_colors(component) {
+                color(vec![name, field], c);
+            }
+        }
+
+        let p = &self.palette;
+        for (name, c) in [
+            ("bright-red", p.bright_red),
+            ("bright-green", p.bright_green),
+            ("bright-orange", p.bright_orange),
+            ("gray-1", p.gray_1),
+            ("gray-2", p.gray_2),
+            ("neutral-0", p.neutral_0),
+            ("neutral-1", p.neutral_1),
+            ("neutral-2", p.neutral_2),
+            ("neutral-3", p.neutral_3),
+            ("neutral-4", p.neutral_4),
+            ("neutral-5", p.neutral_5),
+            ("neutral-6", p.neutral_6),
+            ("neutral-7", p.neutral_7),
+            ("neutral-8", p.neutral_8),
+            ("neutral-9", p.neutral_9),
+            ("neutral-10", p.neutral_10),
+            ("accent-blue", p.accent_blue),
+            ("accent-indigo", p.accent_indigo),
+            ("accent-purple", p.accent_purple),
+            ("accent-pink", p.accent_pink),
+            ("accent-red", p.accent_red),
+            ("accent-orange", p.accent_orange),
+            ("accent-yellow", p.accent_yellow),
+            ("accent-green", p.accent_green),
+            ("accent-warm-grey", p.accent_warm_grey),
+            ("ext-warm-grey", p.ext_warm_grey),
+            ("ext-orange", p.ext_orange),
+            ("ext-yellow", p.ext_yellow),
+            ("ext-blue", p.ext_blue),
+            ("ext-purple", p.ext_purple),
+            ("ext-pink", p.ext_pink),
+            ("ext-indigo", p.ext_indigo),
+        ] {
+            color(vec!["palette", name], c);
+        }
+
+        color(vec!["shade"], self.shade);
+        color(vec!["accent-text"], self.accent_text_color());
+        color(
+            vec!["window-hint"],
+            self.window_hint.map_or(self.accent.base, Srgba::from),
+        );
+
+        let s = &self.spacing;
+        for (name, space) in [
+            ("none", s.space_none),
+            ("xxxs", s.space_xxxs),
+            ("xxs", s.space_xxs),
+            ("xs", s.space_xs),
+            ("s", s.space_s),
+            ("m", s.space_m),
+            ("l", s.space_l),
+            ("xl", s.space_xl),
+            ("xxl", s.space_xxl),
+            ("xxxl", s.space_xxxl),
+        ] {
+            tokens.push(DesignToken {
+                path: vec!["space", name],
+                value: TokenValue::Dimension(f32::from(space)),
+            });
+        }
+
+        for (name, radius) in radii(self) {
+            for (corner, px) in CORNERS.into_iter().zip(radius) {
+                tokens.push(DesignToken {
+                    path: vec!["radius", name, corner],
+                    value: TokenValue::Dimension(px),
+                });
+            }
+        }
+
+        tokens
+    }
+
+    /// The design tokens of the theme in the W3C Design Tokens JSON format.
+    ///
+    /// Two tokens must not have the same path, nor may a token be the group of another.
+    /// Debug builds assert this, and release builds keep the first of such tokens.
+    #[must_use]
+    #[cold]
+    pub fn as_design_tokens_json(&self) -> String {
+        let mut root = Map::new();
+        'tokens: for token in self.design_tokens() {
+            let Some((name, groups)) = token.path.split_last() else {
+                continue;
+            };
+            let mut group = &mut root;
+            for g in groups {
+                let entry = group.entry(*g).or_insert_with(|| Value::Object(Map::new()));
+                debug_assert!(entry.is_object(), "{} is a token", token.path.join("."));
+                let Some(next) = entry.as_object_mut() else {
+                    continue 'tokens;
+                };
+                group = next;
+            }
+            let vacant = !group.contains_key(*name);
+            debug_assert!(vacant, "duplicate token {}", token.path.join("."));
+            if vacant {
+                group.insert((*name).to_owned(), token.to_json());
+            }
+        }
+
+        let mut json = serde_json::to_string_pretty(&Value::Object(root)).unwrap();
+        json.push('\n');
+        json
+    }
+
+    /// The design tokens of the theme as CSS custom properties of `:root`.
+    #[must_use]
+    #[cold]
+    pub fn as_design_tokens_css(&self) -> String {
+        let mut css = String::from("/* GENERATED BY COSMIC */\n:root {\n");
+        for token in self.design_tokens() {
+            css.push_str(&format!("  {}: {};\n", token.css_name(), token.to_css()));
+        }
+        for (name, _) in radii(self) {
+            let corners = CORNERS.map(|corner| format!("var(--cosmic-radius-{name}-{corner})"));
+            css.push_str(&format!(
+                "  --cosmic-radius-{name}: {};\n",
+                corners.join(" ")
+            ));
+        }
+        css.push_str("}\n");
+        css
+    }
+
+    /// Write the design tokens to the XDG data directory, as the `dark` or `light`
+    /// tokens of `design-tokens/cosmic`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error writing the tokens.
+    #[cold]
+    pub fn write_design_tokens(&self) -> Result<(), OutputError> {
+        let cosmic_dir = design_tokens_dir()?.join("cosmic");
+        if !cosmic_dir.exists() {
+            fs::create_dir_all(&cosmic_dir).map_err(OutputError::Io)?;
+        }
+
+        let name = if self.is_dark { "dark" } else { "light" };
+        for (extension, content) in [
+            ("tokens.json", self.as_design_tokens_json()),
+            ("css", self.as_design_tokens_css()),
+        ] {
+            write_atomic(&cosmic_dir.join(format!("{name}.{extension}")), &content)
+                .map_err(OutputError::Io)?;
+        }
+
+        Ok(())
+    }
+
+    /// Link the dark or light design tokens as `design-tokens/cosmic.tokens.json` and
+    /// `design-tokens/cosmic.css` in the XDG data directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error linking the tokens.
+    #[cold]
+    pub fn apply_design_tokens(is_dark: bool) -> Result<(), OutputError> {
+        let dir = design_tokens_dir()?;
+        let cosmic_dir = dir.join("cosmic");
+        fs::create_dir_all(&cosmic_dir).map_err(OutputError::Io)?;
+
+        #[cfg(target_family = "unix")]
+        for extension in ["tokens.json", "css"] {
+            use std::os::unix::fs::symlink;
+
+            let tokens = cosmic_dir.join(format!(
+                "{}.{extension}",
+                if is_dark { "dark" } else { "light" }
+            ));
+            let active = dir.join(format!("cosmic.{extension}"));
+
+            // Never replace tokens which the user put in place of ours.
+            if Self::is_cosmic_css(&active, &cosmic_dir).map_err(OutputError::Io)? == Some(false) {
+                continue;
+            }
+            if fs::symlink_metadata(&active).is_ok() {
+                fs::remove_file(&active).map_err(OutputError::Io)?;
+            }
+            symlink(&tokens, &active).map_err(OutputError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the design tokens linked by us.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error removing the tokens.
+    #[cold]
+    pub fn reset_design_tokens() -> Result<(), OutputError> {
+        Self::reset_design_tokens_in(&design_tokens_dir()?)
+    }
+
+    #[cold]
+    fn reset_design_tokens_in(dir: &Path) -> Result<(), OutputError> {
+        let cosmic_dir = dir.join("cosmic");
+
+        for extension in ["tokens.json", "css"] {
+            let active = dir.join(format!("cosmic.{extension}"));
+            let is_cosmic =
+                match Self::is_cosmic_css(&active, &cosmic_dir).map_err(OutputError::Io)? {
+                    Some(is_cosmic) => is_cosmic,
+                    // A dangling link to tokens of ours which were removed since.
+                    None => fs::read_link(&active)
+                        .is_ok_and(|target| dir.join(target).starts_with(&cosmic_dir)),
+                };
+            if is_cosmic {
+                fs::remove_file(&active).map_err(OutputError::Io)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The directory of design tokens in the XDG data directory.
+fn design_tokens_dir() -> Result<PathBuf, OutputError> {
+    dirs::data_dir()
+        .map(|dir| dir.join("design-tokens"))
+        .ok_or(OutputError::MissingDataDir)
+}
+
+fn container_colors(c: &Container) -> [(&'static str, Srgba); 4] {
+    [
+        ("base", c.base),
+        ("on", c.on),
+        ("divider", c.divider),
+        ("small-widget", c.small_widget),
+    ]
+}
+
+fn component_colors(c: &Component) -> [(&'static str, Srgba); 12] {
+    [
+        ("base", c.base),
+        ("hover", c.hover),
+        ("pressed", c.pressed),
+        ("selected", c.selected),
+        ("selected-text", c.selected_text),
+        ("focus", c.focus),
+        ("divider", c.divider),
+        ("on", c.on),
+        ("disabled", c.disabled),
+        ("on-disabled", c.on_disabled),
+        ("border", c.border),
+        ("disabled-border", c.disabled_border),
+    ]
+}
+
+fn radii(theme: &Theme) -> [(&'static str, [f32; 4]); 6] {
+    let r = &theme.corner_radii;
+    [
+        ("0", r.radius_0),
+        ("xs", r.radius_xs),
+        ("s", r.radius_s),
+        ("m", r.radius_m),
+        ("l", r.radius_l),
+        ("xl", r.radius_xl),
+    ]
+}
+
+/// Rounds a color component to 4 decimal places, which is finer than 8 bits per channel.
+fn round(x: f32) -> f64 {
+    (f64::from(x) * 10_000.0).round() / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_design_tokens() {
+        let theme = Theme::dark_default();
+        let tokens = theme.design_tokens();
+        // No token is a duplicate of another, or a group of other tokens.
+        for (i, token) in tokens.iter().enumerate() {
+            assert!(tokens[i + 1..].iter().all(|t| t.path != token.path));
+            assert!(
+                tokens
+                    .iter()
+                    .all(|t| !t.path.starts_with(&token.path) || t.path == token.path),
+                "{:?}",
+                token.path
+            );
+        }
+
+        let hover = tokens
+            .iter()
+            .find(|t| t.css_name() == "--cosmic-background-component-hover")
+            .unwrap();
+        assert_eq!(
+            hover.value,
+            TokenValue::Color(theme.background.component.hover)
+        );
+
+        let json: Value = serde_json::from_str(&theme.as_design_tokens_json()).unwrap();
+        assert_eq!(json["space"]["m"]["$value"]["value"], 24.0);
+        assert_eq!(json["radius"]["s"]["top-left"]["$type"], "dimension");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_reset_dangling_design_tokens() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cosmic_dir = dir.path().join("cosmic");
+        let json = dir.path().join("cosmic.tokens.json");
+        let css = dir.path().join("cosmic.css");
+
+        // Our link is removed after the tokens it links to were removed, but not a
+        // dangling link of the user.
+        symlink(cosmic_dir.join("dark.tokens.json"), &json).unwrap();
+        symlink(dir.path().join("mine.css"), &css).unwrap();
+        Theme::reset_design_tokens_in(dir.path()).unwrap();
+        assert!(fs::symlink_metadata(&json).is_err());
+        assert!(fs::symlink_metadata(&css).is_ok());
+    }
+
+    #[test]
+    fn test_light_default_design_tokens_json() {
+        let light_default_design_tokens_json = Theme::light_default().as_design_tokens_json();
+        insta::assert_snapshot!(light_default_design_tokens_json);
+    }
+
+    #[test]
+    fn test_dark_default_design_tokens_json() {
+        let dark_default_design_tokens_json = Theme::dark_default().as_design_tokens_json();
+        insta::assert_snapshot!(dark_default_design_tokens_json);
+    }
+
+    #[test]
+    fn test_light_default_design_tokens_css() {
+        let light_default_design_tokens_css = Theme::light_default().as_design_tokens_css();
+        insta::assert_snapshot!(light_default_design_tokens_css);
+    }
+
+    #[test]
+    fn test_dark_default_design_tokens_css() {
+        let dark_default_design_tokens_css = Theme::dark_default().as_design_tokens_css();
+        insta::assert_snapshot!(dark_default_design_tokens_css);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::Theme;
+use palette::WithAlpha;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{OutputError, backup_path, to_rgba, write_atomic};
+
+impl Theme {
+    #[must_use]
+    #[cold]
+    /// turn the theme into css for gtk3
+    ///
+    /// Includes the libadwaita colors of [`Theme::as_gtk4`], which are read by
+    /// adw-gtk3, followed by the colors of the Adwaita gtk3 theme.
+    pub fn as_gtk3(&self) -> String {
+        let Self {
+            background,
+            primary,
+            accent,
+            destructive,
+            warning,
+            success,
+            ..
+        } = self;
+
+        let theme_bg = to_rgba(background.base);
+        let theme_fg = to_rgba(background.on);
+
+        let theme_base = to_rgba(primary.base);
+        let theme_text = to_rgba(primary.on);
+
+        let theme_selected_bg = to_rgba(accent.base);
+        let theme_selected_fg = to_rgba(accent.on);
+
+        let insensitive_bg = to_rgba(background.component.disabled);
+        let insensitive_fg = to_rgba(background.component.on_disabled);
+        let insensitive_base = to_rgba(primary.base);
+
+        let borders = to_rgba(background.divider);
+
+        let warning = to_rgba(warning.base);
+        let error = to_rgba(destructive.base);
+        let success = to_rgba(success.base);
+
+        let wm_title = to_rgba(background.on);
+        let wm_unfocused_title = to_rgba(background.on.with_alpha(0.5));
+
+        let mut css = self.as_gtk4();
+        css.push_str(&format! {r#"
+@define-color theme_bg_color {theme_bg};
+@define-color theme_fg_color {theme_fg};
+@define-color theme_base_color {theme_base};
+@define-color theme_text_color {theme_text};
+@define-color theme_selected_bg_color {theme_selected_bg};
+@define-color theme_selected_fg_color {theme_selected_fg};
+
+@define-color theme_unfocused_bg_color {theme_bg};
+@define-color theme_unfocused_fg_color {theme_fg};
+@define-color theme_unfocused_base_color {theme_base};
+@define-color theme_unfocused_text_color {theme_text};
+@define-color theme_unfocused_selected_bg_color {theme_selected_bg};
+@define-color theme_unfocused_selected_fg_color {theme_selected_fg};
+
+@define-color insensitive_bg_color {insensitive_bg};
+@define-color insensitive_fg_color {insensitive_fg};
+@define-color insensitive_base_color {insensitive_base};
+@define-color unfocused_insensitive_color {insensitive_fg};
+
+@define-color borders {borders};
+@define-color unfocused_borders {borders};
+
+@define-color warning_color {warning};
+@define-color error_color {error};
+@define-color success_color {success};
+
+@define-color content_view_bg {theme_base};
+@define-color text_view_bg {theme_base};
+
+@define-color wm_title {wm_title};
+@define-color wm_unfocused_title {wm_unfocused_title};
+@define-color wm_bg {theme_bg};
+@define-color wm_unfocused_bg {theme_bg};
+"#});
+        css
+    }
+
+    /// write the gtk3 CSS to the appropriate directory
+    /// Should be written in the XDG config directory for gtk-3.0
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error writing the CSS file.
+    #[cold]
+    pub fn write_gtk3(&self) -> Result<(), OutputError> {
+        let css_str = self.as_gtk3();
+        let Some(mut config_dir) = dirs::config_dir() else {
+            return Err(OutputError::MissingConfigDir);
+        };
+
+        let name = if self.is_dark {
+            "dark.css"
+        } else {
+            "light.css"
+        };
+
+        config_dir.extend(["gtk-3.0", "cosmic"]);
+        if !config_dir.exists() {
+            std::fs::create_dir_all(&config_dir).map_err(OutputError::Io)?;
+        }
+
+        write_atomic(&config_dir.join(name), &css_str).map_err(OutputError::Io)
+    }
+
+    /// Apply gtk3 color variable settings
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error applying the CSS file.
+    #[cold]
+    pub fn apply_gtk3(is_dark: bool) -> Result<(), OutputError> {
+        let Some(config_dir) = dirs::config_dir() else {
+            return Err(OutputError::MissingConfigDir);
+        };
+
+        Self::apply_gtk3_in(&config_dir, is_dark)
+    }
+
+    #[cold]
+    fn apply_gtk3_in(config_dir: &Path, is_dark: bool) -> Result<(), OutputError> {
+        let gtk3 = config_dir.join("gtk-3.0");
+        fs::create_dir_all(&gtk3).map_err(OutputError::Io)?;
+
+        let cosmic_css_dir = gtk3.join("cosmic");
+        let cosmic_css = cosmic_css_dir.join(if is_dark { "dark.css" } else { "light.css" });
+        let gtk3_dest = gtk3.join("gtk.css");
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::symlink;
+            if !Self::is_cosmic_gtk3_css(&gtk3_dest, config_dir)
+                .map_err(OutputError::Io)?
+                .unwrap_or(true)
+            {
+                fs::rename(&gtk3_dest, backup_path(&gtk3_dest)).map_err(OutputError::Io)?;
+            }
+
+            if fs::symlink_metadata(&gtk3_dest).is_ok() {
+                fs::remove_file(&gtk3_dest).map_err(OutputError::Io)?;
+            }
+
+            symlink(&cosmic_css, &gtk3_dest).map_err(OutputError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Reset the applied gtk3 css
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error resetting the CSS file.
+    #[cold]
+    pub fn reset_gtk3() -> Result<(), OutputError> {
+        let Some(config_dir) = dirs::config_dir() else {
+            return Err(OutputError::MissingConfigDir);
+        };
+
+        Self::reset_gtk3_in(&config_dir)
+    }
+
+    #[cold]
+    fn reset_gtk3_in(config_dir: &Path) -> Result<(), OutputError> {
+        let gtk3_dest = config_dir.join("gtk-3.0").join("gtk.css");
+        if Self::is_cosmic_gtk3_css(&gtk3_dest, config_dir)
+            .map_err(OutputError::Io)?
+            .unwrap_or_default()
+        {
+            fs::remove_file(&gtk3_dest).map_err(OutputError::Io)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the gtk3 css links to our gtk3 css, or to our gtk4 css which was
+    /// linked in its place before the gtk3 css existed.
+    ///
+    /// Returns `None` if there is no gtk3 css, or if it is a dangling link to somewhere
+    /// else, which is not worth a backup either.
+    fn is_cosmic_gtk3_css(path: &Path, config_dir: &Path) -> io::Result<Option<bool>> {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return Ok(None);
+        };
+
+        let cosmic_dirs = [
+            config_dir.join("gtk-3.0").join("cosmic"),
+            config_dir.join("gtk-4.0").join("cosmic"),
+        ];
+
+        if metadata.file_type().is_symlink() && !path.exists() {
+            let target = fs::read_link(path)?;
+            let target = path
+                .parent()
+                .map_or(target.clone(), |parent| parent.join(&target));
+            return Ok(cosmic_dirs
+                .iter()
+                .any(|cosmic_css| target.starts_with(cosmic_css))
+                .then_some(true));
+        }
+
+        for cosmic_css in cosmic_dirs {
+            if cosmic_css.exists() && Self::is_cosmic_css(path, &cosmic_css)? == Some(true) {
+                return Ok(Some(true));
+            }
+        }
+        Ok(Some(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_default_gtk3() {
+        let light_default_gtk3 = Theme::light_default().as_gtk3();
+        insta::assert_snapshot!(light_default_gtk3);
+    }
+
+    #[test]
+    fn test_dark_default_gtk3() {
+        let dark_default_gtk3 = Theme::dark_default().as_gtk3();
+        insta::assert_snapshot!(dark_default_gtk3);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_apply_gtk3_dangling_link() {
+        use std::os::unix::fs::symlink;
+
+        let config_dir = tempfile::tempdir().unwrap();
+        let gtk3 = config_dir.path().join("gtk-3.0");
+        fs::create_dir_all(&gtk3).unwrap();
+        let gtk_css = gtk3.join("gtk.css");
+        let backup = gtk3.join("gtk.css.bak");
+        fs::write(&backup, "/* the user's own css */").unwrap();
+
+        // A link to our css, which was removed since, is replaced without a backup.
+        symlink(gtk3.join("cosmic").join("light.css"), &gtk_css).unwrap();
+        assert_eq!(
+            Theme::is_cosmic_gtk3_css(&gtk_css, config_dir.path()).unwrap(),
+            Some(true)
+        );
+        Theme::apply_gtk3_in(config_dir.path(), true).unwrap();
+        assert_eq!(
+            fs::read_link(&gtk_css).unwrap(),
+            gtk3.join("cosmic").join("dark.css")
+        );
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            "/* the user's own css */"
+        );
+        assert!(!gtk3.join("gtk.css.bak.1").exists());
+
+        // So is a dangling link to elsewhere, which is not ours to remove on reset.
+        fs::remove_file(&gtk_css).unwrap();
+        symlink(config_dir.path().join("missing.css"), &gtk_css).unwrap();
+        assert_eq!(
+            Theme::is_cosmic_gtk3_css(&gtk_css, config_dir.path()).unwrap(),
+            None
+        );
+        Theme::reset_gtk3_in(config_dir.path()).unwrap();
+        assert!(fs::symlink_metadata(&gtk_css).is_ok());
+        Theme::apply_gtk3_in(config_dir.path(), true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            "/* the user's own css */"
+        );
+
+        // A css of the user is backed up next to the earlier backup.
+        fs::remove_file(&gtk_css).unwrap();
+        fs::write(&gtk_css, "/* newer css */").unwrap();
+        Theme::apply_gtk3_in(config_dir.path(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            "/* the user's own css */"
+        );
+        assert_eq!(
+            fs::read_to_string(gtk3.join("gtk.css.bak.1")).unwrap(),
+            "/* newer css */"
+        );
+
+        // Our dangling link is removed on reset.
+        Theme::reset_gtk3_in(config_dir.path()).unwrap();
+        assert!(fs::symlink_metadata(&gtk_css).is_err());
+    }
+}
@@ -8,7 +8,7 @@ use std::io::{self, Write};
 use std::num::NonZeroUsize;
 use std::path::Path;
 
-use super::{OutputError, to_rgba};
+use super::{OutputError, backup_path, to_rgba};
 
 impl Theme {
     #[must_use]
@@ -193,27 +193,24 @@ impl Theme {
         };
 
         let mut gtk4 = config_dir.join("gtk-4.0");
-        let mut gtk3 = config_dir.join("gtk-3.0");
 
         fs::create_dir_all(&gtk4).map_err(OutputError::Io)?;
-        fs::create_dir_all(&gtk3).map_err(OutputError::Io)?;
 
         let cosmic_css_dir = gtk4.join("cosmic");
         let cosmic_css = cosmic_css_dir.join(if is_dark { "dark.css" } else { "light.css" });
 
         gtk4.push("gtk.css");
-        gtk3.push("gtk.css");
 
         #[cfg(target_family = "unix")]
-        for gtk_dest in [&gtk4, &gtk3] {
+        {
             use std::os::unix::fs::symlink;
-            Self::backup_non_cosmic_css(gtk_dest, &cosmic_css_dir).map_err(OutputError::Io)?;
+            Self::backup_non_cosmic_css(&gtk4, &cosmic_css_dir).map_err(OutputError::Io)?;
 
-            if gtk_dest.exists() {
-                fs::remove_file(gtk_dest).map_err(OutputError::Io)?;
+            if fs::symlink_metadata(&gtk4).is_ok() {
+                fs::remove_file(&gtk4).map_err(OutputError::Io)?;
             }
 
-            symlink(&cosmic_css, gtk_dest).map_err(OutputError::Io)?;
+            symlink(&cosmic_css, &gtk4).map_err(OutputError::Io)?;
         }
         Ok(())
     }
@@ -230,21 +227,16 @@ impl Theme {
         };
 
         let gtk4 = config_dir.join("gtk-4.0");
-        let gtk3 = config_dir.join("gtk-3.0");
         let gtk4_dest = gtk4.join("gtk.css");
         let cosmic_css = gtk4.join("cosmic");
-        let gtk3_dest = gtk3.join("gtk.css");
 
-        let res = Self::reset_cosmic_css(&gtk3_dest, &cosmic_css).map_err(OutputError::Io);
-        Self::reset_cosmic_css(&gtk4_dest, &cosmic_css).map_err(OutputError::Io)?;
-        res
+        Self::reset_cosmic_css(&gtk4_dest, &cosmic_css).map_err(OutputError::Io)
     }
 
     #[cold]
     fn backup_non_cosmic_css(path: &Path, cosmic_css: &Path) -> io::Result<()> {
         if !Self::is_cosmic_css(path, cosmic_css)?.unwrap_or(true) {
-            let backup_path = path.with_extension("css.bak");
-            fs::rename(path, &backup_path)?;
+            fs::rename(path, backup_path(path))?;
         }
         Ok(())
     }
@@ -257,7 +249,7 @@ impl Theme {
         Ok(())
     }
 
-    fn is_cosmic_css(path: &Path, cosmic_css: &Path) -> io::Result<Option<bool>> {
+    pub(super) fn is_cosmic_css(path: &Path, cosmic_css: &Path) -> io::Result<Option<bool>> {
         if !path.exists() {
             return Ok(None);
         }
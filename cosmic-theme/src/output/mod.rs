@@ -1,10 +1,17 @@
 use configparser::ini::WriteOptions;
 use palette::Srgba;
 use palette::rgb::Rgba;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use crate::Theme;
 
+/// Module for outputting the Cosmic theme as design tokens for web content
+pub mod design_tokens;
+/// Module for outputting the Cosmic gtk3 theme type as CSS
+pub mod gtk3_output;
 /// Module for outputting the Cosmic gtk4 theme type as CSS
 pub mod gtk4_output;
 
@@ -12,6 +19,8 @@ pub mod gtk4_output;
 pub mod qt56ct_output;
 /// Module for outputting the Cosmic qt theme type as kdeglobals
 pub mod qt_output;
+/// Module for outputting the Cosmic theme as terminal emulator palettes
+pub mod terminal_output;
 
 pub mod vs_code;
 
@@ -31,38 +40,61 @@ pub enum OutputError {
 
 impl Theme {
     #[inline]
-    /// Apply COSMIC theme exports for GTK and Qt applications.
+    /// Apply COSMIC theme exports for GTK and Qt applications, terminal emulators, and
+    /// web content.
+    ///
+    /// The config files of terminal emulators are not edited, which is opted into with
+    /// [`Theme::include_terminal_themes`].
     pub fn apply_exports(&self) -> Result<(), OutputError> {
         let gtk_res = Theme::apply_gtk(self.is_dark);
+        let gtk3_res = Theme::apply_gtk3(self.is_dark);
         let qt_res = Theme::apply_qt(self.is_dark);
         let qt56ct_res = Theme::apply_qt56ct(self.is_dark);
+        let terminal_res = Theme::apply_terminals(self.is_dark);
+        let tokens_res = Theme::apply_design_tokens(self.is_dark);
         gtk_res?;
+        gtk3_res?;
         qt_res?;
         qt56ct_res?;
+        terminal_res?;
+        tokens_res?;
         Ok(())
     }
 
     #[inline]
-    /// Write COSMIC theme exports for GTK and Qt applications.
+    /// Write COSMIC theme exports for GTK and Qt applications, terminal emulators, and
+    /// web content.
     pub fn write_exports(&self) -> Result<(), OutputError> {
         let gtk_res = self.write_gtk4();
+        let gtk3_res = self.write_gtk3();
         let qt_res = self.write_qt();
         let qt56ct_res = self.write_qt56ct();
+        let terminal_res = self.write_terminals();
+        let tokens_res = self.write_design_tokens();
         gtk_res?;
+        gtk3_res?;
         qt_res?;
         qt56ct_res?;
+        terminal_res?;
+        tokens_res?;
         Ok(())
     }
 
     #[inline]
-    /// Un-export GTK and Qt theme configurations applied by us.
+    /// Un-export GTK, Qt, terminal and design token theme configurations applied by us.
     pub fn reset_exports() -> Result<(), OutputError> {
         let gtk_res = Theme::reset_gtk();
+        let gtk3_res = Theme::reset_gtk3();
         let qt_res = Theme::reset_qt();
         let qt56ct_res = Theme::reset_qt56ct();
+        let terminal_res = Theme::reset_terminals();
+        let tokens_res = Theme::reset_design_tokens();
         gtk_res?;
+        gtk3_res?;
         qt_res?;
         qt56ct_res?;
+        terminal_res?;
+        tokens_res?;
         Ok(())
     }
 }
@@ -75,6 +107,12 @@ pub fn to_hex(c: Srgba) -> String {
     )
 }
 
+/// Converts a color to a hex string in the format `RRGGBB`, ignoring its alpha.
+pub(crate) fn to_rgb_hex(c: Srgba) -> String {
+    let c_u8: Rgba<palette::encoding::Srgb, u8> = c.into_format();
+    format!("{:02x}{:02x}{:02x}", c_u8.red, c_u8.green, c_u8.blue)
+}
+
 pub fn to_rgba(c: Srgba) -> String {
     let c_u8: Rgba<palette::encoding::Srgb, u8> = c.into_format();
     format!(
@@ -83,8 +121,74 @@ pub fn to_rgba(c: Srgba) -> String {
     )
 }
 
+/// Replaces the content of a file through a temporary file, so that an interrupted
+/// write never leaves it truncated. A link to the file, as dotfile managers create,
+/// is kept by writing to its target.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => path.to_owned(),
+        Err(e) => return Err(e),
+    };
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push("~");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut tmp_file = File::create(&tmp_path)?;
+    let res = tmp_file
+        .write_all(content.as_bytes())
+        .and_then(|()| tmp_file.flush())
+        .and_then(|()| fs::rename(&tmp_path, &path));
+    if res.is_err() {
+        _ = fs::remove_file(&tmp_path);
+    }
+    res
+}
+
+/// The first of `gtk.css.bak`, `gtk.css.bak.1`, `gtk.css.bak.2`, ... which does not
+/// exist yet, so that a backup never replaces an earlier one.
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    std::iter::once(path.with_extension("css.bak"))
+        .chain((1..).map(|n| path.with_extension(format!("css.bak.{n}"))))
+        .find(|backup| fs::symlink_metadata(backup).is_err())
+        .unwrap()
+}
+
 pub fn qt_settings_ini_style() -> WriteOptions {
     let mut write_options = WriteOptions::default();
     write_options.blank_lines_between_sections = 1;
     write_options
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let dotfile = dir.path().join("dotfiles-foot.ini");
+        let config_file = dir.path().join("foot.ini");
+
+        write_atomic(&config_file, "font=monospace\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(&config_file).unwrap(),
+            "font=monospace\n"
+        );
+
+        // A linked config file is written through the link, which is kept.
+        fs::remove_file(&config_file).unwrap();
+        fs::write(&dotfile, "font=monospace\n").unwrap();
+        std::os::unix::fs::symlink(&dotfile, &config_file).unwrap();
+        write_atomic(&config_file, "include=cosmic.ini\n").unwrap();
+        assert!(fs::symlink_metadata(&config_file).unwrap().is_symlink());
+        assert_eq!(
+            fs::read_to_string(&dotfile).unwrap(),
+            "include=cosmic.ini\n"
+        );
+
+        let files = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(files, 2);
+    }
+}
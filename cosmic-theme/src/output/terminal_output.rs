@@ -0,0 +1,596 @@
+use crate::Theme;
+use palette::blend::Compose;
+use palette::{Mix, Srgba};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{OutputError, to_rgb_hex, write_atomic};
+
+/// The colors of a terminal emulator, derived from a [`Theme`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalPalette {
+    /// The default text color.
+    pub foreground: Srgba,
+    /// The default background color.
+    pub background: Srgba,
+    /// The color of the cursor.
+    pub cursor: Srgba,
+    /// The color of text under the cursor.
+    pub cursor_text: Srgba,
+    /// The color of selected text.
+    pub selection_foreground: Srgba,
+    /// The background color of selected text.
+    pub selection_background: Srgba,
+    /// ANSI colors 0 to 7: black, red, green, yellow, blue, magenta, cyan and white.
+    pub normal: [Srgba; 8],
+    /// ANSI colors 8 to 15, the bright variants of [`TerminalPalette::normal`].
+    pub bright: [Srgba; 8],
+}
+
+impl TerminalPalette {
+    /// The 16 ANSI colors, in order.
+    pub fn ansi(&self) -> impl Iterator<Item = Srgba> + '_ {
+        self.normal.iter().chain(&self.bright).copied()
+    }
+}
+
+/// The terminal emulators which COSMIC exports a theme for.
+#[derive(Debug, Clone, Copy)]
+enum Terminal {
+    Alacritty,
+    Kitty,
+    Foot,
+    WezTerm,
+}
+
+impl Terminal {
+    const ALL: [Self; 4] = [Self::Alacritty, Self::Kitty, Self::Foot, Self::WezTerm];
+
+    /// The name of the terminal's directory in the XDG config directory.
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Alacritty => "alacritty",
+            Self::Kitty => "kitty",
+            Self::Foot => "foot",
+            Self::WezTerm => "wezterm",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Alacritty | Self::WezTerm => "toml",
+            Self::Kitty => "conf",
+            Self::Foot => "ini",
+        }
+    }
+
+    /// The config file of the terminal which includes the active theme, if it has one we can edit.
+    fn config_file(self) -> Option<&'static str> {
+        match self {
+            Self::Alacritty => Some("alacritty.toml"),
+            Self::Kitty => Some("kitty.conf"),
+            Self::Foot => Some("foot.ini"),
+            // WezTerm is configured with Lua, and picks up color schemes by name instead.
+            Self::WezTerm => None,
+        }
+    }
+
+    /// The path of the link to the active theme, relative to the terminal's config directory.
+    fn active_theme(self) -> &'static str {
+        match self {
+            Self::Alacritty => "cosmic.toml",
+            Self::Kitty => "cosmic.conf",
+            Self::Foot => "cosmic.ini",
+            Self::WezTerm => "colors/COSMIC.toml",
+        }
+    }
+
+    /// The line which includes the active theme from the terminal's config file.
+    fn include_line(self, active_theme: &Path) -> String {
+        match self {
+            Self::Alacritty => format!("general.import = [\"{}\"]", active_theme.display()),
+            Self::Kitty => format!("include {}", active_theme.display()),
+            Self::Foot => format!("include={}", active_theme.display()),
+            Self::WezTerm => String::new(),
+        }
+    }
+
+    fn export(self, theme: &Theme) -> String {
+        match self {
+            Self::Alacritty => theme.as_alacritty(),
+            Self::Kitty => theme.as_kitty(),
+            Self::Foot => theme.as_foot(),
+            Self::WezTerm => theme.as_wezterm(),
+        }
+    }
+
+    /// The terminal's config directory, if the terminal has been configured by the user.
+    fn config_dir(self) -> Result<Option<PathBuf>, OutputError> {
+        let Some(config_dir) = dirs::config_dir() else {
+            return Err(OutputError::MissingConfigDir);
+        };
+        let dir = config_dir.join(self.dir_name());
+        Ok(dir.is_dir().then_some(dir))
+    }
+}
+
+impl Theme {
+    /// Derives a 16 color ANSI palette from the theme.
+    ///
+    /// Black and white come from the neutral ramp, and the other colors from the
+    /// accent palette. Bright colors are mixed toward `neutral_10`, the far end of the
+    /// ramp from the background, so they stand out further in both light and dark themes.
+    #[must_use]
+    pub fn terminal_palette(&self) -> TerminalPalette {
+        let p = &self.palette;
+        let background = self.background.base;
+        let flatten = |c: Srgba| c.over(background);
+        let brighten = |c: Srgba| flatten(c.mix(p.neutral_10, 0.2));
+
+        // The neutral ramp, from darkest to lightest.
+        let mut neutrals = [
+            p.neutral_0,
+            p.neutral_1,
+            p.neutral_2,
+            p.neutral_3,
+            p.neutral_4,
+            p.neutral_5,
+            p.neutral_6,
+            p.neutral_7,
+            p.neutral_8,
+            p.neutral_9,
+            p.neutral_10,
+        ];
+        if !self.is_dark {
+            neutrals.reverse();
+        }
+
+        let colors = [
+            p.accent_red,
+            p.accent_green,
+            p.accent_yellow,
+            p.accent_indigo,
+            p.accent_purple,
+            p.accent_blue,
+        ];
+
+        let mut normal = [flatten(neutrals[2]); 8];
+        let mut bright = [flatten(neutrals[5]); 8];
+        for (i, color) in colors.into_iter().enumerate() {
+            normal[i + 1] = flatten(color);
+            bright[i + 1] = brighten(color);
+        }
+        normal[7] = flatten(neutrals[8]);
+        bright[7] = flatten(neutrals[10]);
+
+        TerminalPalette {
+            foreground: flatten(self.background.on),
+            background: flatten(background),
+            cursor: flatten(self.accent.base),
+            cursor_text: flatten(background),
+            selection_foreground: flatten(background),
+            selection_background: flatten(self.accent.base),
+            normal,
+            bright,
+        }
+    }
+
+    /// Produces a theme for Alacritty, to be imported from `alacritty.toml`.
+    #[must_use]
+    #[cold]
+    pub fn as_alacritty(&self) -> String {
+        let palette = self.terminal_palette();
+        let names = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+        let colors = |colors: &[Srgba; 8]| {
+            names
+                .iter()
+                .zip(colors)
+                .map(|(name, &c)| format!("{name} = \"#{}\"\n", to_rgb_hex(c)))
+                .collect::<String>()
+        };
+
+        format!(
+            r##"# GENERATED BY COSMIC
+
+[colors.primary]
+background = "#{}"
+foreground = "#{}"
+
+[colors.cursor]
+text = "#{}"
+cursor = "#{}"
+
+[colors.selection]
+text = "#{}"
+background = "#{}"
+
+[colors.normal]
+{}
+[colors.bright]
+{}"##,
+            to_rgb_hex(palette.background),
+            to_rgb_hex(palette.foreground),
+            to_rgb_hex(palette.cursor_text),
+            to_rgb_hex(palette.cursor),
+            to_rgb_hex(palette.selection_foreground),
+            to_rgb_hex(palette.selection_background),
+            colors(&palette.normal),
+            colors(&palette.bright),
+        )
+    }
+
+    /// Produces a theme for kitty, to be included from `kitty.conf`.
+    #[must_use]
+    #[cold]
+    pub fn as_kitty(&self) -> String {
+        let palette = self.terminal_palette();
+        let ansi = palette
+            .ansi()
+            .enumerate()
+            .map(|(i, c)| format!("color{i} #{}\n", to_rgb_hex(c)))
+            .collect::<String>();
+
+        format!(
+            r#"# GENERATED BY COSMIC
+
+foreground #{}
+background #{}
+cursor #{}
+cursor_text_color #{}
+selection_foreground #{}
+selection_background #{}
+
+{ansi}"#,
+            to_rgb_hex(palette.foreground),
+            to_rgb_hex(palette.background),
+            to_rgb_hex(palette.cursor),
+            to_rgb_hex(palette.cursor_text),
+            to_rgb_hex(palette.selection_foreground),
+            to_rgb_hex(palette.selection_background),
+        )
+    }
+
+    /// Produces a theme for foot, to be included from `foot.ini`.
+    #[must_use]
+    #[cold]
+    pub fn as_foot(&self) -> String {
+        let palette = self.terminal_palette();
+        let colors = |prefix: &str, colors: &[Srgba; 8]| {
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| format!("{prefix}{i}={}\n", to_rgb_hex(c)))
+                .collect::<String>()
+        };
+
+        format!(
+            r#"# GENERATED BY COSMIC
+
+[colors]
+cursor={} {}
+foreground={}
+background={}
+selection-foreground={}
+selection-background={}
+{}{}"#,
+            to_rgb_hex(palette.cursor_text),
+            to_rgb_hex(palette.cursor),
+            to_rgb_hex(palette.foreground),
+            to_rgb_hex(palette.background),
+            to_rgb_hex(palette.selection_foreground),
+            to_rgb_hex(palette.selection_background),
+            colors("regular", &palette.normal),
+            colors("bright", &palette.bright),
+        )
+    }
+
+    /// Produces a WezTerm color scheme named `COSMIC`.
+    #[must_use]
+    #[cold]
+    pub fn as_wezterm(&self) -> String {
+        let palette = self.terminal_palette();
+        let colors = |colors: &[Srgba; 8]| {
+            colors
+                .iter()
+                .map(|&c| format!("\"#{}\"", to_rgb_hex(c)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        format!(
+            r##"# GENERATED BY COSMIC
+
+[colors]
+foreground = "#{}"
+background = "#{}"
+cursor_bg = "#{}"
+cursor_fg = "#{}"
+cursor_border = "#{}"
+selection_fg = "#{}"
+selection_bg = "#{}"
+ansi = [{}]
+brights = [{}]
+
+[metadata]
+name = "COSMIC"
+"##,
+            to_rgb_hex(palette.foreground),
+            to_rgb_hex(palette.background),
+            to_rgb_hex(palette.cursor),
+            to_rgb_hex(palette.cursor_text),
+            to_rgb_hex(palette.cursor),
+            to_rgb_hex(palette.selection_foreground),
+            to_rgb_hex(palette.selection_background),
+            colors(&palette.normal),
+            colors(&palette.bright),
+        )
+    }
+
+    /// Writes the terminal themes to `cosmic/{dark,light}` in the config directory
+    /// of each terminal which has one: Alacritty, kitty, foot and WezTerm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error writing a theme.
+    #[cold]
+    pub fn write_terminals(&self) -> Result<(), OutputError> {
+        let mut res = Ok(());
+        for terminal in Terminal::ALL {
+            if let Err(e) = self.write_terminal(terminal) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    #[cold]
+    fn write_terminal(&self, terminal: Terminal) -> Result<(), OutputError> {
+        let Some(config_dir) = terminal.config_dir()? else {
+            return Ok(());
+        };
+
+        let cosmic_dir = config_dir.join("cosmic");
+        fs::create_dir_all(&cosmic_dir).map_err(OutputError::Io)?;
+
+        let name = format!(
+            "{}.{}",
+            if self.is_dark { "dark" } else { "light" },
+            terminal.extension()
+        );
+        write_atomic(&cosmic_dir.join(name), &terminal.export(self)).map_err(OutputError::Io)
+    }
+
+    /// Links the light or dark terminal themes as the active theme of each terminal.
+    ///
+    /// The config files of the terminals are left alone, so the active theme is only
+    /// used once it is included with [`Theme::include_terminal_themes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error linking a theme.
+    #[cold]
+    pub fn apply_terminals(is_dark: bool) -> Result<(), OutputError> {
+        let mut res = Ok(());
+        for terminal in Terminal::ALL {
+            if let Err(e) = Self::apply_terminal(terminal, is_dark) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    #[cold]
+    fn apply_terminal(terminal: Terminal, is_dark: bool) -> Result<(), OutputError> {
+        let Some(config_dir) = terminal.config_dir()? else {
+            return Ok(());
+        };
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::symlink;
+
+            let cosmic_dir = config_dir.join("cosmic");
+            let theme = cosmic_dir.join(format!(
+                "{}.{}",
+                if is_dark { "dark" } else { "light" },
+                terminal.extension()
+            ));
+            let active_theme = config_dir.join(terminal.active_theme());
+
+            if let Some(parent) = active_theme.parent() {
+                fs::create_dir_all(parent).map_err(OutputError::Io)?;
+            }
+            // Never replace a theme which the user put in place of ours.
+            if is_cosmic_link(&active_theme, &cosmic_dir).map_err(OutputError::Io)? == Some(false) {
+                return Ok(());
+            }
+            if fs::symlink_metadata(&active_theme).is_ok() {
+                fs::remove_file(&active_theme).map_err(OutputError::Io)?;
+            }
+            symlink(&theme, &active_theme).map_err(OutputError::Io)?;
+        }
+
+        Ok(())
+    }
+
+    /// Includes the active theme from the config file of each terminal, which the user
+    /// opts into, such as by enabling terminal theming in the settings.
+    ///
+    /// WezTerm users select the `COSMIC` color scheme themselves. Alacritty configs
+    /// which already import other files are left for the user to edit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error including a theme.
+    #[cold]
+    pub fn include_terminal_themes() -> Result<(), OutputError> {
+        let mut res = Ok(());
+        for terminal in Terminal::ALL {
+            if let Err(e) = Self::include_terminal_theme(terminal) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    #[cold]
+    fn include_terminal_theme(terminal: Terminal) -> Result<(), OutputError> {
+        let Some(config_dir) = terminal.config_dir()? else {
+            return Ok(());
+        };
+        let Some(config_file) = terminal.config_file() else {
+            return Ok(());
+        };
+        let active_theme = config_dir.join(terminal.active_theme());
+        let config_file = config_dir.join(config_file);
+        let include = terminal.include_line(&active_theme);
+
+        let content = match fs::read_to_string(&config_file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(OutputError::Io(e)),
+        };
+        if content.lines().any(|line| line == include) {
+            return Ok(());
+        }
+        if matches!(terminal, Terminal::Alacritty)
+            && content.lines().any(|line| {
+                let line = line.trim_start();
+                line.starts_with("import") || line.starts_with("general") || line == "[general]"
+            })
+        {
+            return Ok(());
+        }
+
+        // Include the theme first, so that colors set by the user take precedence.
+        write_atomic(&config_file, &format!("{include}\n{content}")).map_err(OutputError::Io)
+    }
+
+    /// Removes the active terminal themes linked by us, and their includes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OutputError` if there is an error removing a theme or include.
+    #[cold]
+    pub fn reset_terminals() -> Result<(), OutputError> {
+        let mut res = Ok(());
+        for terminal in Terminal::ALL {
+            if let Err(e) = Self::reset_terminal(terminal) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    #[cold]
+    fn reset_terminal(terminal: Terminal) -> Result<(), OutputError> {
+        let Some(config_dir) = terminal.config_dir()? else {
+            return Ok(());
+        };
+
+        let active_theme = config_dir.join(terminal.active_theme());
+        if is_cosmic_link(&active_theme, &config_dir.join("cosmic"))
+            .map_err(OutputError::Io)?
+            .unwrap_or_default()
+        {
+            fs::remove_file(&active_theme).map_err(OutputError::Io)?;
+        }
+
+        let Some(config_file) = terminal.config_file() else {
+            return Ok(());
+        };
+        let config_file = config_dir.join(config_file);
+        let include = terminal.include_line(&active_theme);
+
+        let Ok(content) = fs::read_to_string(&config_file) else {
+            return Ok(());
+        };
+        if !content.lines().any(|line| line == include) {
+            return Ok(());
+        }
+
+        let content = content
+            .lines()
+            .filter(|line| *line != include)
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        write_atomic(&config_file, &content).map_err(OutputError::Io)
+    }
+}
+
+/// Whether `path` links into `cosmic_dir`, or `None` if nothing is at `path`.
+fn is_cosmic_link(path: &Path, cosmic_dir: &Path) -> io::Result<Option<bool>> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(None);
+    };
+    if !metadata.file_type().is_symlink() {
+        return Ok(Some(false));
+    }
+    Ok(Some(fs::read_link(path)?.starts_with(cosmic_dir)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_palette() {
+        for theme in [Theme::light_default(), Theme::dark_default()] {
+            let palette = theme.terminal_palette();
+            assert_eq!(palette.ansi().count(), 16);
+            assert!(palette.ansi().all(|c| c.alpha == 1.0));
+            assert_eq!(palette.background, theme.background.base);
+        }
+    }
+
+    #[test]
+    fn test_light_default_alacritty() {
+        let light_default_alacritty = Theme::light_default().as_alacritty();
+        insta::assert_snapshot!(light_default_alacritty);
+    }
+
+    #[test]
+    fn test_dark_default_alacritty() {
+        let dark_default_alacritty = Theme::dark_default().as_alacritty();
+        insta::assert_snapshot!(dark_default_alacritty);
+    }
+
+    #[test]
+    fn test_light_default_kitty() {
+        let light_default_kitty = Theme::light_default().as_kitty();
+        insta::assert_snapshot!(light_default_kitty);
+    }
+
+    #[test]
+    fn test_dark_default_kitty() {
+        let dark_default_kitty = Theme::dark_default().as_kitty();
+        insta::assert_snapshot!(dark_default_kitty);
+    }
+
+    #[test]
+    fn test_light_default_foot() {
+        let light_default_foot = Theme::light_default().as_foot();
+        insta::assert_snapshot!(light_default_foot);
+    }
+
+    #[test]
+    fn test_dark_default_foot() {
+        let dark_default_foot = Theme::dark_default().as_foot();
+        insta::assert_snapshot!(dark_default_foot);
+    }
+
+    #[test]
+    fn test_light_default_wezterm() {
+        let light_default_wezterm = Theme::light_default().as_wezterm();
+        insta::assert_snapshot!(light_default_wezterm);
+    }
+
+    #[test]
+    fn test_dark_default_wezterm() {
+        let dark_default_wezterm = Theme::dark_default().as_wezterm();
+        insta::assert_snapshot!(dark_default_wezterm);
+    }
+}
@@ -0,0 +1,500 @@
+//! Shareable theme packages.
+//!
+//! A package is a directory with a `package.ron` file, holding a [`ThemePackage`],
+//! and an optional preview image next to it. Packages are installed into a
+//! [`ThemeLibrary`], which keeps each package in a directory named by its id.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cosmic_config::CosmicConfigEntry;
+use ron::Value;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{NAME, Theme, ThemeBuilder};
+
+/// The name of the package file in a package directory.
+pub const PACKAGE_FILE: &str = "package.ron";
+
+/// The file extensions which a preview image may have.
+pub const PREVIEW_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "svg"];
+
+/// A theme with a light and dark variant, and the metadata to share it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemePackage {
+    /// The name of the theme, from which its id is derived.
+    pub name: String,
+    /// The author of the theme.
+    pub author: String,
+    /// The license of the theme, preferably as an SPDX expression.
+    pub license: String,
+    /// The version of the theme, as dot separated numbers.
+    pub version: String,
+    /// The file name of the preview image, in the package directory.
+    #[serde(default)]
+    pub preview: Option<String>,
+    /// The builder for the dark variant.
+    pub dark: ThemeBuilder,
+    /// The builder for the light variant.
+    pub light: ThemeBuilder,
+}
+
+/// An error reading, validating or installing a theme package.
+#[derive(Error, Debug)]
+pub enum PackageError {
+    /// IO error
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The package is not valid RON, or a field has the wrong type.
+    #[error("Malformed package: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    /// The package could not be written as RON.
+    #[error("Ron error: {0}")]
+    RonSerialize(#[from] ron::Error),
+    /// A field which a package or builder does not have.
+    #[error("Unknown field `{0}`")]
+    UnknownField(String),
+    /// A required text field is empty.
+    #[error("The `{0}` field is empty")]
+    EmptyField(&'static str),
+    /// The version is not dot separated numbers.
+    #[error("Invalid version `{0}`, expected dot separated numbers such as `1.0.0`")]
+    InvalidVersion(String),
+    /// The palette of a builder does not match its variant.
+    #[error("The `{0}` builder has a palette of the other variant")]
+    MismatchedPalette(&'static str),
+    /// The preview is not an image file in the package directory.
+    #[error("Invalid preview `{0}`, expected the file name of an image in the package")]
+    InvalidPreview(String),
+    /// The preview is missing from the package directory.
+    #[error("Missing preview `{0}`")]
+    MissingPreview(PathBuf),
+    /// There is no package with the id in the library.
+    #[error("No installed package `{0}`")]
+    NotFound(String),
+    /// Missing data directory
+    #[error("Missing data directory")]
+    MissingDataDir,
+    /// Config error
+    #[error("Config error: {0}")]
+    Config(#[from] cosmic_config::Error),
+}
+
+impl ThemePackage {
+    /// Parses and validates a package.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package is malformed, has a field which a package
+    /// or builder does not have, or does not pass [`ThemePackage::validate`].
+    pub fn from_ron(ron: &str) -> Result<Self, PackageError> {
+        let value: Value = ron::from_str(ron)?;
+        let package: Self = ron::from_str(ron)?;
+
+        // Unknown fields of the builders are ignored when deserializing, so look for
+        // fields which do not survive a round trip.
+        let known: Value = ron::from_str(&ron::to_string(&package)?)?;
+        if let Some(path) = unknown_field(&value, &known) {
+            return Err(PackageError::UnknownField(path));
+        }
+
+        package.validate()?;
+        Ok(package)
+    }
+
+    /// Serializes the package.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package cannot be serialized.
+    pub fn to_ron(&self) -> Result<String, PackageError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::new(),
+        )?)
+    }
+
+    /// Reads and validates the package in a package directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package cannot be read, is invalid, or its
+    /// preview is missing.
+    pub fn load(dir: &Path) -> Result<Self, PackageError> {
+        let package = Self::from_ron(&fs::read_to_string(dir.join(PACKAGE_FILE))?)?;
+        if let Some(preview) = package.preview.as_deref() {
+            let path = dir.join(preview);
+            if !path.is_file() {
+                return Err(PackageError::MissingPreview(path));
+            }
+        }
+        Ok(package)
+    }
+
+    /// Checks the metadata of the package, and that each builder has a palette of its variant.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` describing the first problem found.
+    pub fn validate(&self) -> Result<(), PackageError> {
+        for (field, value) in [
+            ("name", &self.name),
+            ("author", &self.author),
+            ("license", &self.license),
+        ] {
+            if value.trim().is_empty() {
+                return Err(PackageError::EmptyField(field));
+            }
+        }
+
+        if self.id().is_empty() {
+            return Err(PackageError::EmptyField("name"));
+        }
+
+        if self
+            .version
+            .split('.')
+            .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(PackageError::InvalidVersion(self.version.clone()));
+        }
+
+        if !self.dark.palette.is_dark() {
+            return Err(PackageError::MismatchedPalette("dark"));
+        }
+        if self.light.palette.is_dark() {
+            return Err(PackageError::MismatchedPalette("light"));
+        }
+
+        if let Some(preview) = self.preview.as_deref() {
+            let path = Path::new(preview);
+            let is_file_name = path.file_name().is_some_and(|name| name == preview);
+            let is_image = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PREVIEW_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+            if !is_file_name || !is_image {
+                return Err(PackageError::InvalidPreview(preview.to_owned()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The id of the package, which is its name in lowercase with each run of other
+    /// characters than letters and digits replaced by a `-`.
+    #[must_use]
+    pub fn id(&self) -> String {
+        let mut id = String::with_capacity(self.name.len());
+        for c in self.name.chars().flat_map(char::to_lowercase) {
+            if c.is_alphanumeric() {
+                id.push(c);
+            } else if !id.is_empty() && !id.ends_with('-') {
+                id.push('-');
+            }
+        }
+        if id.ends_with('-') {
+            id.pop();
+        }
+        id
+    }
+
+    /// Writes the builders of the package to the theme builder configs, and the themes they
+    /// build to [`Theme::dark_config`] and [`Theme::light_config`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if a config cannot be written.
+    #[cold]
+    pub fn apply(&self) -> Result<(), PackageError> {
+        self.dark.write_entry(&ThemeBuilder::dark_config()?)?;
+        self.dark
+            .clone()
+            .build()
+            .write_entry(&Theme::dark_config()?)?;
+
+        self.light.write_entry(&ThemeBuilder::light_config()?)?;
+        self.light
+            .clone()
+            .build()
+            .write_entry(&Theme::light_config()?)?;
+        Ok(())
+    }
+}
+
+/// The path of the first field of `value` which `known` does not have, if any.
+fn unknown_field(value: &Value, known: &Value) -> Option<String> {
+    match (value, known) {
+        (Value::Map(value), Value::Map(known)) => value.iter().find_map(|(key, v)| {
+            let name = match key {
+                Value::String(name) => name.clone(),
+                key => format!("{key:?}"),
+            };
+            match known.get(key) {
+                Some(k) => unknown_field(v, k).map(|path| format!("{name}.{path}")),
+                None => Some(name),
+            }
+        }),
+        (Value::Seq(value), Value::Seq(known)) => value
+            .iter()
+            .zip(known)
+            .enumerate()
+            .find_map(|(i, (v, k))| unknown_field(v, k).map(|path| format!("{i}.{path}"))),
+        (Value::Option(Some(value)), Value::Option(Some(known))) => unknown_field(value, known),
+        _ => None,
+    }
+}
+
+/// A package installed in a [`ThemeLibrary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledPackage {
+    /// The directory of the package in the library.
+    pub path: PathBuf,
+    /// The package.
+    pub package: ThemePackage,
+}
+
+impl InstalledPackage {
+    /// The path of the preview image, if the package has one.
+    #[must_use]
+    pub fn preview_path(&self) -> Option<PathBuf> {
+        self.package
+            .preview
+            .as_deref()
+            .map(|preview| self.path.join(preview))
+    }
+}
+
+/// A directory of installed theme packages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeLibrary {
+    path: PathBuf,
+}
+
+impl ThemeLibrary {
+    /// The library of the user, in the cosmic data directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if there is no data directory.
+    pub fn user() -> Result<Self, PackageError> {
+        let mut path = dirs::data_dir().ok_or(PackageError::MissingDataDir)?;
+        path.extend(["cosmic", NAME, "packages"]);
+        Ok(Self::new(path))
+    }
+
+    /// A library in the directory.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The directory of the library.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Validates the package in a package directory, and copies it into the library.
+    ///
+    /// A package which is already installed with the same id is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package is invalid, or cannot be copied.
+    #[cold]
+    pub fn install(&self, dir: &Path) -> Result<InstalledPackage, PackageError> {
+        let package = ThemePackage::load(dir)?;
+        let path = self.path.join(package.id());
+
+        // Copy into a temporary directory first, then move it into place.
+        let tmp_path = self.path.join(format!(".{}~", package.id()));
+        if tmp_path.exists() {
+            fs::remove_dir_all(&tmp_path)?;
+        }
+        fs::create_dir_all(&tmp_path)?;
+        let res = fs::write(tmp_path.join(PACKAGE_FILE), package.to_ron()?)
+            .and_then(|()| match package.preview.as_deref() {
+                Some(preview) => fs::copy(dir.join(preview), tmp_path.join(preview)).map(|_| ()),
+                None => Ok(()),
+            })
+            .and_then(|()| {
+                if path.exists() {
+                    fs::remove_dir_all(&path)?;
+                }
+                fs::rename(&tmp_path, &path)
+            });
+        if let Err(e) = res {
+            _ = fs::remove_dir_all(&tmp_path);
+            return Err(PackageError::Io(e));
+        }
+
+        Ok(InstalledPackage { path, package })
+    }
+
+    /// The installed package with the id.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package is not installed, or is invalid.
+    pub fn get(&self, id: &str) -> Result<InstalledPackage, PackageError> {
+        let path = self.path.join(id);
+        if id.starts_with('.') || id.contains(std::path::is_separator) || !path.is_dir() {
+            return Err(PackageError::NotFound(id.to_owned()));
+        }
+        let package = ThemePackage::load(&path)?;
+        Ok(InstalledPackage { path, package })
+    }
+
+    /// Every valid installed package, sorted by id.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the library cannot be read.
+    pub fn packages(&self) -> Result<Vec<InstalledPackage>, PackageError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir()
+                && let Some(id) = entry.file_name().to_str()
+                && !id.starts_with('.')
+            {
+                ids.push(id.to_owned());
+            }
+        }
+        ids.sort();
+
+        Ok(ids.iter().filter_map(|id| self.get(id).ok()).collect())
+    }
+
+    /// Removes the installed package with the id.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PackageError` if the package is not installed, or cannot be removed.
+    #[cold]
+    pub fn uninstall(&self, id: &str) -> Result<(), PackageError> {
+        let installed = self.get(id)?;
+        fs::remove_dir_all(installed.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package() -> ThemePackage {
+        ThemePackage {
+            name: "Deep Sea".to_owned(),
+            author: "Jane Doe".to_owned(),
+            license: "MIT".to_owned(),
+            version: "1.2.0".to_owned(),
+            preview: Some("preview.png".to_owned()),
+            dark: ThemeBuilder::dark().accent(palette::Srgb::new(0.1, 0.5, 0.8)),
+            light: ThemeBuilder::light(),
+        }
+    }
+
+    #[test]
+    fn test_package_ron() {
+        let package = package();
+        assert_eq!(package.id(), "deep-sea");
+        let ron = package.to_ron().unwrap();
+        assert_eq!(ThemePackage::from_ron(&ron).unwrap(), package);
+
+        let unknown = ron.replacen("name:", "homepage: \"\",\n    name:", 1);
+        assert!(matches!(
+            ThemePackage::from_ron(&unknown),
+            Err(PackageError::Ron(_))
+        ));
+
+        let unknown = ron.replacen("accent:", "accent_colour: None,\n        accent:", 1);
+        assert!(matches!(
+            ThemePackage::from_ron(&unknown),
+            Err(PackageError::UnknownField(field)) if field == "dark.accent_colour"
+        ));
+
+        let malformed = ron.replacen("version: \"1.2.0\"", "version: 1", 1);
+        assert!(matches!(
+            ThemePackage::from_ron(&malformed),
+            Err(PackageError::Ron(_))
+        ));
+    }
+
+    #[test]
+    fn test_package_validate() {
+        assert!(package().validate().is_ok());
+
+        let mut invalid = package();
+        invalid.author = " ".to_owned();
+        assert!(matches!(
+            invalid.validate(),
+            Err(PackageError::EmptyField("author"))
+        ));
+
+        let mut invalid = package();
+        invalid.version = "1.x".to_owned();
+        assert!(matches!(
+            invalid.validate(),
+            Err(PackageError::InvalidVersion(_))
+        ));
+
+        let mut invalid = package();
+        invalid.light = ThemeBuilder::dark();
+        assert!(matches!(
+            invalid.validate(),
+            Err(PackageError::MismatchedPalette("light"))
+        ));
+
+        for preview in ["../preview.png", "preview.txt"] {
+            let mut invalid = package();
+            invalid.preview = Some(preview.to_owned());
+            assert!(matches!(
+                invalid.validate(),
+                Err(PackageError::InvalidPreview(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_library() {
+        let root = tempfile::tempdir().unwrap();
+        let source = ThemeLibrary::new(root.path().join("source"));
+        let dir = source.path().join("deep-sea");
+        fs::create_dir_all(&dir).unwrap();
+        let package = package();
+        fs::write(dir.join(PACKAGE_FILE), package.to_ron().unwrap()).unwrap();
+        assert!(matches!(
+            source.install(&dir),
+            Err(PackageError::MissingPreview(_))
+        ));
+        fs::write(dir.join("preview.png"), b"preview").unwrap();
+
+        let library = ThemeLibrary::new(root.path().join("library"));
+        assert!(library.packages().unwrap().is_empty());
+        let installed = library.install(&dir).unwrap();
+        assert_eq!(installed.package, package);
+        assert_eq!(
+            fs::read(installed.preview_path().unwrap()).unwrap(),
+            b"preview"
+        );
+        assert_eq!(library.packages().unwrap(), vec![installed.clone()]);
+
+        // Installing again replaces the package.
+        assert_eq!(library.install(&dir).unwrap(), installed);
+        assert_eq!(library.packages().unwrap().len(), 1);
+
+        assert!(matches!(
+            library.get("../source"),
+            Err(PackageError::NotFound(_))
+        ));
+        library.uninstall("deep-sea").unwrap();
+        assert!(library.packages().unwrap().is_empty());
+    }
+}
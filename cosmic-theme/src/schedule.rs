@@ -0,0 +1,636 @@
+//! Switch between the light and dark theme on a schedule.
+//!
+//! A [`Schedule`] is either a fixed time of day for each mode, or sunset to
+//! sunrise at a location. Sunrise and sunset are computed offline with the NOAA
+//! solar equations. Time is read from a [`Clock`], so that the switches of a
+//! schedule can be followed without waiting for them.
+
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use cosmic_config::{Config, CosmicConfigEntry};
+use iced_futures::futures::SinkExt;
+use iced_futures::futures::channel::mpsc;
+use iced_futures::futures::future::pending;
+use iced_futures::stream;
+use jiff::civil::{Date, Time};
+use jiff::{SignedDuration, Timestamp, ToSpan, Zoned};
+use serde::{Deserialize, Serialize};
+
+/// ID for the ThemeSchedule config
+pub const THEME_SCHEDULE_ID: &str = "com.system76.CosmicTheme.Schedule";
+
+/// The number of days to search for the next switch, so that polar day or night
+/// at a location does not stop the schedule.
+const MAX_SEARCH_DAYS: i32 = 366;
+
+/// The longest time to sleep at once, so that the clock is read again after a suspend.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// The config for switching between the light and dark theme on a schedule
+#[derive(Debug, Clone, Copy, PartialEq, cosmic_config::cosmic_config_derive::CosmicConfigEntry)]
+#[version = 1]
+pub struct ThemeSchedule {
+    /// Whether the schedule switches the theme.
+    pub enabled: bool,
+    /// The schedule.
+    pub schedule: Schedule,
+}
+
+impl Default for ThemeSchedule {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl ThemeSchedule {
+    #[inline]
+    /// Get the config for the theme schedule
+    pub fn config() -> Result<Config, cosmic_config::Error> {
+        Config::new(THEME_SCHEDULE_ID, Self::VERSION)
+    }
+}
+
+/// A time of day, in the local time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimeOfDay {
+    /// The hour, from 0 to 23.
+    pub hour: u8,
+    /// The minute, from 0 to 59.
+    pub minute: u8,
+}
+
+impl TimeOfDay {
+    /// A time of day, clamped to a valid hour and minute.
+    #[must_use]
+    pub const fn new(hour: u8, minute: u8) -> Self {
+        Self {
+            hour: if hour > 23 { 23 } else { hour },
+            minute: if minute > 59 { 59 } else { minute },
+        }
+    }
+
+    fn time(self) -> Time {
+        Time::new(self.hour.min(23) as i8, self.minute.min(59) as i8, 0, 0)
+            .unwrap_or(Time::midnight())
+    }
+}
+
+/// When to use the dark theme.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Schedule {
+    /// Dark from one time of day until another.
+    Fixed {
+        /// The time to switch to the dark theme.
+        dark: TimeOfDay,
+        /// The time to switch to the light theme.
+        light: TimeOfDay,
+    },
+    /// Dark from sunset until sunrise at a location.
+    Sun {
+        /// The latitude in degrees, positive to the north.
+        latitude: f64,
+        /// The longitude in degrees, positive to the east.
+        longitude: f64,
+    },
+}
+
+impl Default for Schedule {
+    #[inline]
+    fn default() -> Self {
+        Self::Fixed {
+            dark: TimeOfDay::new(19, 0),
+            light: TimeOfDay::new(7, 0),
+        }
+    }
+}
+
+impl Hash for Schedule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Self::Fixed { dark, light } => {
+                0u8.hash(state);
+                dark.hash(state);
+                light.hash(state);
+            }
+            Self::Sun {
+                latitude,
+                longitude,
+            } => {
+                1u8.hash(state);
+                latitude.to_bits().hash(state);
+                longitude.to_bits().hash(state);
+            }
+        }
+    }
+}
+
+/// A switch between the light and dark theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch {
+    /// The time of the switch.
+    pub at: Zoned,
+    /// Whether the dark theme is used from the time of the switch.
+    pub is_dark: bool,
+}
+
+/// The times of sunrise and sunset on a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SunTimes {
+    /// The sun rises and sets.
+    Daylight {
+        /// The time of sunrise.
+        sunrise: Timestamp,
+        /// The time of sunset.
+        sunset: Timestamp,
+    },
+    /// The sun does not set.
+    PolarDay,
+    /// The sun does not rise.
+    PolarNight,
+}
+
+impl SunTimes {
+    /// The times of sunrise and sunset on a date at a location.
+    ///
+    /// Uses the NOAA solar equations, with the zenith of 90.833° for atmospheric refraction
+    /// and the radius of the sun. The times are within a few minutes for latitudes between
+    /// the polar circles.
+    #[must_use]
+    pub fn new(date: Date, latitude: f64, longitude: f64) -> Self {
+        use std::f64::consts::TAU;
+
+        let gamma = TAU / 365.0 * f64::from(date.day_of_year() - 1);
+        let equation_of_time = 229.18
+            * (0.000_075 + 0.001_868 * gamma.cos()
+                - 0.032_077 * gamma.sin()
+                - 0.014_615 * (2.0 * gamma).cos()
+                - 0.040_849 * (2.0 * gamma).sin());
+        let declination = 0.006_918 - 0.399_912 * gamma.cos() + 0.070_257 * gamma.sin()
+            - 0.006_758 * (2.0 * gamma).cos()
+            + 0.000_907 * (2.0 * gamma).sin()
+            - 0.002_697 * (3.0 * gamma).cos()
+            + 0.001_48 * (3.0 * gamma).sin();
+
+        let latitude = latitude.clamp(-90.0, 90.0).to_radians();
+        let cos_hour_angle = 90.833_f64.to_radians().cos() / (latitude.cos() * declination.cos())
+            - latitude.tan() * declination.tan();
+        if cos_hour_angle > 1.0 {
+            return Self::PolarNight;
+        } else if cos_hour_angle < -1.0 || cos_hour_angle.is_nan() {
+            return Self::PolarDay;
+        }
+        let hour_angle = cos_hour_angle.acos().to_degrees();
+
+        // Minutes after midnight UTC.
+        let noon = 720.0 - 4.0 * longitude - equation_of_time;
+        let midnight = date
+            .to_zoned(jiff::tz::TimeZone::UTC)
+            .map(|z| z.timestamp())
+            .ok();
+        let at = |minutes: f64| {
+            midnight
+                .and_then(|midnight| {
+                    midnight
+                        .checked_add(SignedDuration::from_secs((minutes * 60.0).round() as i64))
+                        .ok()
+                })
+                .unwrap_or(Timestamp::UNIX_EPOCH)
+        };
+        Self::Daylight {
+            sunrise: at(noon - 4.0 * hour_angle),
+            sunset: at(noon + 4.0 * hour_angle),
+        }
+    }
+}
+
+impl Schedule {
+    /// Whether the dark theme is used at a time.
+    #[must_use]
+    pub fn is_dark_at(&self, at: &Zoned) -> bool {
+        match *self {
+            Self::Fixed { dark, light } => {
+                let (dark, light, time) = (dark.time(), light.time(), at.time());
+                if dark > light {
+                    time >= dark || time < light
+                } else {
+                    dark <= time && time < light
+                }
+            }
+            Self::Sun {
+                latitude,
+                longitude,
+            } => match SunTimes::new(at.date(), latitude, longitude) {
+                SunTimes::Daylight { sunrise, sunset } => {
+                    let at = at.timestamp();
+                    at < sunrise || at >= sunset
+                }
+                SunTimes::PolarDay => false,
+                SunTimes::PolarNight => true,
+            },
+        }
+    }
+
+    /// The first switch after a time, or `None` if the schedule never switches.
+    #[must_use]
+    pub fn next_switch(&self, after: &Zoned) -> Option<Switch> {
+        if let Self::Fixed { dark, light } = *self
+            && dark == light
+        {
+            return None;
+        }
+
+        let tz = after.time_zone();
+        let is_dark = self.is_dark_at(after);
+        let start = after.date().yesterday().ok()?;
+
+        (0..MAX_SEARCH_DAYS)
+            .filter_map(|days| start.checked_add(days.days()).ok())
+            .flat_map(|date| {
+                let events: [Option<(Timestamp, bool)>; 2] = match *self {
+                    Self::Fixed { dark, light } => [
+                        date.to_datetime(dark.time())
+                            .to_zoned(tz.clone())
+                            .ok()
+                            .map(|z| (z.timestamp(), true)),
+                        date.to_datetime(light.time())
+                            .to_zoned(tz.clone())
+                            .ok()
+                            .map(|z| (z.timestamp(), false)),
+                    ],
+                    Self::Sun {
+                        latitude,
+                        longitude,
+                    } => match SunTimes::new(date, latitude, longitude) {
+                        SunTimes::Daylight { sunrise, sunset } => {
+                            [Some((sunrise, false)), Some((sunset, true))]
+                        }
+                        SunTimes::PolarDay => [Some((date_start(date, tz), false)), None],
+                        SunTimes::PolarNight => [Some((date_start(date, tz), true)), None],
+                    },
+                };
+                let mut events: Vec<_> = events.into_iter().flatten().collect();
+                events.sort();
+                events
+            })
+            .find(|&(at, dark)| at > after.timestamp() && dark != is_dark)
+            .map(|(at, is_dark)| Switch {
+                at: at.to_zoned(tz.clone()),
+                is_dark,
+            })
+    }
+
+    /// Follows the schedule with a clock, blocking until each switch.
+    ///
+    /// The first item is the mode at the current time of the clock. Switches
+    /// which have already passed by the time the clock is read again, such as
+    /// during a suspend, are skipped for the mode at that time.
+    pub fn switches<C: Clock>(self, clock: C) -> Switches<C> {
+        Switches {
+            follower: Follower::new(self),
+            clock,
+        }
+    }
+}
+
+fn date_start(date: Date, tz: &jiff::tz::TimeZone) -> Timestamp {
+    date.to_zoned(tz.clone())
+        .map_or(Timestamp::UNIX_EPOCH, |z| z.timestamp())
+}
+
+/// A source of the current time.
+pub trait Clock {
+    /// The current time, in the local time zone.
+    fn now(&self) -> Zoned;
+
+    /// Blocks until the time.
+    fn sleep_until(&self, at: Timestamp);
+
+    /// Waits on the timer of the async runtime until the time, or for at most a
+    /// minute, so that the clock is read again after a suspend.
+    ///
+    /// Resolves to `false` without the `tokio` or `async-std` feature.
+    fn wait_until(&self, at: Timestamp) -> impl Future<Output = bool> + Send {
+        let remaining =
+            Duration::try_from(at.duration_since(self.now().timestamp())).unwrap_or_default();
+        sleep(remaining.min(MAX_SLEEP))
+    }
+}
+
+/// The clock of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Zoned {
+        Zoned::now()
+    }
+
+    fn sleep_until(&self, at: Timestamp) {
+        loop {
+            let now = Timestamp::now();
+            if now >= at {
+                return;
+            }
+            let remaining = Duration::try_from(at.duration_since(now)).unwrap_or_default();
+            std::thread::sleep(remaining.min(MAX_SLEEP));
+        }
+    }
+}
+
+/// The switches of a [`Schedule`], from [`Schedule::switches`].
+#[derive(Debug)]
+pub struct Switches<C> {
+    follower: Follower,
+    clock: C,
+}
+
+impl<C: Clock> Iterator for Switches<C> {
+    type Item = Switch;
+
+    fn next(&mut self) -> Option<Switch> {
+        loop {
+            if self.follower.is_dark.is_some() {
+                self.clock.sleep_until(self.follower.wake_at()?);
+            }
+            if let Some(switch) = self.follower.update(self.clock.now()) {
+                return Some(switch);
+            }
+        }
+    }
+}
+
+/// Tracks the mode of a schedule as the time is read.
+#[derive(Debug)]
+struct Follower {
+    schedule: Schedule,
+    is_dark: Option<bool>,
+    next: Option<Switch>,
+}
+
+impl Follower {
+    fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            is_dark: None,
+            next: None,
+        }
+    }
+
+    /// The switch to the mode at a time, if it differs from the last mode.
+    fn update(&mut self, now: Zoned) -> Option<Switch> {
+        let is_dark = self.schedule.is_dark_at(&now);
+        self.next = self.schedule.next_switch(&now);
+        if self.is_dark.replace(is_dark) == Some(is_dark) {
+            return None;
+        }
+        Some(Switch { at: now, is_dark })
+    }
+
+    /// The time of the next switch, or `None` if the schedule never switches.
+    fn wake_at(&self) -> Option<Timestamp> {
+        self.next.as_ref().map(|next| next.at.timestamp())
+    }
+}
+
+/// Yields whether the dark theme is used by the schedule, now and at each switch.
+///
+/// The timer needs the `tokio` or `async-std` feature; without either, only the
+/// current mode is yielded.
+#[cold]
+pub fn schedule_subscription<I: 'static + Hash>(
+    id: I,
+    schedule: Schedule,
+) -> iced_futures::Subscription<bool> {
+    iced_futures::Subscription::run_with((id, schedule), |(_, schedule)| {
+        let schedule = *schedule;
+
+        stream::channel(1, move |mut output: mpsc::Sender<bool>| async move {
+            follow(schedule, SystemClock, &mut output).await;
+            pending::<()>().await;
+        })
+    })
+}
+
+/// Sends whether the dark theme is used by the schedule, now and at each switch,
+/// until the clock cannot wait or the output is closed.
+async fn follow<C: Clock>(schedule: Schedule, clock: C, output: &mut mpsc::Sender<bool>) {
+    let mut follower = Follower::new(schedule);
+
+    loop {
+        if let Some(switch) = follower.update(clock.now())
+            && output.send(switch.is_dark).await.is_err()
+        {
+            return;
+        }
+
+        let Some(at) = follower.wake_at() else {
+            return;
+        };
+        if !clock.wait_until(at).await {
+            return;
+        }
+    }
+}
+
+/// Sleeps on the timer of the async runtime, or returns `false` without one.
+async fn sleep(duration: Duration) -> bool {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    async_std::task::sleep(duration).await;
+    #[cfg(not(any(feature = "tokio", feature = "async-std")))]
+    let _ = duration;
+
+    cfg!(any(feature = "tokio", feature = "async-std"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use jiff::civil::date;
+    use jiff::tz::TimeZone;
+
+    use super::*;
+
+    /// A clock which jumps to the time it sleeps until, plus an oversleep.
+    struct TestClock(Mutex<Zoned>, SignedDuration);
+
+    impl Clock for TestClock {
+        fn now(&self) -> Zoned {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn sleep_until(&self, at: Timestamp) {
+            let mut now = self.0.lock().unwrap();
+            *now = (at + self.1).to_zoned(now.time_zone().clone());
+        }
+
+        fn wait_until(&self, at: Timestamp) -> impl Future<Output = bool> + Send {
+            self.sleep_until(at);
+            std::future::ready(true)
+        }
+    }
+
+    fn zoned(tz: &str, y: i16, m: i8, d: i8, h: i8, min: i8) -> Zoned {
+        date(y, m, d)
+            .at(h, min, 0, 0)
+            .to_zoned(TimeZone::get(tz).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fixed_schedule() {
+        let schedule = Schedule::default();
+        let now = zoned("Europe/Berlin", 2024, 3, 30, 12, 0);
+        assert!(!schedule.is_dark_at(&now));
+
+        let next = schedule.next_switch(&now).unwrap();
+        assert_eq!(next.at, zoned("Europe/Berlin", 2024, 3, 30, 19, 0));
+        assert!(next.is_dark);
+
+        // The switch after that crosses the change to daylight saving time.
+        let next = schedule.next_switch(&next.at).unwrap();
+        assert_eq!(next.at, zoned("Europe/Berlin", 2024, 3, 31, 7, 0));
+        assert!(!next.is_dark);
+
+        let schedule = Schedule::Fixed {
+            dark: TimeOfDay::new(1, 30),
+            light: TimeOfDay::new(6, 0),
+        };
+        assert!(schedule.is_dark_at(&zoned("UTC", 2024, 1, 1, 2, 0)));
+        assert!(!schedule.is_dark_at(&zoned("UTC", 2024, 1, 1, 0, 0)));
+
+        let schedule = Schedule::Fixed {
+            dark: TimeOfDay::new(8, 0),
+            light: TimeOfDay::new(8, 0),
+        };
+        assert_eq!(schedule.next_switch(&now), None);
+    }
+
+    #[test]
+    fn test_sun_times() {
+        // Denver on the summer solstice: sunrise at 5:32 and sunset at 20:31 local time.
+        let tz = TimeZone::get("America/Denver").unwrap();
+        let SunTimes::Daylight { sunrise, sunset } =
+            SunTimes::new(date(2024, 6, 21), 39.74, -104.99)
+        else {
+            panic!("no sunrise");
+        };
+        let minutes = |t: Timestamp, h: i8, m: i8| {
+            let expected = date(2024, 6, 21)
+                .at(h, m, 0, 0)
+                .to_zoned(tz.clone())
+                .unwrap();
+            (t.as_second() - expected.timestamp().as_second()).abs() / 60
+        };
+        assert!(minutes(sunrise, 5, 32) <= 3, "{sunrise}");
+        assert!(minutes(sunset, 20, 31) <= 3, "{sunset}");
+
+        assert_eq!(
+            SunTimes::new(date(2024, 6, 21), 78.22, 15.65),
+            SunTimes::PolarDay
+        );
+        assert_eq!(
+            SunTimes::new(date(2024, 12, 21), 78.22, 15.65),
+            SunTimes::PolarNight
+        );
+    }
+
+    #[test]
+    fn test_sun_schedule() {
+        let schedule = Schedule::Sun {
+            latitude: 39.74,
+            longitude: -104.99,
+        };
+        let now = zoned("America/Denver", 2024, 6, 21, 12, 0);
+        assert!(!schedule.is_dark_at(&now));
+        assert!(schedule.is_dark_at(&zoned("America/Denver", 2024, 6, 21, 23, 0)));
+        assert!(schedule.is_dark_at(&zoned("America/Denver", 2024, 6, 22, 3, 0)));
+
+        let next = schedule.next_switch(&now).unwrap();
+        assert!(next.is_dark);
+        assert_eq!(next.at.date(), date(2024, 6, 21));
+        assert_eq!(next.at.hour(), 20);
+
+        // Svalbard switches to the dark theme once the midnight sun is over.
+        let schedule = Schedule::Sun {
+            latitude: 78.22,
+            longitude: 15.65,
+        };
+        let now = zoned("Arctic/Longyearbyen", 2024, 6, 21, 12, 0);
+        let next = schedule.next_switch(&now).unwrap();
+        assert!(next.is_dark);
+        assert_eq!(next.at.month(), 8);
+    }
+
+    #[test]
+    fn test_switches() {
+        let clock = TestClock(
+            Mutex::new(zoned("UTC", 2024, 1, 1, 12, 0)),
+            SignedDuration::ZERO,
+        );
+        let switches: Vec<_> = Schedule::default()
+            .switches(clock)
+            .take(4)
+            .map(|switch| (switch.at.datetime(), switch.is_dark))
+            .collect();
+        assert_eq!(
+            switches,
+            [
+                (date(2024, 1, 1).at(12, 0, 0, 0), false),
+                (date(2024, 1, 1).at(19, 0, 0, 0), true),
+                (date(2024, 1, 2).at(7, 0, 0, 0), false),
+                (date(2024, 1, 2).at(19, 0, 0, 0), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switches_skip_past() {
+        // Waking a day late skips the switches that were slept through.
+        let clock = TestClock(
+            Mutex::new(zoned("UTC", 2024, 1, 1, 12, 0)),
+            SignedDuration::from_hours(26),
+        );
+        let switches: Vec<_> = Schedule::default()
+            .switches(clock)
+            .take(3)
+            .map(|switch| (switch.at.datetime(), switch.is_dark))
+            .collect();
+        assert_eq!(
+            switches,
+            [
+                (date(2024, 1, 1).at(12, 0, 0, 0), false),
+                (date(2024, 1, 2).at(21, 0, 0, 0), true),
+                (date(2024, 1, 4).at(9, 0, 0, 0), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_follow() {
+        use iced_futures::futures::{StreamExt, future};
+
+        let clock = TestClock(
+            Mutex::new(zoned("UTC", 2024, 1, 1, 12, 0)),
+            SignedDuration::ZERO,
+        );
+        let (mut output, modes) = mpsc::channel(1);
+
+        // Following ends once the modes are no longer received.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let ((), modes) = runtime.block_on(future::join(
+            follow(Schedule::default(), clock, &mut output),
+            modes.take(4).collect::<Vec<_>>(),
+        ));
+        assert_eq!(modes, [false, true, false, true]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Extract accent colors from wallpaper images.
+//!
+//! Pixels are quantized into a grid of OKLab cells. Each cell is ranked by its
+//! chroma and the area of the image it covers, and cells which do not contrast
+//! with the backgrounds of the theme are dropped. The result only depends on
+//! the pixels of the image, so the same image always gives the same accents.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use image::{ImageError, RgbaImage};
+use palette::color_difference::EuclideanDistance;
+use palette::{IntoColor, Oklab, Srgb, Srgba};
+
+use crate::{ThemeBuilder, wcag_contrast};
+
+/// The minimum WCAG 2.x contrast of an accent against the backgrounds of the theme,
+/// which is the minimum for user interface components.
+pub const MIN_ACCENT_CONTRAST: f32 = 3.0;
+
+/// The minimum OKLab chroma of an accent, below which a color reads as gray.
+pub const MIN_ACCENT_CHROMA: f32 = 0.05;
+
+/// The number of pixels sampled from each image, at most.
+const MAX_SAMPLES: u32 = 256 * 256;
+
+/// The size of a cell of the OKLab grid, for lightness and for each chroma axis.
+const CELL_SIZE: (f32, f32) = (0.1, 0.04);
+
+/// The OKLab distance within which a candidate is merged into a higher ranked one.
+const MERGE_DISTANCE: f32 = 0.08;
+
+/// A color of a wallpaper which could be used as the accent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccentCandidate {
+    /// The mean color of the pixels in the cell.
+    pub color: Srgb,
+    /// The fraction of the image covered by the cell and the cells merged into it.
+    pub area: f32,
+    /// The OKLab chroma of the color.
+    pub chroma: f32,
+    /// The rank of the candidate, which is its chroma scaled by the square root of its area.
+    pub score: f32,
+}
+
+#[derive(Default)]
+struct Cell {
+    l: f32,
+    a: f32,
+    b: f32,
+    count: u32,
+}
+
+impl ThemeBuilder {
+    /// The candidate accents of an image, from the best to the worst.
+    ///
+    /// Candidates which contrast less than [`MIN_ACCENT_CONTRAST`] with the background
+    /// or primary container of the built theme are left out.
+    #[must_use]
+    #[cold]
+    pub fn wallpaper_accents(&self, image: &RgbaImage) -> Vec<AccentCandidate> {
+        let theme = self.clone().build();
+        let backgrounds = [theme.background.base, theme.primary.base];
+
+        let mut candidates: Vec<(Oklab, AccentCandidate)> = Vec::new();
+        for (lab, candidate) in quantize(image) {
+            let color = Srgba::from(candidate.color);
+            if backgrounds
+                .iter()
+                .any(|bg| wcag_contrast(color, *bg) < MIN_ACCENT_CONTRAST)
+            {
+                continue;
+            }
+
+            if let Some((_, merged)) = candidates
+                .iter_mut()
+                .find(|(other, _)| other.distance(lab) < MERGE_DISTANCE)
+            {
+                merged.area += candidate.area;
+                merged.score = merged.chroma * merged.area.sqrt();
+                continue;
+            }
+
+            candidates.push((lab, candidate));
+        }
+
+        let mut candidates: Vec<_> = candidates
+            .into_iter()
+            .map(|(_, candidate)| candidate)
+            .collect();
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates
+    }
+
+    /// The candidate accents of an image file, from the best to the worst.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ImageError` if the image cannot be read or decoded.
+    #[cold]
+    pub fn wallpaper_accents_from_path(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<AccentCandidate>, ImageError> {
+        let image = image::open(path)?.to_rgba8();
+        Ok(self.wallpaper_accents(&image))
+    }
+
+    /// Use the best accent of an image, if it has one.
+    #[cold]
+    pub fn wallpaper_accent(self, image: &RgbaImage) -> Self {
+        match self.wallpaper_accents(image).first() {
+            Some(candidate) => self.accent(candidate.color),
+            None => self,
+        }
+    }
+}
+
+/// Quantizes the image in OKLab, returning each colorful cell from the highest score
+/// to the lowest.
+fn quantize(image: &RgbaImage) -> Vec<(Oklab, AccentCandidate)> {
+    let (width, height) = image.dimensions();
+    let stride = ((f64::from(width) * f64::from(height) / f64::from(MAX_SAMPLES))
+        .sqrt()
+        .ceil() as u32)
+        .max(1);
+
+    let mut cells: BTreeMap<(i32, i32, i32), Cell> = BTreeMap::new();
+    let mut total = 0;
+    for y in (0..height).step_by(stride as usize) {
+        for x in (0..width).step_by(stride as usize) {
+            let pixel = image.get_pixel(x, y).0;
+            // Mostly transparent pixels are not seen on the desktop.
+            if pixel[3] < 128 {
+                continue;
+            }
+
+            let lab: Oklab = Srgb::new(pixel[0], pixel[1], pixel[2])
+                .into_format::<f32>()
+                .into_color();
+            let key = (
+                (lab.l / CELL_SIZE.0).floor() as i32,
+                (lab.a / CELL_SIZE.1).floor() as i32,
+                (lab.b / CELL_SIZE.1).floor() as i32,
+            );
+            let cell = cells.entry(key).or_default();
+            cell.l += lab.l;
+            cell.a += lab.a;
+            cell.b += lab.b;
+            cell.count += 1;
+            total += 1;
+        }
+    }
+
+    let mut candidates: Vec<_> = cells
+        .into_values()
+        .filter_map(|cell| {
+            let count = cell.count as f32;
+            let lab = Oklab::new(cell.l / count, cell.a / count, cell.b / count);
+            let chroma = lab.a.hypot(lab.b);
+            if chroma < MIN_ACCENT_CHROMA {
+                return None;
+            }
+
+            let color: Srgb = lab.into_color();
+            let area = count / total as f32;
+            Some((
+                lab,
+                AccentCandidate {
+                    color: color.into_format::<u8>().into_format(),
+                    area,
+                    chroma,
+                    score: chroma * area.sqrt(),
+                },
+            ))
+        })
+        .collect();
+
+    // The sort is stable, so equal scores keep the order of the cells.
+    candidates.sort_by(|(_, a), (_, b)| b.score.total_cmp(&a.score));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    const BLUE: [u8; 3] = [0x2b, 0x7c, 0xd9];
+    const GRAY: [u8; 3] = [0x80, 0x80, 0x80];
+
+    fn fixture() -> RgbaImage {
+        image::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/wallpaper.png"
+        ))
+        .unwrap()
+        .to_rgba8()
+    }
+
+    #[test]
+    fn test_wallpaper_accents() {
+        let image = fixture();
+        let accents = ThemeBuilder::dark().wallpaper_accents(&image);
+        assert_eq!(accents, ThemeBuilder::dark().wallpaper_accents(&image));
+        assert!(!accents.is_empty());
+        for pair in accents.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+        for accent in &accents {
+            assert!(accent.chroma >= MIN_ACCENT_CHROMA);
+        }
+
+        let best: [u8; 3] = accents[0].color.into_format::<u8>().into();
+        let distance = |c: [u8; 3]| {
+            best.iter()
+                .zip(c)
+                .map(|(a, b)| a.abs_diff(b) as u32)
+                .sum::<u32>()
+        };
+        assert!(distance(BLUE) < 16, "{best:?}");
+    }
+
+    #[test]
+    fn test_wallpaper_accents_contrast() {
+        // A light theme has too little contrast with a pale yellow.
+        let image = RgbaImage::from_pixel(8, 8, Rgba([0xf6, 0xe7, 0x8b, 0xff]));
+        assert!(ThemeBuilder::light().wallpaper_accents(&image).is_empty());
+        assert!(!ThemeBuilder::dark().wallpaper_accents(&image).is_empty());
+
+        let image = RgbaImage::from_pixel(8, 8, Rgba([GRAY[0], GRAY[1], GRAY[2], 0xff]));
+        assert!(ThemeBuilder::dark().wallpaper_accents(&image).is_empty());
+
+        let builder = ThemeBuilder::light();
+        assert_eq!(builder.clone().wallpaper_accent(&image), builder);
+    }
+
+    #[test]
+    fn test_wallpaper_accent() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/wallpaper.png");
+        let accents = ThemeBuilder::dark()
+            .wallpaper_accents_from_path(path)
+            .unwrap();
+        let builder = ThemeBuilder::dark().wallpaper_accent(&fixture());
+        assert_eq!(builder.accent, Some(accents[0].color));
+        assert!(
+            ThemeBuilder::dark()
+                .wallpaper_accents_from_path("missing.png")
+                .is_err()
+        );
+    }
+}
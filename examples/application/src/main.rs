@@ -295,9 +295,9 @@ impl cosmic::Application for App {
         Element::from(centered)
     }
 
-    fn header_start(&self) -> Vec<Element<'_, Self::Message>> {
-        vec![cosmic::widget::responsive_menu_bar().into_element(
-            self.core(),
+    fn menu_bar(&self) -> Option<cosmic::app::MenuBar<Self::Message>> {
+        Some(cosmic::app::MenuBar::new(
+            MENU_ID.clone(),
             &self.keybinds,
             MENU_ID.clone(),
             Message::Surface,
@@ -369,7 +369,7 @@ impl cosmic::Application for App {
                     ],
                 ),
             ],
-        )]
+        ))
     }
 }
 
@@ -1,6 +1,27 @@
 # Context Drawer
 close = Close
 
+# Command Palette
+command-palette-search = Search actions
+
+# Keyboard Shortcuts
+keyboard-shortcuts = Keyboard Shortcuts
+shortcuts-search = Search shortcuts
+shortcuts-general = General
+shortcuts-navigation = Navigation
+shortcut-command-palette = Command palette
+shortcut-escape = Cancel
+shortcut-focus-next = Focus next
+shortcut-focus-previous = Focus previous
+shortcut-fullscreen = Maximize window
+shortcut-search = Search
+
+# Undo
+undo = Undo
+undo-command = Undo { $command }
+redo = Redo
+redo-command = Redo { $command }
+
 # About
 license = License
 links = Links
@@ -37,3 +58,8 @@ saturday = Saturday
 sat = Sat
 sunday = Sunday
 sun = Sun
+
+# Settings
+setting-locked = This setting is managed by your administrator
+setting-on = On
+setting-off = Off
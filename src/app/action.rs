@@ -18,6 +18,8 @@ pub enum Action {
     AppThemeChange(Theme),
     /// Requests to close the window.
     Close,
+    /// Updates the command palette.
+    CommandPalette(super::command_palette::Message),
     /// Closes or shows the context drawer.
     ContextDrawer(bool),
     #[cfg(feature = "single-instance")]
@@ -40,6 +42,8 @@ pub enum Action {
     Opened(iced::window::Id),
     /// Set scaling factor
     ScaleFactor(f32),
+    /// Updates the keyboard shortcuts dialog.
+    ShortcutsHelp(super::shortcuts_help::Message),
     /// Show the window menu
     ShowWindowMenu,
     /// Tracks updates to window suggested size.
@@ -54,6 +58,8 @@ pub enum Action {
     SystemThemeChange(Vec<&'static str>, Theme),
     /// Notification of system theme mode changes.
     SystemThemeModeChange(Vec<&'static str>, ThemeMode),
+    /// Advances the animated transition between system themes.
+    ThemeTransitionFrame(std::time::Instant),
     /// Toggles visibility of the nav bar.
     ToggleNavBar,
     /// Toggles the condensed status of the nav bar.
@@ -64,6 +70,8 @@ pub enum Action {
     Unfocus(iced::window::Id),
     /// Windowing system initialized
     WindowingSystemInitialized,
+    /// Saves the window layouts if no window was resized since the given resize.
+    WindowLayoutSettled(u64),
     /// Updates the window maximized state
     WindowMaximized(iced::window::Id, bool),
     /// Updates the tracked window geometry.
@@ -0,0 +1,670 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! A command palette which fuzzy-searches the actions of an application.
+//!
+//! The palette is toggled with the `command-palette` shortcut of [`keyboard_nav`],
+//! which is Ctrl+Shift+P by default. It lists the actions of [`Application::menu_bar`],
+//! the [`Commands`] returned by [`Application::commands`] and the entries of the nav
+//! bar, and dispatches the message of the command which is activated.
+//!
+//! Menus shown elsewhere are listed by passing their items to [`Commands::menu`], and
+//! other shortcuts with [`Commands::shortcuts`].
+//!
+//! [`keyboard_nav`]: crate::keyboard_nav
+
+use super::{Application, Task};
+use crate::app::shortcuts::{Shortcut, Shortcuts};
+use crate::widget::menu::{self, KeyBind};
+use crate::widget::{self, nav_bar};
+use crate::{Apply, Element, fl, theme};
+use iced::advanced::widget::text::Style as TextStyle;
+use iced::{Alignment, Event, Length, Subscription, event, keyboard};
+use iced_core::keyboard::Key;
+use iced_core::keyboard::key::Named;
+use iced_futures::event::listen_raw;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Id of the search input of the palette.
+static INPUT_ID: LazyLock<widget::Id> =
+    LazyLock::new(|| widget::Id::new("COSMIC_command_palette_search"));
+
+/// A message of the command palette.
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// Activates the result at the index.
+    Activate(usize),
+    /// Closes the palette.
+    Close,
+    /// Selects the next result.
+    Next,
+    /// Selects the previous result.
+    Previous,
+    /// Updates the search query.
+    Query(String),
+    /// Activates the selected result.
+    Submit,
+    /// Opens or closes the palette.
+    Toggle,
+}
+
+/// The search state of an open palette.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    query: String,
+    selected: usize,
+}
+
+/// An action listed in the command palette.
+#[derive(Clone, Debug)]
+pub struct Command<Message> {
+    /// Label of the action.
+    pub label: String,
+    /// Labels of the menus which contain the action, outermost first.
+    pub path: Vec<String>,
+    /// Key binding which triggers the action, if any.
+    pub key_bind: Option<KeyBind>,
+    /// Message dispatched when the command is activated.
+    pub message: crate::Action<Message>,
+}
+
+/// The actions listed in the command palette, in the order they are shown before
+/// searching.
+#[derive(Clone, Debug)]
+pub struct Commands<Message> {
+    commands: Vec<Command<Message>>,
+    /// Every key binding of the menus and shortcuts, with the root of the menu which
+    /// contains it and its label, for the keyboard shortcuts dialog.
+    key_binds: Vec<(Option<String>, String, KeyBind)>,
+}
+
+impl<Message> Default for Commands<Message> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            key_binds: Vec::new(),
+        }
+    }
+}
+
+impl<Message> Commands<Message> {
+    /// An empty list of commands.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a command which dispatches `message`.
+    #[must_use]
+    pub fn command(self, label: impl Into<String>, message: Message) -> Self {
+        self.push(Command {
+            label: label.into(),
+            path: Vec::new(),
+            key_bind: None,
+            message: crate::Action::App(message),
+        })
+    }
+
+    /// Adds a command.
+    #[must_use]
+    pub fn push(mut self, command: Command<Message>) -> Self {
+        self.commands.push(command);
+        self
+    }
+
+    /// Adds the enabled actions of a menu, such as a root of the menu bar, labelled with
+    /// the key bindings of `key_binds` as in [`menu::items`].
+    ///
+    /// Actions in folders are listed with the labels of the folders as their path.
+    #[must_use]
+    pub fn menu<A, L>(
+        mut self,
+        label: impl Into<String>,
+        key_binds: &HashMap<KeyBind, A>,
+        items: Vec<menu::Item<A, L>>,
+    ) -> Self
+    where
+        A: menu::Action<Message = Message>,
+        L: Into<Cow<'static, str>>,
+    {
+        self.push_menu(&mut vec![label.into()], key_binds, items);
+        self
+    }
+
+    /// Adds the registered shortcuts which are not already listed, labelled with their
+    /// [`Shortcut::label`], or else their names.
+    ///
+    /// A shortcut is already listed when a command has its key binding, as actions added
+    /// with [`Commands::menu`] do when given [`Shortcuts::key_binds`].
+    #[must_use]
+    pub fn shortcuts<A>(mut self, shortcuts: &Shortcuts<A>) -> Self
+    where
+        A: menu::Action<Message = Message>,
+    {
+        for shortcut in shortcuts.iter() {
+            let name = shortcut
+                .label
+                .clone()
+                .unwrap_or_else(|| shortcut.name.clone());
+
+            // Other bindings of an action in a menu are shown with the menu item.
+            let (menu, label) = self
+                .key_binds
+                .iter()
+                .find(|(_, _, key_bind)| shortcut.key_binds.contains(key_bind))
+                .map_or_else(
+                    || (None, name.clone()),
+                    |(menu, label, _)| (menu.clone(), label.clone()),
+                );
+            for key_bind in &shortcut.key_binds {
+                if !self
+                    .key_binds
+                    .iter()
+                    .any(|(_, _, listed)| listed == key_bind)
+                {
+                    self.key_binds
+                        .push((menu.clone(), label.clone(), key_bind.clone()));
+                }
+            }
+
+            let listed = self.commands.iter().any(|command| {
+                command
+                    .key_bind
+                    .as_ref()
+                    .is_some_and(|key_bind| shortcut.key_binds.contains(key_bind))
+            });
+
+            if !listed {
+                self.commands.push(Command {
+                    label: name,
+                    path: Vec::new(),
+                    key_bind: shortcut.key_binds.first().cloned(),
+                    message: crate::Action::App(shortcut.action.message()),
+                });
+            }
+        }
+        self
+    }
+
+    /// Adds the commands and key bindings of `other` which are not listed yet.
+    ///
+    /// A command is already listed when a command has its key binding, or its label and
+    /// path.
+    #[must_use]
+    pub(crate) fn merge(mut self, other: Self) -> Self {
+        for command in other.commands {
+            let listed = self.commands.iter().any(|listed| {
+                (listed.label == command.label && listed.path == command.path)
+                    || (listed.key_bind.is_some() && listed.key_bind == command.key_bind)
+            });
+
+            if !listed {
+                self.commands.push(command);
+            }
+        }
+
+        for key_bind in other.key_binds {
+            if !self
+                .key_binds
+                .iter()
+                .any(|(_, _, listed)| *listed == key_bind.2)
+            {
+                self.key_binds.push(key_bind);
+            }
+        }
+
+        self
+    }
+
+    /// Adds the enabled entries of a nav bar, which are activated as if selected.
+    #[must_use]
+    pub fn nav_model(mut self, model: &nav_bar::Model) -> Self {
+        for id in model.iter() {
+            if let Some(text) = model.text(id)
+                && model.is_enabled(id)
+            {
+                self.commands.push(Command {
+                    label: text.to_owned(),
+                    path: Vec::new(),
+                    key_bind: None,
+                    message: crate::Action::Cosmic(super::Action::NavBar(id)),
+                });
+            }
+        }
+        self
+    }
+
+    /// Every command, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Command<Message>> {
+        self.commands.iter()
+    }
+
+    /// Whether there are no commands.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Every key binding of the menus and shortcuts which were added, with the root of
+    /// the menu which contains it and its label.
+    pub(crate) fn key_binds(&self) -> &[(Option<String>, String, KeyBind)] {
+        &self.key_binds
+    }
+
+    /// The commands which fuzzy-match `query`, from the best match to the worst.
+    ///
+    /// Every command matches an empty query, in the order they were added.
+    #[must_use]
+    pub fn search(&self, query: &str) -> Vec<&Command<Message>> {
+        let mut results: Vec<(u32, &Command<Message>)> = self
+            .commands
+            .iter()
+            .filter_map(|command| {
+                // Matches of the label are preferred over matches of its menu path.
+                fuzzy_score(query, &command.label)
+                    .map(|score| score + 1)
+                    .or_else(|| {
+                        fuzzy_score(
+                            query,
+                            &format!("{} {}", command.path.join(" "), command.label),
+                        )
+                    })
+                    .map(|score| (score, command))
+            })
+            .collect();
+
+        results.sort_by(|(a, _), (b, _)| b.cmp(a));
+        results.into_iter().map(|(_, command)| command).collect()
+    }
+
+    fn push_menu<A, L>(
+        &mut self,
+        path: &mut Vec<String>,
+        key_binds: &HashMap<KeyBind, A>,
+        items: Vec<menu::Item<A, L>>,
+    ) where
+        A: menu::Action<Message = Message>,
+        L: Into<Cow<'static, str>>,
+    {
+        for item in items {
+            let (label, action, enabled) = match item {
+                menu::Item::Button(label, _, action)
+                | menu::Item::CheckBox(label, _, _, action) => (label, action, true),
+                menu::Item::ButtonDisabled(label, _, action) => (label, action, false),
+                menu::Item::Folder(label, children) => {
+                    path.push(label.into().into_owned());
+                    self.push_menu(path, key_binds, children);
+                    path.pop();
+                    continue;
+                }
+                menu::Item::Divider => continue,
+            };
+
+            let label = label.into().into_owned();
+            let mut action_key_binds: Vec<&KeyBind> = key_binds
+                .iter()
+                .filter(|(_, key_action)| **key_action == action)
+                .map(|(key_bind, _)| key_bind)
+                .collect();
+            action_key_binds.sort();
+
+            // The shortcuts of disabled items are still shown in the shortcuts dialog.
+            for key_bind in &action_key_binds {
+                self.key_binds
+                    .push((path.first().cloned(), label.clone(), (*key_bind).clone()));
+            }
+
+            if enabled {
+                self.commands.push(Command {
+                    label,
+                    path: path.clone(),
+                    key_bind: action_key_binds.first().map(|key_bind| (*key_bind).clone()),
+                    message: crate::Action::App(action.message()),
+                });
+            }
+        }
+    }
+}
+
+/// The commands of the menu bar, the application and its nav bar.
+pub(crate) fn commands<App: Application>(app: &App) -> Commands<App::Message> {
+    let commands = match app.menu_bar() {
+        Some(menu_bar) => menu_bar.commands().clone().merge(app.commands()),
+        None => app.commands(),
+    };
+    match app.nav_model() {
+        Some(model) => commands.nav_model(model),
+        None => commands,
+    }
+}
+
+pub(crate) fn update<App: Application>(app: &mut App, message: Message) -> Task<App::Message> {
+    match message {
+        Message::Toggle => {
+            if app.core_mut().command_palette.take().is_none() && !commands(app).is_empty() {
+                app.core_mut().command_palette = Some(State::default());
+                app.core_mut().shortcuts_help = None;
+                return widget::text_input::focus(INPUT_ID.clone());
+            }
+        }
+
+        Message::Close => app.core_mut().command_palette = None,
+
+        Message::Query(query) => {
+            if let Some(state) = app.core_mut().command_palette.as_mut() {
+                state.query = query;
+                state.selected = 0;
+            }
+        }
+
+        Message::Next | Message::Previous => {
+            let Some(state) = app.core().command_palette.as_ref() else {
+                return Task::none();
+            };
+            let len = commands(app).search(&state.query).len();
+            let state = app.core_mut().command_palette.as_mut().unwrap();
+            if len > 0 {
+                state.selected = if matches!(message, Message::Next) {
+                    (state.selected + 1) % len
+                } else {
+                    (state.selected + len - 1) % len
+                };
+            }
+        }
+
+        Message::Activate(index) => return activate(app, index),
+
+        Message::Submit => {
+            if let Some(selected) = app.core().command_palette.as_ref().map(|s| s.selected) {
+                return activate(app, selected);
+            }
+        }
+    }
+
+    Task::none()
+}
+
+fn activate<App: Application>(app: &mut App, index: usize) -> Task<App::Message> {
+    let Some(state) = app.core_mut().command_palette.take() else {
+        return Task::none();
+    };
+
+    let commands = commands(app);
+    match commands.search(&state.query).get(index) {
+        Some(command) => Task::done(command.message.clone()),
+        None => Task::none(),
+    }
+}
+
+/// Selects results with the arrow keys while the palette is open.
+pub(crate) fn subscription() -> Subscription<Message> {
+    listen_raw(|event, status, _| {
+        let Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Named(key),
+            ..
+        }) = event
+        else {
+            return None;
+        };
+
+        match key {
+            Named::ArrowDown => Some(Message::Next),
+            Named::ArrowUp => Some(Message::Previous),
+            // The search input captures escape, which otherwise reaches `keyboard_nav`.
+            Named::Escape if status == event::Status::Captured => Some(Message::Close),
+            _ => None,
+        }
+    })
+}
+
+pub(crate) fn view<App: Application>(
+    app: &App,
+) -> Option<Element<'_, crate::Action<App::Message>>> {
+    let state = app.core().command_palette.as_ref()?;
+    let spacing = theme::spacing();
+    let commands = commands(app);
+
+    let results: Vec<Element<'_, _>> = commands
+        .search(&state.query)
+        .into_iter()
+        .enumerate()
+        .map(|(i, command)| {
+            let path = command.path.join(" › ");
+            let key_bind = command
+                .key_bind
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+
+            menu::menu_button(vec![
+                widget::text(command.label.clone()).into(),
+                widget::space::horizontal().width(spacing.space_xs).into(),
+                widget::text(path)
+                    .class(theme::Text::Custom(dim_text))
+                    .into(),
+                widget::space::horizontal().into(),
+                widget::text(key_bind)
+                    .class(theme::Text::Custom(dim_text))
+                    .into(),
+            ])
+            .selected(i == state.selected)
+            .on_press(crate::Action::Cosmic(super::Action::CommandPalette(
+                Message::Activate(i),
+            )))
+            .into()
+        })
+        .collect();
+
+    let input = widget::search_input(fl!("command-palette-search"), state.query.as_str())
+        .id(INPUT_ID.clone())
+        .on_input(|query| {
+            crate::Action::Cosmic(super::Action::CommandPalette(Message::Query(query)))
+        })
+        .on_submit(|_| crate::Action::Cosmic(super::Action::CommandPalette(Message::Submit)));
+
+    let palette = widget::column::with_capacity(2)
+        .push(input)
+        .push(
+            widget::scrollable(widget::column::with_children(results)).height(Length::Fixed(360.0)),
+        )
+        .spacing(spacing.space_xs)
+        .align_x(Alignment::Center)
+        .apply(widget::container)
+        .class(crate::style::Container::Dialog)
+        .padding(spacing.space_s)
+        .width(Length::Fill)
+        .max_width(560);
+
+    Some(palette.into())
+}
+
+fn dim_text(theme: &crate::Theme) -> TextStyle {
+    let mut color = theme.cosmic().background.component.on;
+    color.alpha *= 0.75;
+    TextStyle {
+        color: Some(color.into()),
+        ..Default::default()
+    }
+}
+
+/// Scores how well `query` matches `text`, or `None` unless every character of the query
+/// appears in the text in order, ignoring case.
+///
+/// Characters which begin a word or follow the previous match score higher.
+pub(crate) fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut text = text.chars().enumerate();
+    let mut prev: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (i, c) = text.next()?;
+            let word_start = prev.is_none_or(|p| !p.is_alphanumeric());
+            prev = Some(c);
+
+            if c.to_lowercase().eq(q.to_lowercase()) {
+                score += 1;
+                if word_start {
+                    score += 3;
+                }
+                if last_match.is_some_and(|last| last + 1 == i) {
+                    score += 2;
+                }
+                last_match = Some(i);
+                break;
+            }
+        }
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TestAction as Action;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "Open"), Some(0));
+        assert_eq!(fuzzy_score("opn", "Open"), fuzzy_score("OPN", "open"));
+        assert_eq!(fuzzy_score("xo", "Open"), None);
+        assert_eq!(fuzzy_score("nepo", "Open"), None);
+        // Word starts and consecutive characters are preferred.
+        assert!(fuzzy_score("nt", "New tab") > fuzzy_score("nt", "Print"));
+        assert!(fuzzy_score("op", "Open") > fuzzy_score("op", "Overlap"));
+    }
+
+    fn labels<'a>(commands: impl IntoIterator<Item = &'a Command<Action>>) -> Vec<&'a str> {
+        commands
+            .into_iter()
+            .map(|command| command.label.as_str())
+            .collect()
+    }
+
+    fn file_menu(key_binds: &HashMap<KeyBind, Action>) -> Commands<Action> {
+        Commands::new().menu(
+            "File",
+            key_binds,
+            vec![
+                menu::Item::Button("New window", None, Action::New),
+                menu::Item::Button("Open", None, Action::Open),
+                menu::Item::ButtonDisabled("Close", None, Action::Close),
+                menu::Item::Divider,
+                menu::Item::Folder(
+                    "Open recent",
+                    vec![menu::Item::Button("Notes", None, Action::Recent)],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_menu() {
+        let key_binds = HashMap::from([
+            ("Ctrl + O".parse().unwrap(), Action::Open),
+            ("Ctrl + W".parse().unwrap(), Action::Close),
+        ]);
+        let commands = file_menu(&key_binds);
+
+        // Disabled items and dividers are skipped.
+        assert_eq!(labels(commands.iter()), ["New window", "Open", "Notes"]);
+
+        let paths: Vec<_> = commands
+            .iter()
+            .map(|command| command.path.clone())
+            .collect();
+        assert_eq!(
+            paths,
+            [vec!["File"], vec!["File"], vec!["File", "Open recent"]]
+        );
+
+        let key_binds: Vec<_> = commands
+            .iter()
+            .map(|command| command.key_bind.as_ref().map(ToString::to_string))
+            .collect();
+        assert_eq!(key_binds, [None, Some("Ctrl + O".to_owned()), None]);
+
+        assert!(matches!(
+            commands.iter().nth(2).unwrap().message,
+            crate::Action::App(Action::Recent)
+        ));
+    }
+
+    #[test]
+    fn test_search() {
+        let commands = file_menu(&HashMap::new()).command("Quit", Action::Quit);
+
+        // An empty query lists every command in order.
+        assert_eq!(
+            labels(commands.search("")),
+            ["New window", "Open", "Notes", "Quit"]
+        );
+        // Better matches come first.
+        assert_eq!(
+            labels(commands.search("o")),
+            ["Open", "New window", "Notes"]
+        );
+        assert_eq!(labels(commands.search("qu")), ["Quit"]);
+        // The path of a command matches after its label.
+        assert_eq!(labels(commands.search("recent")), ["Notes"]);
+        assert!(commands.search("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        let shortcuts = Shortcuts::new()
+            .register("open", Action::Open, ["Ctrl + O".parse().unwrap()])
+            .register("quit", Action::Quit, ["Ctrl + Q".parse().unwrap()])
+            .register("about", Action::About, ["F1".parse().unwrap()])
+            .label("open", "Open a file")
+            .label("quit", "Quit");
+        let commands = file_menu(shortcuts.key_binds()).shortcuts(&shortcuts);
+
+        // Open is listed by its menu item, quit by its label, and about by its name.
+        assert_eq!(
+            labels(commands.iter()),
+            ["New window", "Open", "Notes", "Quit", "about"]
+        );
+        let quit = commands.iter().nth(3).unwrap();
+        assert_eq!(
+            quit.key_bind.as_ref().map(ToString::to_string).as_deref(),
+            Some("Ctrl + Q")
+        );
+        assert!(quit.path.is_empty());
+    }
+
+    #[test]
+    fn test_merge() {
+        let key_binds = HashMap::from([("Ctrl + O".parse().unwrap(), Action::Open)]);
+        let shortcuts = Shortcuts::new()
+            .register("open", Action::Open, ["Ctrl + O".parse().unwrap()])
+            .register("quit", Action::Quit, ["Ctrl + Q".parse().unwrap()])
+            .label("quit", "Quit");
+        let app = file_menu(&HashMap::new())
+            .command("About", Action::About)
+            .shortcuts(&shortcuts);
+        let commands = file_menu(&key_binds).merge(app);
+
+        // Commands of the menu bar which the application lists again are skipped.
+        assert_eq!(
+            labels(commands.iter()),
+            ["New window", "Open", "Notes", "About", "Quit"]
+        );
+        let key_binds: Vec<_> = commands
+            .key_binds()
+            .iter()
+            .map(|(menu, label, key_bind)| (menu.as_deref(), label.as_str(), key_bind.to_string()))
+            .collect();
+        assert_eq!(
+            key_binds,
+            [
+                (Some("File"), "Open", "Ctrl + O".to_owned()),
+                (None, "Quit", "Ctrl + Q".to_owned())
+            ]
+        );
+    }
+}
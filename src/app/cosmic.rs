@@ -5,7 +5,8 @@ use std::borrow::Borrow;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use super::{Action, Application, ApplicationExt, Subscription};
+use super::{Action, Application, ApplicationExt, Subscription, command_palette, shortcuts_help};
+use crate::theme::transition::Transition;
 use crate::theme::{THEME, Theme, ThemeType};
 use crate::{Core, Element, keyboard_nav};
 #[cfg(all(feature = "wayland", target_os = "linux"))]
@@ -80,6 +81,10 @@ fn init_windowing_system<M>(handle: window::raw_window_handle::WindowHandle) ->
     crate::Action::Cosmic(Action::WindowingSystemInitialized)
 }
 
+/// How long a window must go without being resized before its layout is saved.
+#[cfg(feature = "tokio")]
+const WINDOW_LAYOUT_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
 #[derive(Default)]
 pub struct Cosmic<App: Application> {
     pub app: App,
@@ -94,6 +99,8 @@ pub struct Cosmic<App: Application> {
     >,
     pub tracked_windows: HashSet<window::Id>,
     pub opened_surfaces: HashMap<window::Id, u32>,
+    /// The animated transition to the latest system theme, if one is running.
+    pub theme_transition: Option<Transition>,
 }
 
 impl<T: Application> Cosmic<T>
@@ -543,6 +550,14 @@ where
                 .map(crate::Action::Cosmic),
         ];
 
+        if self.theme_transition.is_some() {
+            subscriptions.push(
+                window::frames()
+                    .map(Action::ThemeTransitionFrame)
+                    .map(crate::Action::Cosmic),
+            );
+        }
+
         if self.app.core().keyboard_nav {
             subscriptions.push(
                 keyboard_nav::subscription()
@@ -551,6 +566,22 @@ where
             );
         }
 
+        if self.app.core().command_palette.is_some() {
+            subscriptions.push(
+                command_palette::subscription()
+                    .map(Action::CommandPalette)
+                    .map(crate::Action::Cosmic),
+            );
+        }
+
+        if self.app.core().shortcuts_help.is_some() {
+            subscriptions.push(
+                shortcuts_help::subscription()
+                    .map(Action::ShortcutsHelp)
+                    .map(crate::Action::Cosmic),
+            );
+        }
+
         #[cfg(feature = "single-instance")]
         if self.app.core().single_instance {
             subscriptions.push(crate::dbus_activation::subscription::<T>());
@@ -622,6 +653,8 @@ impl<T: Application> Cosmic<T> {
     fn cosmic_update(&mut self, message: Action) -> iced::Task<crate::Action<T::Message>> {
         match message {
             Action::WindowMaximized(id, maximized) => {
+                self.app.core_mut().window_layout_maximized(id, maximized);
+
                 #[cfg(not(all(feature = "wayland", target_os = "linux")))]
                 if self
                     .app
@@ -645,11 +678,35 @@ impl<T: Application> Cosmic<T> {
                 }
 
                 self.app.on_window_resize(id, width, height);
+                let resize = self
+                    .app
+                    .core_mut()
+                    .window_layout_resized(id, iced::Size::new(width, height));
 
                 //TODO: more efficient test of maximized (winit has no event for maximize if set by the OS)
-                return iced::window::is_maximized(id).map(move |maximized| {
+                let is_maximized = iced::window::is_maximized(id).map(move |maximized| {
                     crate::Action::Cosmic(Action::WindowMaximized(id, maximized))
                 });
+
+                // Save the layout once the window stops resizing, in case the app exits without closing it.
+                #[cfg(feature = "tokio")]
+                if let Some(resize) = resize {
+                    return Task::batch([
+                        is_maximized,
+                        crate::task::future(async move {
+                            tokio::time::sleep(WINDOW_LAYOUT_SAVE_DELAY).await;
+                            crate::Action::Cosmic(Action::WindowLayoutSettled(resize))
+                        }),
+                    ]);
+                }
+                #[cfg(not(feature = "tokio"))]
+                let _ = resize;
+
+                return is_maximized;
+            }
+
+            Action::WindowLayoutSettled(resize) => {
+                self.app.core_mut().window_layout_settled(resize);
             }
 
             #[cfg(all(feature = "wayland", target_os = "linux"))]
@@ -728,12 +785,39 @@ impl<T: Application> Cosmic<T> {
                 keyboard_nav::Action::FocusPrevious => {
                     return iced::widget::operation::focus_previous().map(crate::Action::Cosmic);
                 }
-                keyboard_nav::Action::Escape => return self.app.on_escape(),
+                keyboard_nav::Action::CommandPalette => {
+                    return command_palette::update(
+                        &mut self.app,
+                        command_palette::Message::Toggle,
+                    );
+                }
+                keyboard_nav::Action::Escape => {
+                    let core = self.app.core_mut();
+                    if core.command_palette.is_some() || core.shortcuts_help.is_some() {
+                        core.command_palette = None;
+                        core.shortcuts_help = None;
+                        return iced::Task::none();
+                    }
+                    return self.app.on_escape();
+                }
+                keyboard_nav::Action::KeyboardShortcuts => {
+                    return shortcuts_help::update(&mut self.app, shortcuts_help::Message::Toggle);
+                }
                 keyboard_nav::Action::Search => return self.app.on_search(),
+                keyboard_nav::Action::Undo => return self.app.on_undo(),
+                keyboard_nav::Action::Redo => return self.app.on_redo(),
 
                 keyboard_nav::Action::Fullscreen => return self.app.core().toggle_maximize(None),
             },
 
+            Action::CommandPalette(message) => {
+                return command_palette::update(&mut self.app, message);
+            }
+
+            Action::ShortcutsHelp(message) => {
+                return shortcuts_help::update(&mut self.app, message);
+            }
+
             Action::ContextDrawer(show) => {
                 self.app.core_mut().set_show_context(show);
                 return self.app.on_context_drawer();
@@ -776,9 +860,18 @@ impl<T: Application> Cosmic<T> {
                     };
                 }
 
+                self.theme_transition = None;
                 THEME.lock().unwrap().set_theme(theme.theme_type);
             }
 
+            Action::ThemeTransitionFrame(now) => {
+                if let Some(transition) = &self.theme_transition
+                    && transition.frame(&mut THEME.lock().unwrap(), now)
+                {
+                    self.theme_transition = None;
+                }
+            }
+
             Action::SystemThemeChange(keys, theme) => {
                 let cur_is_dark = THEME.lock().unwrap().theme_type.is_dark();
                 // Ignore updates if the current theme mode does not match.
@@ -810,7 +903,8 @@ impl<T: Application> Cosmic<T> {
                         };
                         new_theme.theme_type.prefer_dark(prefer_dark);
 
-                        cosmic_theme.set_theme(new_theme.theme_type);
+                        self.theme_transition =
+                            Transition::start(&mut cosmic_theme, new_theme.theme_type);
                         #[cfg(all(feature = "wayland", target_os = "linux"))]
                         if self.app.core().sync_window_border_radii_to_theme() {
                             use iced_runtime::platform_specific::wayland::CornerRadius;
@@ -906,6 +1000,7 @@ impl<T: Application> Cosmic<T> {
             }
 
             Action::Close => {
+                self.app.core_mut().save_window_layouts();
                 return match self.app.on_app_exit() {
                     Some(message) => self.app.update(message),
                     None => self.close(),
@@ -956,7 +1051,8 @@ impl<T: Application> Cosmic<T> {
 
                         // Only apply update if the theme is set to load a system theme
                         if let ThemeType::System { .. } = cosmic_theme.theme_type {
-                            cosmic_theme.set_theme(new_theme.theme_type);
+                            self.theme_transition =
+                                Transition::start(&mut cosmic_theme, new_theme.theme_type);
                             #[cfg(all(feature = "wayland", target_os = "linux"))]
                             if self.app.core().sync_window_border_radii_to_theme() {
                                 use iced_runtime::platform_specific::wayland::CornerRadius;
@@ -1074,6 +1170,8 @@ impl<T: Application> Cosmic<T> {
             Action::Surface(action) => return self.surface_update(action),
 
             Action::SurfaceClosed(id) => {
+                self.app.core_mut().window_layout_closed(id);
+
                 if self.opened_surfaces.get_mut(&id).is_some_and(|v| {
                     *v = v.saturating_sub(1);
                     *v == 0
@@ -1147,7 +1245,8 @@ impl<T: Application> Cosmic<T> {
 
                         // Only apply update if the theme is set to load a system theme
                         if let ThemeType::System { theme: _, .. } = cosmic_theme.theme_type {
-                            cosmic_theme.set_theme(new_theme.theme_type);
+                            self.theme_transition =
+                                Transition::start(&mut cosmic_theme, new_theme.theme_type);
                         }
                     }
                 }
@@ -1172,12 +1271,19 @@ impl<T: Application> Cosmic<T> {
                     if let ThemeType::System {
                         theme: t,
                         prefer_dark,
-                    } = cosmic_theme.theme_type.clone()
+                    } = self
+                        .theme_transition
+                        .as_ref()
+                        .map_or(&cosmic_theme.theme_type, Transition::target)
+                        .clone()
                     {
-                        cosmic_theme.set_theme(ThemeType::System {
-                            theme: Arc::new(t.with_accent(c)),
-                            prefer_dark,
-                        });
+                        self.theme_transition = Transition::start(
+                            &mut cosmic_theme,
+                            ThemeType::System {
+                                theme: Arc::new(t.with_accent(c)),
+                                prefer_dark,
+                            },
+                        );
                     }
                 }
             }
@@ -1300,6 +1406,7 @@ impl<App: Application> Cosmic<App> {
             surface_views: HashMap::new(),
             tracked_windows: HashSet::new(),
             opened_surfaces: HashMap::new(),
+            theme_transition: None,
         }
     }
 
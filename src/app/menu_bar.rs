@@ -0,0 +1,87 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! The menu bar of the header of an application.
+//!
+//! The [`MenuBar`] returned by [`Application::menu_bar`] is shown at the start of the
+//! header. Its actions are listed in the command palette and its key bindings in the
+//! keyboard shortcuts dialog, without passing its items to [`Application::commands`].
+//!
+//! [`Application::menu_bar`]: super::Application::menu_bar
+//! [`Application::commands`]: super::Application::commands
+
+use super::command_palette::Commands;
+use crate::widget::menu::{self, KeyBind};
+use crate::widget::{self, ResponsiveMenuBar, responsive_menu_bar};
+use crate::{Core, Element, surface};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A responsive menu bar, which collapses into a single menu when the header is too
+/// narrow, along with the commands of its menus.
+pub struct MenuBar<Message> {
+    commands: Commands<Message>,
+    widget: ResponsiveMenuBar,
+    view: Box<dyn Fn(ResponsiveMenuBar, &Core) -> Element<'static, Message>>,
+}
+
+impl<Message: Clone + 'static> MenuBar<Message> {
+    /// A menu bar with a menu for each of `roots`, labelled with the key bindings of
+    /// `key_binds` as in [`menu::items`].
+    ///
+    /// The size of the menu bar is tracked by `id`, and the surface actions of its
+    /// menus are dispatched with `on_surface_action`.
+    #[must_use]
+    pub fn new<A, L>(
+        id: widget::Id,
+        key_binds: &HashMap<KeyBind, A>,
+        on_surface_action: impl Fn(surface::Action) -> Message + Send + Sync + Clone + 'static,
+        roots: Vec<(L, Vec<menu::Item<A, L>>)>,
+    ) -> Self
+    where
+        A: menu::Action<Message = Message> + 'static,
+        L: Into<Cow<'static, str>> + Clone + 'static,
+    {
+        let commands = roots
+            .iter()
+            .cloned()
+            .fold(Commands::new(), |commands, (label, items)| {
+                let label: Cow<'static, str> = label.into();
+                commands.menu(label, key_binds, items)
+            });
+
+        let key_binds = key_binds.clone();
+        let view = move |widget: ResponsiveMenuBar, core: &Core| {
+            widget.into_element(
+                core,
+                &key_binds,
+                id.clone(),
+                on_surface_action.clone(),
+                roots.clone(),
+            )
+        };
+
+        Self {
+            commands,
+            widget: responsive_menu_bar(),
+            view: Box::new(view),
+        }
+    }
+
+    /// Sets the item sizes and spacing of the menus.
+    #[must_use]
+    pub fn responsive(mut self, widget: ResponsiveMenuBar) -> Self {
+        self.widget = widget;
+        self
+    }
+
+    /// The enabled actions of the menus, with every key binding of their items.
+    #[must_use]
+    pub fn commands(&self) -> &Commands<Message> {
+        &self.commands
+    }
+
+    pub(crate) fn view(&self, core: &Core) -> Element<'static, Message> {
+        (self.view)(self.widget, core)
+    }
+}
@@ -9,21 +9,30 @@
 mod action;
 pub use action::Action;
 use cosmic_config::CosmicConfigEntry;
+pub mod command_palette;
+pub use command_palette::Commands;
 pub mod context_drawer;
 pub use context_drawer::{ContextDrawer, context_drawer};
 use iced::application::BootFn;
 pub mod cosmic;
+pub mod menu_bar;
+pub use menu_bar::MenuBar;
 pub mod settings;
+pub mod shortcuts;
+pub mod shortcuts_help;
+pub mod undo;
 
 pub type Task<M> = iced::Task<crate::Action<M>>;
 
 pub use crate::Core;
+pub use crate::config::window_layout::WindowLayout;
 use crate::prelude::*;
 use crate::theme::THEME;
 use crate::widget::{container, id_container, menu, nav_bar, popover, space};
 use apply::Apply;
 use iced::{Length, Subscription, theme, window};
 pub use settings::Settings;
+pub use shortcuts::Shortcuts;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -50,6 +59,30 @@ pub(crate) fn iced_settings<App: Application>(
 
     THEME.lock().unwrap().set_theme(settings.theme.theme_type);
 
+    let mut nav_shortcuts = shortcuts::keyboard_nav();
+    match shortcuts::config(crate::config::ID) {
+        Ok(config) => {
+            for why in nav_shortcuts.load(&config) {
+                tracing::error!(?why, "failed to load keyboard navigation shortcut");
+            }
+        }
+        Err(why) => tracing::error!(?why, "failed to open keyboard shortcuts config"),
+    }
+    for conflict in nav_shortcuts.conflicts() {
+        tracing::warn!(
+            key_bind = %conflict.key_bind,
+            names = ?conflict.names,
+            "keyboard navigation shortcuts share a key binding, which triggers the first of them"
+        );
+    }
+    crate::keyboard_nav::set_key_binds(nav_shortcuts.iter().flat_map(|shortcut| {
+        let action = shortcut.action;
+        shortcut
+            .key_binds
+            .iter()
+            .map(move |key_bind| (key_bind.clone(), action))
+    }));
+
     if settings.no_main_window {
         core.main_window = Some(iced::window::Id::NONE);
     }
@@ -87,6 +120,20 @@ pub(crate) fn iced_settings<App: Application>(
     }
 
     window_settings.transparent = settings.transparent;
+
+    if settings.persist_window_layout {
+        match cosmic_config::Config::new_state(App::APP_ID, WindowLayout::VERSION) {
+            Ok(config) => {
+                core.window_layout_config = Some(config);
+                if let Some(layout) = core.window_layout(WindowLayout::MAIN_KEY) {
+                    layout.apply(&mut window_settings);
+                    core.restore_window_layout(&layout);
+                }
+            }
+            Err(why) => tracing::error!(?why, "failed to open window layout state"),
+        }
+    }
+
     (iced, (core, flags), window_settings)
 }
 
@@ -347,6 +394,14 @@ where
     /// Creates the application, and optionally emits task on initialize.
     fn init(core: Core, flags: Self::Flags) -> (Self, Task<Self::Message>);
 
+    /// Actions listed in the command palette after those of [`Application::menu_bar`],
+    /// followed by the entries of the nav bar.
+    ///
+    /// Commands which the menu bar already lists are skipped.
+    fn commands(&self) -> Commands<Self::Message> {
+        Commands::new()
+    }
+
     /// Displays a context drawer on the side of the application window when `Some`.
     /// Use the [`ApplicationExt::set_show_context`] function for this to take effect.
     fn context_drawer(&self) -> Option<ContextDrawer<'_, Self::Message>> {
@@ -363,7 +418,7 @@ where
         None
     }
 
-    /// Attaches elements to the start section of the header.
+    /// Attaches elements to the start section of the header, after the menu bar.
     fn header_start(&self) -> Vec<Element<'_, Self::Message>> {
         Vec::new()
     }
@@ -378,6 +433,12 @@ where
         Vec::new()
     }
 
+    /// Displays a menu bar at the start of the header when `Some`, whose actions and key
+    /// bindings are also listed in the command palette and the keyboard shortcuts dialog.
+    fn menu_bar(&self) -> Option<MenuBar<Self::Message>> {
+        None
+    }
+
     /// Allows overriding the default nav bar widget.
     fn nav_bar(&self) -> Option<Element<'_, crate::Action<Self::Message>>> {
         if !self.core().nav_bar_active() {
@@ -449,6 +510,16 @@ where
         Task::none()
     }
 
+    /// Called when the undo shortcut is pressed, to undo with an [`undo::UndoStack`].
+    fn on_undo(&mut self) -> Task<Self::Message> {
+        Task::none()
+    }
+
+    /// Called when the redo shortcut is pressed, to redo with an [`undo::UndoStack`].
+    fn on_redo(&mut self) -> Task<Self::Message> {
+        Task::none()
+    }
+
     /// Called when a window is resized.
     fn on_window_resize(&mut self, id: window::Id, width: f32, height: f32) {}
 
@@ -803,6 +874,10 @@ impl<App: Application> ApplicationExt for App {
                         header = header.on_minimize(crate::Action::Cosmic(Action::Minimize));
                     }
 
+                    if let Some(menu_bar) = self.menu_bar() {
+                        header = header.start(menu_bar.view(core).map(crate::Action::App));
+                    }
+
                     for element in self.header_start() {
                         header = header.start(element.map(crate::Action::App));
                     }
@@ -871,7 +946,17 @@ impl<App: Application> ApplicationExt for App {
         // Show any current dialog on top and centered over the view content
         // We have to use a popover even without a dialog to keep the tree from changing
         let mut popover = popover(view_column).modal(true);
-        if let Some(dialog) = self
+        if let Some(palette) = command_palette::view(self) {
+            popover = popover.popup(id_container(
+                palette,
+                iced_core::id::Id::new("COSMIC_command_palette"),
+            ));
+        } else if let Some(help) = shortcuts_help::view(self) {
+            popover = popover.popup(id_container(
+                help,
+                iced_core::id::Id::new("COSMIC_shortcuts_help"),
+            ));
+        } else if let Some(dialog) = self
             .dialog()
             .map(|w| Element::from(id_container(w, iced_core::id::Id::new("COSMIC_dialog"))))
         {
@@ -903,3 +988,27 @@ fn preload_fonts() {
         .iter()
         .for_each(move |font| font_system.load_font(Cow::Borrowed(font)));
 }
+
+/// A menu action shared by the tests of the application modules.
+#[cfg(test)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum TestAction {
+    About,
+    Close,
+    New,
+    NewTab,
+    Open,
+    Quit,
+    Recent,
+    Redo,
+    Undo,
+}
+
+#[cfg(test)]
+impl menu::Action for TestAction {
+    type Message = Self;
+
+    fn message(&self) -> Self {
+        *self
+    }
+}
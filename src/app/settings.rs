@@ -41,6 +41,10 @@ pub struct Settings {
     /// Set the default mmap threshold for malloc with mallopt.
     pub(crate) default_mmap_threshold: Option<i32>,
 
+    /// Save the geometry and layout of the main window in the state directory of the
+    /// application, and restore it on the next run.
+    pub(crate) persist_window_layout: bool,
+
     /// Whether the window should be resizable or not.
     /// and the size of the window border which can be dragged for a resize
     pub(crate) resizable: Option<f64>,
@@ -89,6 +93,7 @@ impl Default for Settings {
             default_icon_theme: None,
             default_text_size: 14.0,
             default_mmap_threshold: Some(128 * 1024),
+            persist_window_layout: false,
             resizable: Some(8.0),
             scale_factor: std::env::var("COSMIC_SCALE")
                 .ok()
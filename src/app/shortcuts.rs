@@ -0,0 +1,368 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Register the keyboard shortcuts of an application in one place.
+//!
+//! Each shortcut is a named action with default key bindings, which the user may
+//! override in the `{APP_ID}.Shortcuts` config, with a key per shortcut name and a
+//! list of key bindings such as `["Ctrl + Shift + T"]` as its value. The same bindings
+//! are used to dispatch key presses with [`Shortcuts::matches`] and to label menu items
+//! with [`Shortcuts::key_binds`], so that the two never differ.
+
+use crate::keyboard_nav;
+use crate::widget::menu::KeyBind;
+use crate::widget::menu::key_bind;
+use cosmic_config::{Config, ConfigGet};
+use iced_core::keyboard::key::Physical;
+use iced_core::keyboard::{Key, Modifiers};
+use std::collections::HashMap;
+
+/// Version of the configs in which shortcuts are overridden.
+pub const VERSION: u64 = 1;
+
+/// The config in which the user overrides the shortcuts of an application.
+///
+/// # Errors
+///
+/// Returns an error if the config directory cannot be found or created.
+pub fn config(app_id: &str) -> Result<Config, cosmic_config::Error> {
+    Config::new(&format!("{app_id}.Shortcuts"), VERSION)
+}
+
+/// A named action and the key bindings which trigger it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Shortcut<A> {
+    /// Stable name of the shortcut, which is its key in the config.
+    pub name: String,
+    /// Translated label of the shortcut, which is listed by its name if there is none.
+    pub label: Option<String>,
+    /// Action triggered by the shortcut.
+    pub action: A,
+    /// Key bindings registered by the application.
+    pub defaults: Vec<KeyBind>,
+    /// Key bindings in effect, which are the defaults unless overridden.
+    pub key_binds: Vec<KeyBind>,
+}
+
+impl<A> Shortcut<A> {
+    /// Whether the key bindings are the defaults.
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.key_binds == self.defaults
+    }
+}
+
+/// A key binding of more than one shortcut.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The key binding.
+    pub key_bind: KeyBind,
+    /// Names of the shortcuts bound to it, in the order they were registered.
+    ///
+    /// Only the first of them is triggered.
+    pub names: Vec<String>,
+}
+
+/// The keyboard shortcuts of an application.
+#[derive(Clone, Debug)]
+pub struct Shortcuts<A> {
+    shortcuts: Vec<Shortcut<A>>,
+    key_binds: HashMap<KeyBind, A>,
+}
+
+impl<A> Default for Shortcuts<A> {
+    fn default() -> Self {
+        Self {
+            shortcuts: Vec::new(),
+            key_binds: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Clone> Shortcuts<A> {
+    /// An empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a shortcut with its default key bindings.
+    ///
+    /// Registering a name again replaces the earlier shortcut.
+    #[must_use]
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        action: A,
+        defaults: impl IntoIterator<Item = KeyBind>,
+    ) -> Self {
+        let name = name.into();
+        let defaults: Vec<KeyBind> = defaults.into_iter().map(normalize).collect();
+        let shortcut = Shortcut {
+            name,
+            label: None,
+            action,
+            key_binds: defaults.clone(),
+            defaults,
+        };
+
+        match self.shortcuts.iter_mut().find(|s| s.name == shortcut.name) {
+            Some(existing) => *existing = shortcut,
+            None => self.shortcuts.push(shortcut),
+        }
+        self.update_key_binds();
+        self
+    }
+
+    /// Sets the translated label of a registered shortcut, which the command palette and
+    /// the keyboard shortcuts dialog show unless the shortcut is in a menu.
+    #[must_use]
+    pub fn label(mut self, name: &str, label: impl Into<String>) -> Self {
+        if let Some(shortcut) = self.shortcuts.iter_mut().find(|s| s.name == name) {
+            shortcut.label = Some(label.into());
+        }
+        self
+    }
+
+    /// Loads the key bindings overridden by the user in a config, such as the one of
+    /// [`config`].
+    ///
+    /// Returns the errors of overrides which could not be read, which are ignored.
+    pub fn load(&mut self, config: &Config) -> Vec<cosmic_config::Error> {
+        let mut errors = Vec::new();
+        for shortcut in &mut self.shortcuts {
+            match config.get::<Vec<KeyBind>>(&shortcut.name) {
+                Ok(key_binds) => {
+                    shortcut.key_binds = key_binds.into_iter().map(normalize).collect();
+                }
+                Err(cosmic_config::Error::GetKey(_, err))
+                    if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(why) if why.is_err() => errors.push(why),
+                Err(_) => (),
+            }
+        }
+        self.update_key_binds();
+        errors
+    }
+
+    /// Overrides the key bindings of a shortcut, returning `false` if no shortcut has the name.
+    pub fn set(&mut self, name: &str, key_binds: impl IntoIterator<Item = KeyBind>) -> bool {
+        let Some(shortcut) = self.shortcuts.iter_mut().find(|s| s.name == name) else {
+            return false;
+        };
+        shortcut.key_binds = key_binds.into_iter().map(normalize).collect();
+        self.update_key_binds();
+        true
+    }
+
+    /// Restores the default key bindings of a shortcut, returning `false` if no shortcut has the name.
+    pub fn reset(&mut self, name: &str) -> bool {
+        let Some(shortcut) = self.shortcuts.iter_mut().find(|s| s.name == name) else {
+            return false;
+        };
+        shortcut.key_binds = shortcut.defaults.clone();
+        self.update_key_binds();
+        true
+    }
+
+    /// The shortcut with the name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Shortcut<A>> {
+        self.shortcuts.iter().find(|s| s.name == name)
+    }
+
+    /// Every shortcut, in the order they were registered.
+    pub fn iter(&self) -> impl Iterator<Item = &Shortcut<A>> {
+        self.shortcuts.iter()
+    }
+
+    /// The key bindings which are bound to more than one shortcut.
+    #[must_use]
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = Vec::new();
+        for shortcut in &self.shortcuts {
+            for key_bind in &shortcut.key_binds {
+                match conflicts.iter_mut().find(|c| c.key_bind == *key_bind) {
+                    Some(conflict) => {
+                        if !conflict.names.contains(&shortcut.name) {
+                            conflict.names.push(shortcut.name.clone());
+                        }
+                    }
+                    None => conflicts.push(Conflict {
+                        key_bind: key_bind.clone(),
+                        names: vec![shortcut.name.clone()],
+                    }),
+                }
+            }
+        }
+        conflicts.retain(|c| c.names.len() > 1);
+        conflicts
+    }
+
+    /// The action of the first shortcut which matches a key press.
+    ///
+    /// Keys are resolved as by the keyboard navigation, with [`KeyBind::matches`] falling
+    /// back to the physical key on non-Latin keyboard layouts, and symbols typed with
+    /// Shift, such as `?`, matching bindings without it, such as `Ctrl + ?`.
+    #[must_use]
+    pub fn matches(
+        &self,
+        modifiers: Modifiers,
+        key: &Key,
+        modified_key: &Key,
+        physical_key: Option<&Physical>,
+    ) -> Option<&A> {
+        key_bind::find(
+            self.shortcuts.iter().flat_map(|shortcut| {
+                shortcut
+                    .key_binds
+                    .iter()
+                    .map(move |key_bind| (key_bind, &shortcut.action))
+            }),
+            |_| Modifiers::empty(),
+            modifiers,
+            key,
+            modified_key,
+            physical_key,
+        )
+    }
+
+    /// The action of each key binding, for labelling menu items with [`menu::items`].
+    ///
+    /// [`menu::items`]: crate::widget::menu::items
+    #[must_use]
+    pub fn key_binds(&self) -> &HashMap<KeyBind, A> {
+        &self.key_binds
+    }
+
+    fn update_key_binds(&mut self) {
+        self.key_binds.clear();
+        for shortcut in &self.shortcuts {
+            for key_bind in &shortcut.key_binds {
+                // The first shortcut of a conflict is the one which is triggered.
+                self.key_binds
+                    .entry(key_bind.clone())
+                    .or_insert_with(|| shortcut.action.clone());
+            }
+        }
+    }
+}
+
+/// The shortcuts of the keyboard navigation built into COSMIC applications, which the
+/// user may override in the `com.system76.CosmicTk.Shortcuts` config.
+#[must_use]
+pub fn keyboard_nav() -> Shortcuts<keyboard_nav::Action> {
+    keyboard_nav::Action::ALL
+        .into_iter()
+        .fold(Shortcuts::new(), |shortcuts, action| {
+            shortcuts.register(action.name(), action, action.default_key_binds())
+        })
+}
+
+/// Sorts the modifiers of a key binding and lowercases its character, so that equal
+/// key bindings compare equal.
+fn normalize(mut key_bind: KeyBind) -> KeyBind {
+    key_bind.modifiers.sort();
+    key_bind.modifiers.dedup();
+    if let Key::Character(c) = &key_bind.key {
+        key_bind.key = Key::Character(c.to_lowercase().into());
+    }
+    key_bind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TestAction as Action;
+    use iced_core::keyboard::key::Named;
+
+    fn shortcuts() -> Shortcuts<Action> {
+        Shortcuts::new()
+            .register("close", Action::Close, ["Ctrl + W".parse().unwrap()])
+            .register("quit", Action::Quit, ["Ctrl + Q".parse().unwrap()])
+            .register(
+                "new-tab",
+                Action::NewTab,
+                [
+                    "Ctrl + T".parse().unwrap(),
+                    "Shift + Ctrl + N".parse().unwrap(),
+                ],
+            )
+    }
+
+    #[test]
+    fn test_matches() {
+        let shortcuts = shortcuts().register("about", Action::About, ["Ctrl + ?".parse().unwrap()]);
+        let w = Key::Character("w".into());
+        let n = Key::Character("N".into());
+        let cyrillic = Key::Character("й".into());
+        assert_eq!(
+            shortcuts.matches(Modifiers::CTRL, &w, &w, None),
+            Some(&Action::Close)
+        );
+        assert_eq!(
+            shortcuts.matches(Modifiers::CTRL | Modifiers::SHIFT, &n, &n, None),
+            Some(&Action::NewTab)
+        );
+        // Cyrillic layouts fall back to the physical key.
+        assert_eq!(
+            shortcuts.matches(
+                Modifiers::CTRL,
+                &cyrillic,
+                &cyrillic,
+                Some(&Physical::Code(iced_core::keyboard::key::Code::KeyQ))
+            ),
+            Some(&Action::Quit)
+        );
+        // Symbols typed with Shift match bindings without it.
+        assert_eq!(
+            shortcuts.matches(
+                Modifiers::CTRL | Modifiers::SHIFT,
+                &Key::Character("/".into()),
+                &Key::Character("?".into()),
+                None
+            ),
+            Some(&Action::About)
+        );
+        assert_eq!(shortcuts.matches(Modifiers::empty(), &w, &w, None), None);
+    }
+
+    #[test]
+    fn test_set_and_conflicts() {
+        let mut shortcuts = shortcuts();
+        let w = Key::Character("w".into());
+        assert!(shortcuts.conflicts().is_empty());
+        assert_eq!(shortcuts.key_binds().len(), 4);
+
+        assert!(shortcuts.set("quit", ["Ctrl + w".parse().unwrap()]));
+        assert!(!shortcuts.get("quit").unwrap().is_default());
+        let conflicts = shortcuts.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].names, ["close", "quit"]);
+        // The first registered shortcut wins, for both dispatch and menu labels.
+        assert_eq!(
+            shortcuts.matches(Modifiers::CTRL, &w, &w, None),
+            Some(&Action::Close)
+        );
+        assert_eq!(
+            shortcuts.key_binds().get(&"Ctrl + W".parse().unwrap()),
+            Some(&Action::Close)
+        );
+        assert!(!shortcuts.key_binds().values().any(|a| *a == Action::Quit));
+
+        assert!(shortcuts.reset("quit"));
+        assert!(shortcuts.conflicts().is_empty());
+        assert!(!shortcuts.set("missing", []));
+    }
+
+    #[test]
+    fn test_keyboard_nav() {
+        let shortcuts = keyboard_nav();
+        let tab = Key::Named(Named::Tab);
+        assert!(shortcuts.conflicts().is_empty());
+        assert_eq!(
+            shortcuts.matches(Modifiers::SHIFT, &tab, &tab, None),
+            Some(&keyboard_nav::Action::FocusPrevious)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! A dialog listing the keyboard shortcuts of an application.
+//!
+//! The dialog is toggled with the `keyboard-shortcuts` shortcut of [`keyboard_nav`],
+//! which is Ctrl+? by default. It lists the key bindings of [`Application::menu_bar`]
+//! and of the menus and shortcuts given to [`Commands::menu`] and [`Commands::shortcuts`]
+//! by [`Application::commands`], grouped by the root of their menu, followed by the
+//! keyboard navigation of libcosmic. Commands added one by one are not listed. The key
+//! bindings are read when the dialog is shown, so that it reflects shortcuts rebound by
+//! the user.
+//!
+//! Menu items are listed by their labels, and shortcuts outside of the menus by their
+//! [`Shortcut::label`], so that the dialog is translated along with the application.
+//!
+//! [`Commands::menu`]: super::Commands::menu
+//! [`Commands::shortcuts`]: super::Commands::shortcuts
+//! [`Shortcut::label`]: super::shortcuts::Shortcut::label
+
+use super::command_palette::{Commands, commands, fuzzy_score};
+use super::{Application, Task};
+use crate::widget::menu::KeyBind;
+use crate::widget::{self, settings};
+use crate::{Element, fl, keyboard_nav, theme};
+use iced::{Event, Length, Subscription, event, keyboard};
+use iced_core::keyboard::Key;
+use iced_core::keyboard::key::Named;
+use iced_futures::event::listen_raw;
+use std::sync::LazyLock;
+
+/// Id of the search input of the dialog.
+static INPUT_ID: LazyLock<widget::Id> =
+    LazyLock::new(|| widget::Id::new("COSMIC_shortcuts_help_search"));
+
+/// A message of the keyboard shortcuts dialog.
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// Closes the dialog.
+    Close,
+    /// Updates the search query.
+    Query(String),
+    /// Opens or closes the dialog.
+    Toggle,
+}
+
+/// The search state of an open dialog.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    query: String,
+}
+
+pub(crate) fn update<App: Application>(app: &mut App, message: Message) -> Task<App::Message> {
+    let core = app.core_mut();
+    match message {
+        Message::Toggle => {
+            if core.shortcuts_help.take().is_none() {
+                core.shortcuts_help = Some(State::default());
+                core.command_palette = None;
+                return widget::text_input::focus(INPUT_ID.clone());
+            }
+        }
+
+        Message::Close => core.shortcuts_help = None,
+
+        Message::Query(query) => {
+            if let Some(state) = core.shortcuts_help.as_mut() {
+                state.query = query;
+            }
+        }
+    }
+
+    Task::none()
+}
+
+/// Closes the dialog with the escape key, which the search input captures.
+pub(crate) fn subscription() -> Subscription<Message> {
+    listen_raw(|event, status, _| match event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Named(Named::Escape),
+            ..
+        }) if status == event::Status::Captured => Some(Message::Close),
+        _ => None,
+    })
+}
+
+pub(crate) fn view<App: Application>(
+    app: &App,
+) -> Option<Element<'_, crate::Action<App::Message>>> {
+    let state = app.core().shortcuts_help.as_ref()?;
+    let spacing = theme::spacing();
+
+    let sections: Vec<Element<'_, _>> =
+        groups(&commands(app), keyboard_nav::key_binds(), &state.query)
+            .into_iter()
+            .map(|(title, shortcuts)| {
+                settings::section()
+                    .title(title)
+                    .extend(
+                        shortcuts
+                            .into_iter()
+                            .map(|(label, key_bind)| settings::item(label, keycaps(&key_bind))),
+                    )
+                    .into()
+            })
+            .collect();
+
+    let input = widget::search_input(fl!("shortcuts-search"), state.query.as_str())
+        .id(INPUT_ID.clone())
+        .on_input(|query| {
+            crate::Action::Cosmic(super::Action::ShortcutsHelp(Message::Query(query)))
+        });
+
+    let dialog = widget::dialog()
+        .title(fl!("keyboard-shortcuts"))
+        .control(input)
+        .control(
+            widget::scrollable(widget::column::with_children(sections).spacing(spacing.space_m))
+                .height(Length::Fixed(400.0)),
+        )
+        .primary_action(
+            widget::button::standard(fl!("close")).on_press(crate::Action::Cosmic(
+                super::Action::ShortcutsHelp(Message::Close),
+            )),
+        );
+
+    Some(dialog.into())
+}
+
+/// The shortcuts matching `query`, as labels and key bindings grouped by title.
+fn groups<Message>(
+    commands: &Commands<Message>,
+    navigation: Vec<(KeyBind, keyboard_nav::Action)>,
+    query: &str,
+) -> Vec<(String, Vec<(String, KeyBind)>)> {
+    let mut groups: Vec<(String, Vec<(String, KeyBind)>)> = Vec::new();
+    let mut push = |title: String, label: String, key_bind: KeyBind| {
+        if fuzzy_score(query, &label).is_none()
+            && fuzzy_score(query, &key_bind.to_string()).is_none()
+        {
+            return;
+        }
+
+        match groups.iter_mut().find(|(t, _)| *t == title) {
+            Some((_, shortcuts)) => shortcuts.push((label, key_bind)),
+            None => groups.push((title, vec![(label, key_bind)])),
+        }
+    };
+
+    for (menu, label, key_bind) in commands.key_binds() {
+        let title = menu.clone().unwrap_or_else(|| fl!("shortcuts-general"));
+        push(title, label.clone(), key_bind.clone());
+    }
+
+    for (key_bind, action) in navigation {
+        push(fl!("shortcuts-navigation"), label(action), key_bind);
+    }
+
+    groups
+}
+
+/// The label of a keyboard navigation action.
+fn label(action: keyboard_nav::Action) -> String {
+    match action {
+        keyboard_nav::Action::CommandPalette => fl!("shortcut-command-palette"),
+        keyboard_nav::Action::Escape => fl!("shortcut-escape"),
+        keyboard_nav::Action::FocusNext => fl!("shortcut-focus-next"),
+        keyboard_nav::Action::FocusPrevious => fl!("shortcut-focus-previous"),
+        keyboard_nav::Action::Fullscreen => fl!("shortcut-fullscreen"),
+        keyboard_nav::Action::KeyboardShortcuts => fl!("keyboard-shortcuts"),
+        keyboard_nav::Action::Redo => fl!("redo"),
+        keyboard_nav::Action::Search => fl!("shortcut-search"),
+        keyboard_nav::Action::Undo => fl!("undo"),
+    }
+}
+
+/// The keys of a key binding as a row of keycaps.
+fn keycaps<'a, Message: 'static>(key_bind: &KeyBind) -> Element<'a, Message> {
+    let spacing = theme::spacing();
+
+    widget::row::with_children(
+        key_bind
+            .symbols()
+            .into_iter()
+            .map(|symbol| {
+                widget::container(widget::text::caption(symbol))
+                    .padding([spacing.space_none, spacing.space_xxs])
+                    .class(theme::Container::custom(keycap_style))
+                    .into()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .spacing(spacing.space_xxxs)
+    .into()
+}
+
+fn keycap_style(theme: &crate::Theme) -> widget::container::Style {
+    let cosmic = theme.cosmic();
+    widget::container::Style {
+        background: Some(iced::Background::Color(
+            cosmic.background.component.base.into(),
+        )),
+        border: iced::Border {
+            color: cosmic.background.component.divider.into(),
+            width: 1.0,
+            radius: cosmic.radius_xs().into(),
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TestAction as Action;
+    use crate::app::shortcuts::Shortcuts;
+    use crate::widget::menu;
+    use std::collections::HashMap;
+
+    fn commands() -> Commands<Action> {
+        let shortcuts = Shortcuts::new()
+            .register(
+                "open",
+                Action::Open,
+                ["Ctrl + O".parse().unwrap(), "F3".parse().unwrap()],
+            )
+            .register("close", Action::Close, ["Ctrl + W".parse().unwrap()])
+            .register("quit", Action::Quit, ["Ctrl + Q".parse().unwrap()])
+            .label("quit", "Quit");
+        let key_binds: HashMap<KeyBind, Action> = shortcuts
+            .key_binds()
+            .iter()
+            .filter(|(key_bind, _)| key_bind.to_string() != "F3")
+            .map(|(key_bind, action)| (key_bind.clone(), *action))
+            .collect();
+
+        Commands::new()
+            .menu(
+                "File",
+                &key_binds,
+                vec![
+                    menu::Item::Button("Open", None, Action::Open),
+                    menu::Item::ButtonDisabled("Close", None, Action::Close),
+                ],
+            )
+            .command("About", Action::About)
+            .shortcuts(&shortcuts)
+    }
+
+    fn titles(groups: &[(String, Vec<(String, KeyBind)>)]) -> Vec<&str> {
+        groups.iter().map(|(title, _)| title.as_str()).collect()
+    }
+
+    fn shortcuts(group: &(String, Vec<(String, KeyBind)>)) -> Vec<(&str, String)> {
+        group
+            .1
+            .iter()
+            .map(|(label, key_bind)| (label.as_str(), key_bind.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_groups() {
+        let navigation = vec![("Escape".parse().unwrap(), keyboard_nav::Action::Escape)];
+        let found = groups(&commands(), navigation, "");
+
+        assert_eq!(
+            titles(&found),
+            [
+                "File",
+                fl!("shortcuts-general").as_str(),
+                fl!("shortcuts-navigation").as_str()
+            ]
+        );
+        // Menu items are listed with each of their key bindings, even when disabled.
+        assert_eq!(
+            shortcuts(&found[0]),
+            [
+                ("Open", "Ctrl + O".to_owned()),
+                ("Close", "Ctrl + W".to_owned()),
+                ("Open", "F3".to_owned()),
+            ]
+        );
+        // Shortcuts outside of the menus are listed by their labels, and commands
+        // without key bindings are not listed.
+        assert_eq!(shortcuts(&found[1]), [("Quit", "Ctrl + Q".to_owned())]);
+        assert_eq!(
+            shortcuts(&found[2]),
+            [(fl!("shortcut-escape").as_str(), "Escape".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let found = groups(&commands(), keyboard_nav::key_binds(), "quit");
+        assert_eq!(titles(&found), [fl!("shortcuts-general")]);
+        assert_eq!(shortcuts(&found[0]), [("Quit", "Ctrl + Q".to_owned())]);
+
+        // Key bindings are searched too.
+        let found = groups(&commands(), Vec::new(), "ctrl w");
+        assert_eq!(shortcuts(&found[0]), [("Close", "Ctrl + W".to_owned())]);
+
+        assert!(groups(&commands(), Vec::new(), "xyz").is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Record reversible commands to undo and redo them.
+//!
+//! An application applies each change to its state through an [`UndoStack`], which is
+//! undone and redone from [`Application::on_undo`] and [`Application::on_redo`]. These
+//! are called with the `undo` and `redo` shortcuts of [`keyboard_nav`], which are
+//! Ctrl+Z and Ctrl+Shift+Z by default. The items of [`UndoStack::menu_items`] are
+//! disabled when there is nothing to undo or redo, and are labelled with the
+//! description of the command, as in `Undo Rename`. Their shortcuts are shown once
+//! the key binds of the menu are extended with [`menu_key_binds`].
+//!
+//! The stack holds no window state, so that it may be tested on its own.
+//!
+//! [`Application::on_undo`]: super::Application::on_undo
+//! [`Application::on_redo`]: super::Application::on_redo
+//! [`keyboard_nav`]: crate::keyboard_nav
+
+use crate::fl;
+use crate::keyboard_nav;
+use crate::widget::menu;
+use crate::widget::menu::key_bind::KeyBind;
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+/// A reversible change to the state of an application.
+pub trait Command: Sized {
+    /// The state which the command changes.
+    type State;
+
+    /// Short description of the change, such as `Rename`.
+    fn description(&self) -> Cow<'static, str>;
+
+    /// Applies the change to the state.
+    fn apply(&mut self, state: &mut Self::State);
+
+    /// Reverts the change from the state.
+    fn revert(&mut self, state: &mut Self::State);
+
+    /// Merges a change which was applied right after this one, such as the next
+    /// character of a typing burst, so that both are undone at once.
+    ///
+    /// Returns the change back if it cannot be merged, which is the default.
+    fn merge(&mut self, next: Self) -> Result<(), Self> {
+        Err(next)
+    }
+}
+
+/// The history of the commands applied to the state of an application.
+#[derive(Debug)]
+pub struct UndoStack<C> {
+    undo: Vec<(C, Instant)>,
+    redo: Vec<C>,
+    merge_window: Duration,
+    limit: Option<usize>,
+    merge_broken: bool,
+}
+
+impl<C> Default for UndoStack<C> {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            merge_window: Duration::from_secs(1),
+            limit: None,
+            merge_broken: false,
+        }
+    }
+}
+
+impl<C: Command> UndoStack<C> {
+    /// An empty history, which merges commands applied within a second of each other.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how soon after the last command a command must be applied to be merged
+    /// into it. A zero duration disables merging.
+    #[must_use]
+    pub fn merge_window(mut self, merge_window: Duration) -> Self {
+        self.merge_window = merge_window;
+        self
+    }
+
+    /// Sets the number of commands which may be undone, forgetting the oldest beyond it.
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Applies a command to the state and records it, discarding the commands which
+    /// were undone.
+    pub fn push(&mut self, state: &mut C::State, command: C) {
+        self.push_at(state, command, Instant::now());
+    }
+
+    /// Applies a command to the state and records it as applied at `now`, which
+    /// decides whether it is merged into the last command.
+    pub fn push_at(&mut self, state: &mut C::State, mut command: C, now: Instant) {
+        command.apply(state);
+        self.redo.clear();
+
+        if !self.merge_broken
+            && let Some((last, at)) = self.undo.last_mut()
+            && now.saturating_duration_since(*at) < self.merge_window
+        {
+            match last.merge(command) {
+                Ok(()) => {
+                    *at = now;
+                    return;
+                }
+                Err(next) => command = next,
+            }
+        }
+
+        self.merge_broken = false;
+        self.undo.push((command, now));
+        if let Some(limit) = self.limit
+            && self.undo.len() > limit
+        {
+            self.undo.drain(..self.undo.len() - limit);
+        }
+    }
+
+    /// Ends the current burst of commands, so that the next command is not merged into
+    /// the last, such as when the cursor of a text editor is moved.
+    pub fn break_merge(&mut self) {
+        self.merge_broken = true;
+    }
+
+    /// Reverts the last command, returning `false` if there is nothing to undo.
+    pub fn undo(&mut self, state: &mut C::State) -> bool {
+        let Some((mut command, _)) = self.undo.pop() else {
+            return false;
+        };
+
+        command.revert(state);
+        self.redo.push(command);
+        self.merge_broken = true;
+        true
+    }
+
+    /// Applies the last command which was undone, returning `false` if there is nothing
+    /// to redo.
+    pub fn redo(&mut self, state: &mut C::State) -> bool {
+        let Some(mut command) = self.redo.pop() else {
+            return false;
+        };
+
+        command.apply(state);
+        self.undo.push((command, Instant::now()));
+        self.merge_broken = true;
+        true
+    }
+
+    /// Whether there is a command to undo.
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there is a command to redo.
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Description of the command which would be undone.
+    #[must_use]
+    pub fn undo_description(&self) -> Option<Cow<'static, str>> {
+        self.undo.last().map(|(command, _)| command.description())
+    }
+
+    /// Description of the command which would be redone.
+    #[must_use]
+    pub fn redo_description(&self) -> Option<Cow<'static, str>> {
+        self.redo.last().map(Command::description)
+    }
+
+    /// Label of the undo menu item, such as `Undo Rename`.
+    #[must_use]
+    pub fn undo_label(&self) -> String {
+        match self.undo_description() {
+            Some(command) => fl!("undo-command", command = command.as_ref()),
+            None => fl!("undo"),
+        }
+    }
+
+    /// Label of the redo menu item, such as `Redo Rename`.
+    #[must_use]
+    pub fn redo_label(&self) -> String {
+        match self.redo_description() {
+            Some(command) => fl!("redo-command", command = command.as_ref()),
+            None => fl!("redo"),
+        }
+    }
+
+    /// The undo and redo items of an edit menu, which are disabled when there is
+    /// nothing to undo or redo.
+    ///
+    /// Extend the key binds given to [`menu::items`] with [`menu_key_binds`] of the
+    /// same actions to show their shortcuts.
+    #[must_use]
+    pub fn menu_items<A: menu::Action>(&self, undo: A, redo: A) -> [menu::Item<A, String>; 2] {
+        let item = |enabled: bool, label: String, action: A| {
+            if enabled {
+                menu::Item::Button(label, None, action)
+            } else {
+                menu::Item::ButtonDisabled(label, None, action)
+            }
+        };
+
+        [
+            item(self.can_undo(), self.undo_label(), undo),
+            item(self.can_redo(), self.redo_label(), redo),
+        ]
+    }
+
+    /// Forgets every command.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merge_broken = false;
+    }
+}
+
+/// The undo and redo shortcuts of [`keyboard_nav`], bound to the actions of the
+/// items of [`UndoStack::menu_items`].
+///
+/// These are for showing the shortcuts in a menu. The shortcuts already call
+/// [`Application::on_undo`] and [`Application::on_redo`], so an application should
+/// not handle them again.
+///
+/// [`Application::on_undo`]: super::Application::on_undo
+/// [`Application::on_redo`]: super::Application::on_redo
+#[must_use]
+pub fn menu_key_binds<A: menu::Action>(undo: A, redo: A) -> Vec<(KeyBind, A)> {
+    keyboard_nav::key_binds()
+        .into_iter()
+        .filter_map(|(key_bind, action)| match action {
+            keyboard_nav::Action::Undo => Some((key_bind, undo)),
+            keyboard_nav::Action::Redo => Some((key_bind, redo)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TestAction as Action;
+
+    /// Inserts text at the end of a string.
+    #[derive(Debug)]
+    struct Insert(String);
+
+    impl Command for Insert {
+        type State = String;
+
+        fn description(&self) -> Cow<'static, str> {
+            Cow::Borrowed("Typing")
+        }
+
+        fn apply(&mut self, state: &mut String) {
+            state.push_str(&self.0);
+        }
+
+        fn revert(&mut self, state: &mut String) {
+            state.truncate(state.len() - self.0.len());
+        }
+
+        fn merge(&mut self, next: Self) -> Result<(), Self> {
+            self.0.push_str(&next.0);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut text = String::new();
+        let mut stack = UndoStack::new().merge_window(Duration::ZERO);
+        assert!(!stack.can_undo() && !stack.can_redo());
+
+        stack.push(&mut text, Insert("a".into()));
+        stack.push(&mut text, Insert("b".into()));
+        assert_eq!(text, "ab");
+
+        assert!(stack.undo(&mut text));
+        assert_eq!(text, "a");
+        assert!(stack.can_redo());
+        assert!(stack.redo(&mut text));
+        assert_eq!(text, "ab");
+        assert!(!stack.redo(&mut text));
+
+        // A new command discards the commands which were undone.
+        stack.undo(&mut text);
+        stack.push(&mut text, Insert("c".into()));
+        assert_eq!(text, "ac");
+        assert!(!stack.can_redo());
+
+        assert!(stack.undo(&mut text));
+        assert!(stack.undo(&mut text));
+        assert!(!stack.undo(&mut text));
+        assert_eq!(text, "");
+    }
+
+    #[test]
+    fn test_merge_window() {
+        let mut text = String::new();
+        let mut stack = UndoStack::new().merge_window(Duration::from_millis(500));
+        let start = Instant::now();
+
+        // A burst extends the window with each command.
+        stack.push_at(&mut text, Insert("a".into()), start);
+        stack.push_at(
+            &mut text,
+            Insert("b".into()),
+            start + Duration::from_millis(400),
+        );
+        stack.push_at(
+            &mut text,
+            Insert("c".into()),
+            start + Duration::from_millis(800),
+        );
+        // A pause begins a new command.
+        stack.push_at(
+            &mut text,
+            Insert("d".into()),
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(text, "abcd");
+
+        stack.undo(&mut text);
+        assert_eq!(text, "abc");
+        stack.undo(&mut text);
+        assert_eq!(text, "");
+
+        // Breaking the burst prevents a merge.
+        stack.push_at(&mut text, Insert("a".into()), start);
+        stack.break_merge();
+        stack.push_at(&mut text, Insert("b".into()), start);
+        stack.undo(&mut text);
+        assert_eq!(text, "a");
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut text = String::new();
+        let mut stack = UndoStack::new().merge_window(Duration::ZERO).limit(2);
+        for c in ["a", "b", "c"] {
+            stack.push(&mut text, Insert(c.into()));
+        }
+
+        assert!(stack.undo(&mut text) && stack.undo(&mut text));
+        assert!(!stack.undo(&mut text));
+        assert_eq!(text, "a");
+    }
+
+    #[test]
+    fn test_menu_items() {
+        let mut text = String::new();
+        let mut stack = UndoStack::new();
+        assert!(matches!(
+            stack.menu_items(Action::Undo, Action::Redo),
+            [
+                menu::Item::ButtonDisabled(_, _, Action::Undo),
+                menu::Item::ButtonDisabled(_, _, Action::Redo)
+            ]
+        ));
+
+        stack.push(&mut text, Insert("a".into()));
+        assert_eq!(stack.undo_description().as_deref(), Some("Typing"));
+        assert!(stack.undo_label().contains("Typing"));
+        assert!(matches!(
+            stack.menu_items(Action::Undo, Action::Redo),
+            [
+                menu::Item::Button(_, _, Action::Undo),
+                menu::Item::ButtonDisabled(_, _, Action::Redo)
+            ]
+        ));
+
+        let key_binds = menu_key_binds(Action::Undo, Action::Redo);
+        let shortcut = |action| {
+            key_binds
+                .iter()
+                .find(|(_, a)| *a == action)
+                .map(|(key_bind, _)| key_bind.to_string())
+        };
+        assert_eq!(shortcut(Action::Undo).as_deref(), Some("Ctrl + Z"));
+        assert_eq!(shortcut(Action::Redo).as_deref(), Some("Ctrl + Shift + Z"));
+    }
+}
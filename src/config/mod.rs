@@ -3,12 +3,15 @@
 
 //! Configurations available to libcosmic applications.
 
+pub mod window_layout;
+
 use crate::cosmic_theme::Density;
 use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
 use cosmic_config::{Config, CosmicConfigEntry};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
 
 /// ID for the `CosmicTk` config.
 pub const ID: &str = "com.system76.CosmicTk";
@@ -83,6 +86,18 @@ pub fn monospace_font() -> FontConfig {
     COSMIC_TK.read().unwrap().monospace_font.clone()
 }
 
+/// Avoid animations which are not essential.
+#[allow(clippy::missing_panics_doc)]
+pub fn reduce_motion() -> bool {
+    COSMIC_TK.read().unwrap().reduce_motion
+}
+
+/// Duration of the transition between system themes.
+#[allow(clippy::missing_panics_doc)]
+pub fn theme_transition_duration() -> Duration {
+    Duration::from_millis(u64::from(COSMIC_TK.read().unwrap().theme_transition_ms))
+}
+
 #[derive(Clone, CosmicConfigEntry, Debug, Eq, PartialEq)]
 #[version = 1]
 pub struct CosmicTk {
@@ -109,6 +124,12 @@ pub struct CosmicTk {
 
     /// Mono font family
     pub monospace_font: FontConfig,
+
+    /// Avoid animations which are not essential.
+    pub reduce_motion: bool,
+
+    /// Duration of the transition between system themes, in milliseconds.
+    pub theme_transition_ms: u32,
 }
 
 impl Default for CosmicTk {
@@ -132,6 +153,8 @@ impl Default for CosmicTk {
                 stretch: iced::font::Stretch::Normal,
                 style: iced::font::Style::Normal,
             },
+            reduce_motion: false,
+            theme_transition_ms: 300,
         }
     }
 }
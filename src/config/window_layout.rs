@@ -0,0 +1,201 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Persist the geometry and layout of windows between runs of an application.
+//!
+//! When enabled with `Settings::persist_window_layout` of the application, the layout of
+//! the main window is restored before its first frame, and saved in the state directory
+//! of the application when it is maximized or restored, when it stops being resized,
+//! when the nav bar is toggled, and when it or the application is closed. Other windows are persisted by a stable key with
+//! [`Core::persist_window`](crate::Core::persist_window).
+
+use iced::Size;
+use serde::{Deserialize, Serialize};
+
+/// The geometry and layout of a window, as saved in the state directory of the application.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    /// Width of the window when it is not maximized, or `0.0` if unknown.
+    pub width: f32,
+    /// Height of the window when it is not maximized, or `0.0` if unknown.
+    pub height: f32,
+    /// Whether the window is maximized.
+    pub maximized: bool,
+    /// Whether the nav bar is shown, when the window is wide enough for it and the content.
+    pub nav_bar_toggled: bool,
+    /// Whether the nav bar is shown, when the window is condensed.
+    pub nav_bar_toggled_condensed: bool,
+    /// Preferred width of the context drawer, if one was set.
+    pub context_drawer_width: Option<f32>,
+}
+
+impl Default for WindowLayout {
+    fn default() -> Self {
+        Self {
+            width: 0.0,
+            height: 0.0,
+            maximized: false,
+            nav_bar_toggled: true,
+            nav_bar_toggled_condensed: false,
+            context_drawer_width: None,
+        }
+    }
+}
+
+impl WindowLayout {
+    /// Version of the state config in which layouts are saved.
+    pub const VERSION: u64 = 1;
+
+    /// Key of the main window.
+    pub const MAIN_KEY: &'static str = "main";
+
+    /// The size of the window when it is not maximized, if known.
+    #[must_use]
+    pub fn size(&self) -> Option<Size> {
+        (self.width > 0.0 && self.height > 0.0).then(|| Size::new(self.width, self.height))
+    }
+
+    /// Applies the size and maximized state to the settings of a window before it is opened.
+    pub fn apply(&self, settings: &mut iced::window::Settings) {
+        if let Some(size) = self.size() {
+            settings.size = size;
+        }
+        settings.maximized = self.maximized;
+    }
+
+    /// The name of the key of the state config in which the layout is saved.
+    pub(crate) fn config_key(key: &str) -> String {
+        format!("window_layout_{key}")
+    }
+}
+
+/// The layout of an open window, which is saved only when it settles.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LiveLayout {
+    /// The current layout of the window.
+    pub(crate) layout: WindowLayout,
+    /// The layout as it was last saved.
+    saved: WindowLayout,
+    /// The size of the window before it was last resized.
+    previous_size: Option<Size>,
+}
+
+impl LiveLayout {
+    pub(crate) fn new(saved: WindowLayout) -> Self {
+        Self {
+            layout: saved,
+            saved,
+            previous_size: None,
+        }
+    }
+
+    /// Record the size of the window, unless it is maximized.
+    pub(crate) fn resized(&mut self, size: Size) {
+        if !self.layout.maximized {
+            self.previous_size = self.layout.size();
+            self.layout.width = size.width;
+            self.layout.height = size.height;
+        }
+    }
+
+    /// Record the maximized state of the window, returning whether it changed.
+    pub(crate) fn maximized(&mut self, maximized: bool) -> bool {
+        if maximized == self.layout.maximized {
+            return false;
+        }
+        // The window was resized to its maximized size before it was known to be maximized.
+        if maximized && let Some(size) = self.previous_size.take() {
+            self.layout.width = size.width;
+            self.layout.height = size.height;
+        }
+        self.layout.maximized = maximized;
+        true
+    }
+
+    /// The layout to save, if it changed since it was last saved.
+    pub(crate) fn take_unsaved(&mut self) -> Option<WindowLayout> {
+        (self.layout != self.saved).then(|| {
+            self.saved = self.layout;
+            self.layout
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maximize_keeps_previous_size() {
+        let mut live = LiveLayout::new(WindowLayout::default());
+        live.resized(Size::new(800.0, 600.0));
+        live.resized(Size::new(1920.0, 1080.0));
+        assert!(live.maximized(true));
+        assert_eq!(live.layout.size(), Some(Size::new(800.0, 600.0)));
+
+        // Resizes are ignored while maximized, and the maximized state is only reported once.
+        live.resized(Size::new(1920.0, 1040.0));
+        assert!(!live.maximized(true));
+        assert_eq!(live.layout.size(), Some(Size::new(800.0, 600.0)));
+
+        assert!(live.maximized(false));
+        live.resized(Size::new(1024.0, 768.0));
+        assert_eq!(live.layout.size(), Some(Size::new(1024.0, 768.0)));
+    }
+
+    #[test]
+    fn test_take_unsaved() {
+        let mut live = LiveLayout::new(WindowLayout::default());
+        assert_eq!(live.take_unsaved(), None);
+
+        live.resized(Size::new(800.0, 600.0));
+        live.layout.nav_bar_toggled = false;
+        let layout = live.take_unsaved().unwrap();
+        assert_eq!(layout.size(), Some(Size::new(800.0, 600.0)));
+        assert!(!layout.nav_bar_toggled);
+        assert_eq!(live.take_unsaved(), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use cosmic_config::{ConfigGet, ConfigSet};
+
+        let config = cosmic_config::Config::in_memory("com.system76.WindowLayoutTest", 1).unwrap();
+        let layout = WindowLayout {
+            width: 1024.0,
+            height: 768.0,
+            maximized: true,
+            nav_bar_toggled: false,
+            nav_bar_toggled_condensed: true,
+            context_drawer_width: Some(400.0),
+        };
+        let key = WindowLayout::config_key("preferences");
+        config.set(&key, layout).unwrap();
+        assert_eq!(config.get::<WindowLayout>(&key).unwrap(), layout);
+
+        // Fields missing from older layouts take their defaults.
+        #[derive(Serialize)]
+        struct Older {
+            width: f32,
+            height: f32,
+        }
+        config
+            .set(
+                &key,
+                Older {
+                    width: 640.0,
+                    height: 480.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            config.get::<WindowLayout>(&key).unwrap(),
+            WindowLayout {
+                width: 640.0,
+                height: 480.0,
+                ..WindowLayout::default()
+            }
+        );
+    }
+}
@@ -3,8 +3,9 @@
 
 use std::collections::HashMap;
 
+use crate::config::window_layout::{LiveLayout, WindowLayout};
 use crate::widget::nav_bar;
-use cosmic_config::CosmicConfigEntry;
+use cosmic_config::{ConfigGet, ConfigSet, CosmicConfigEntry};
 use cosmic_theme::ThemeMode;
 use iced::{Limits, Size, window};
 use iced_core::window::Id;
@@ -39,6 +40,7 @@ pub struct Window {
     pub show_maximize: bool,
     pub show_minimize: bool,
     pub is_maximized: bool,
+    context_drawer_width: Option<f32>,
     height: f32,
     width: f32,
 }
@@ -101,6 +103,26 @@ pub struct Core {
 
     #[cfg(all(feature = "wayland", target_os = "linux"))]
     pub(crate) sync_window_border_radii_to_theme: bool,
+
+    /// State config in which window layouts are persisted, if enabled.
+    pub(crate) window_layout_config: Option<cosmic_config::Config>,
+
+    /// Keys of the persisted windows besides the main window.
+    persisted_windows: HashMap<window::Id, String>,
+
+    /// Layouts of the persisted windows by key, as they change between saves.
+    window_layouts: HashMap<String, LiveLayout>,
+
+    /// Number of resizes of persisted windows, to save their layouts once resizing stops.
+    window_layout_resizes: u64,
+
+    /// Search state of the command palette, while it is open.
+    #[cfg(feature = "winit")]
+    pub(crate) command_palette: Option<crate::app::command_palette::State>,
+
+    /// Search state of the keyboard shortcuts dialog, while it is open.
+    #[cfg(feature = "winit")]
+    pub(crate) shortcuts_help: Option<crate::app::shortcuts_help::State>,
 }
 
 impl Default for Core {
@@ -143,6 +165,7 @@ impl Default for Core {
                 show_minimize: true,
                 show_window_menu: false,
                 is_maximized: false,
+                context_drawer_width: None,
                 height: 0.,
                 width: 0.,
             },
@@ -161,6 +184,14 @@ impl Default for Core {
             menu_bars: HashMap::new(),
             #[cfg(all(feature = "wayland", target_os = "linux"))]
             sync_window_border_radii_to_theme: true,
+            window_layout_config: None,
+            persisted_windows: HashMap::new(),
+            window_layouts: HashMap::new(),
+            window_layout_resizes: 0,
+            #[cfg(feature = "winit")]
+            command_palette: None,
+            #[cfg(feature = "winit")]
+            shortcuts_help: None,
         }
     }
 }
@@ -248,9 +279,17 @@ impl Core {
 
         #[allow(clippy::manual_clamp)]
         // This logic is to ensure the context drawer does not take up too much of the content's space
-        // The minimum width is 344px and the maximum with is 480px
+        // The minimum width is 344px and the maximum with is 480px, unless a wider width is preferred
         // We want to keep the content at least 360px until going down to the minimum width
-        (window_width - reserved_width).min(480.0).max(344.0)
+        (window_width - reserved_width)
+            .min(self.window.context_drawer_width.unwrap_or(480.0))
+            .max(344.0)
+    }
+
+    /// Set the preferred width of the context drawer, which is kept within the space
+    /// left by the content. If `None`, the drawer is at most 480px wide.
+    pub fn set_context_drawer_width(&mut self, width: Option<f32>) {
+        self.window.context_drawer_width = width;
     }
 
     #[cold]
@@ -280,11 +319,13 @@ impl Core {
     pub fn nav_bar_toggle(&mut self) {
         self.nav_bar.toggled = !self.nav_bar.toggled;
         self.nav_bar_set_toggled_condensed(self.nav_bar.toggled);
+        self.save_main_window_layout();
     }
 
     #[inline]
     pub fn nav_bar_toggle_condensed(&mut self) {
         self.nav_bar_set_toggled_condensed(!self.nav_bar.toggled_condensed);
+        self.save_main_window_layout();
     }
 
     #[inline]
@@ -301,6 +342,7 @@ impl Core {
     pub fn nav_bar_set_toggled(&mut self, toggled: bool) {
         self.nav_bar.toggled = toggled;
         self.nav_bar_set_toggled_condensed(self.nav_bar.toggled);
+        self.save_main_window_layout();
     }
 
     #[cold]
@@ -502,4 +544,142 @@ impl Core {
     pub fn sync_window_border_radii_to_theme(&self) -> bool {
         self.sync_window_border_radii_to_theme
     }
+
+    /// The saved layout of a window, if window layouts are persisted.
+    #[must_use]
+    pub fn window_layout(&self, key: &str) -> Option<WindowLayout> {
+        if let Some(live) = self.window_layouts.get(key) {
+            return Some(live.layout);
+        }
+        self.window_layout_config
+            .as_ref()?
+            .get(&WindowLayout::config_key(key))
+            .ok()
+    }
+
+    /// Persist the layout of a window besides the main window, by a key which is
+    /// stable between runs, such as `"preferences"`.
+    ///
+    /// Apply the [`Core::window_layout`] of the key to the settings of the window
+    /// before opening it to restore its layout. Has no effect unless window layouts
+    /// are persisted.
+    pub fn persist_window(&mut self, id: window::Id, key: impl Into<String>) {
+        if self.window_layout_config.is_some() {
+            self.persisted_windows.insert(id, key.into());
+        }
+    }
+
+    /// Restore the layout of the main window.
+    pub(crate) fn restore_window_layout(&mut self, layout: &WindowLayout) {
+        if let Some(size) = layout.size() {
+            self.set_window_width(size.width);
+            self.set_window_height(size.height);
+        }
+        self.window.is_maximized = layout.maximized;
+        self.window.context_drawer_width = layout.context_drawer_width;
+        self.nav_bar.toggled = layout.nav_bar_toggled;
+        self.nav_bar.toggled_condensed = layout.nav_bar_toggled_condensed;
+        self.nav_bar_update();
+    }
+
+    /// Record the size of a persisted window, returning the number of this resize, which
+    /// is saved by [`Core::window_layout_settled`] if no other resize follows it.
+    pub(crate) fn window_layout_resized(&mut self, id: window::Id, size: Size) -> Option<u64> {
+        let mut resized = false;
+        self.update_window_layout(id, |live| {
+            live.resized(size);
+            resized = true;
+            false
+        });
+
+        resized.then(|| {
+            self.window_layout_resizes += 1;
+            self.window_layout_resizes
+        })
+    }
+
+    /// Save the layouts of persisted windows, unless a window was resized after `resize`.
+    pub(crate) fn window_layout_settled(&mut self, resize: u64) {
+        if resize == self.window_layout_resizes {
+            self.save_window_layouts();
+        }
+    }
+
+    /// Save the layout of every persisted window which changed since it was last saved.
+    pub(crate) fn save_window_layouts(&mut self) {
+        let Some(config) = self.window_layout_config.as_ref() else {
+            return;
+        };
+
+        for (key, live) in &mut self.window_layouts {
+            if let Some(layout) = live.take_unsaved() {
+                save_window_layout(config, key, layout);
+            }
+        }
+    }
+
+    /// Save the maximized state of a persisted window, if it changed.
+    pub(crate) fn window_layout_maximized(&mut self, id: window::Id, maximized: bool) {
+        self.update_window_layout(id, |live| live.maximized(maximized));
+    }
+
+    /// Save the layout of a persisted window as it is closed.
+    pub(crate) fn window_layout_closed(&mut self, id: window::Id) {
+        self.update_window_layout(id, |_| true);
+        self.persisted_windows.remove(&id);
+    }
+
+    /// Save the layout of the main window, such as after the nav bar is toggled.
+    fn save_main_window_layout(&mut self) {
+        if let Some(id) = self.main_window_id() {
+            self.update_window_layout(id, |_| true);
+        }
+    }
+
+    /// Update the layout of a persisted window, and write it if `update` returns
+    /// `true` and it changed since it was last written.
+    fn update_window_layout(
+        &mut self,
+        id: window::Id,
+        update: impl FnOnce(&mut LiveLayout) -> bool,
+    ) {
+        if self.window_layout_config.is_none() {
+            return;
+        }
+
+        let is_main = self.main_window_is(id);
+        let key = if is_main {
+            WindowLayout::MAIN_KEY.to_owned()
+        } else if let Some(key) = self.persisted_windows.get(&id) {
+            key.clone()
+        } else {
+            return;
+        };
+
+        let mut live = self
+            .window_layouts
+            .get(&key)
+            .copied()
+            .unwrap_or_else(|| LiveLayout::new(self.window_layout(&key).unwrap_or_default()));
+        let save = update(&mut live);
+        if is_main {
+            live.layout.nav_bar_toggled = self.nav_bar.toggled;
+            live.layout.nav_bar_toggled_condensed = self.nav_bar.toggled_condensed;
+            live.layout.context_drawer_width = self.window.context_drawer_width;
+        }
+
+        if save
+            && let Some(layout) = live.take_unsaved()
+            && let Some(config) = self.window_layout_config.as_ref()
+        {
+            save_window_layout(config, &key, layout);
+        }
+        self.window_layouts.insert(key, live);
+    }
+}
+
+fn save_window_layout(config: &cosmic_config::Config, key: &str, layout: WindowLayout) {
+    if let Err(why) = config.set(&WindowLayout::config_key(key), layout) {
+        tracing::error!(?why, %key, "failed to save window layout");
+    }
 }
@@ -3,17 +3,124 @@
 
 //! Subscribe to common application keyboard shortcuts.
 
+use crate::widget::menu::KeyBind;
+use crate::widget::menu::key_bind::{self, Modifier};
 use iced::{Event, Subscription, event, keyboard};
 use iced_core::keyboard::key::Named;
+use iced_core::keyboard::key::Physical;
+use iced_core::keyboard::{Key, Modifiers};
 use iced_futures::event::listen_raw;
+use std::sync::{LazyLock, RwLock};
+
+/// Key bindings of the actions, in the order they are matched.
+static KEY_BINDS: LazyLock<RwLock<Vec<(KeyBind, Action)>>> = LazyLock::new(|| {
+    RwLock::new(
+        Action::ALL
+            .into_iter()
+            .flat_map(|action| {
+                action
+                    .default_key_binds()
+                    .into_iter()
+                    .map(move |key_bind| (key_bind, action))
+            })
+            .collect(),
+    )
+});
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
+    CommandPalette,
     Escape,
     FocusNext,
     FocusPrevious,
     Fullscreen,
+    KeyboardShortcuts,
+    Redo,
     Search,
+    Undo,
+}
+
+impl Action {
+    /// Every action, in the order their key bindings are matched.
+    pub const ALL: [Self; 9] = [
+        Self::FocusNext,
+        Self::FocusPrevious,
+        Self::Escape,
+        Self::Fullscreen,
+        Self::Search,
+        Self::CommandPalette,
+        Self::KeyboardShortcuts,
+        Self::Undo,
+        Self::Redo,
+    ];
+
+    /// Stable name of the shortcut of the action.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::CommandPalette => "command-palette",
+            Self::Escape => "escape",
+            Self::FocusNext => "focus-next",
+            Self::FocusPrevious => "focus-previous",
+            Self::Fullscreen => "fullscreen",
+            Self::KeyboardShortcuts => "keyboard-shortcuts",
+            Self::Redo => "redo",
+            Self::Search => "search",
+            Self::Undo => "undo",
+        }
+    }
+
+    /// Key bindings of the action, unless they are overridden.
+    #[must_use]
+    pub fn default_key_binds(self) -> Vec<KeyBind> {
+        let (modifiers, key) = match self {
+            Self::CommandPalette => (
+                vec![Modifier::Ctrl, Modifier::Shift],
+                Key::Character("p".into()),
+            ),
+            Self::Escape => (vec![], Key::Named(Named::Escape)),
+            Self::FocusNext => (vec![], Key::Named(Named::Tab)),
+            Self::FocusPrevious => (vec![Modifier::Shift], Key::Named(Named::Tab)),
+            Self::Fullscreen => (vec![], Key::Named(Named::F11)),
+            Self::KeyboardShortcuts => (vec![Modifier::Ctrl], Key::Character("?".into())),
+            Self::Redo => (
+                vec![Modifier::Ctrl, Modifier::Shift],
+                Key::Character("z".into()),
+            ),
+            Self::Search => (vec![Modifier::Ctrl], Key::Character("f".into())),
+            Self::Undo => (vec![Modifier::Ctrl], Key::Character("z".into())),
+        };
+        vec![KeyBind { modifiers, key }]
+    }
+
+    /// Modifiers which may be held on top of the key bindings of the action, such as
+    /// Alt+Tab still moving focus, or Escape closing a dialog with Ctrl held.
+    ///
+    /// A key binding which matches the modifiers exactly is preferred, so that Ctrl+Z
+    /// does not shadow Ctrl+Shift+Z.
+    #[must_use]
+    pub fn extra_modifiers(self) -> Modifiers {
+        match self {
+            Self::Escape | Self::Fullscreen => Modifiers::all(),
+            Self::FocusNext | Self::FocusPrevious => Modifiers::ALT | Modifiers::LOGO,
+            Self::Search => Modifiers::SHIFT | Modifiers::ALT | Modifiers::LOGO,
+            Self::CommandPalette | Self::KeyboardShortcuts | Self::Redo | Self::Undo => {
+                Modifiers::empty()
+            }
+        }
+    }
+}
+
+/// The key bindings of the actions, in the order they are matched.
+#[allow(clippy::missing_panics_doc)]
+pub fn key_binds() -> Vec<(KeyBind, Action)> {
+    KEY_BINDS.read().unwrap().clone()
+}
+
+/// Sets the key bindings of the actions, in the order they are matched.
+#[allow(clippy::missing_panics_doc)]
+pub fn set_key_binds(key_binds: impl IntoIterator<Item = (KeyBind, Action)>) {
+    *KEY_BINDS.write().unwrap() = key_binds.into_iter().collect();
 }
 
 #[cold]
@@ -23,41 +130,101 @@ pub fn subscription() -> Subscription<Action> {
             return None;
         }
 
-        match event {
-            Event::Keyboard(keyboard::Event::KeyPressed {
-                key: keyboard::Key::Named(key),
-                modifiers,
-                ..
-            }) => match key {
-                Named::Tab if !modifiers.control() => {
-                    return Some(if modifiers.shift() {
-                        Action::FocusPrevious
-                    } else {
-                        Action::FocusNext
-                    });
-                }
-
-                Named::Escape => {
-                    return Some(Action::Escape);
-                }
-
-                Named::F11 => {
-                    return Some(Action::Fullscreen);
-                }
-
-                _ => (),
-            },
-            Event::Keyboard(keyboard::Event::KeyPressed {
-                key: keyboard::Key::Character(c),
-                modifiers,
-                ..
-            }) if c == "f" && modifiers.control() => {
-                return Some(Action::Search);
-            }
-
-            _ => (),
-        }
+        let Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            modified_key,
+            physical_key,
+            modifiers,
+            ..
+        }) = event
+        else {
+            return None;
+        };
 
-        None
+        find(
+            &KEY_BINDS.read().unwrap(),
+            modifiers,
+            &key,
+            &modified_key,
+            &physical_key,
+        )
     })
 }
+
+/// The action bound to a key press, matching the modifiers exactly if any binding does,
+/// or else ignoring the [`Action::extra_modifiers`] which the binding does not use.
+fn find(
+    key_binds: &[(KeyBind, Action)],
+    modifiers: Modifiers,
+    key: &Key,
+    modified_key: &Key,
+    physical_key: &Physical,
+) -> Option<Action> {
+    key_bind::find(
+        key_binds
+            .iter()
+            .map(|(key_bind, action)| (key_bind, action)),
+        |action| action.extra_modifiers(),
+        modifiers,
+        key,
+        modified_key,
+        Some(physical_key),
+    )
+    .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_core::keyboard::key::Code;
+
+    fn find_default(modifiers: Modifiers, key: Key, code: Code) -> Option<Action> {
+        let key_binds = KEY_BINDS.read().unwrap();
+        find(&key_binds, modifiers, &key, &key, &Physical::Code(code))
+    }
+
+    #[test]
+    fn test_extra_modifiers() {
+        let tab = || Key::Named(Named::Tab);
+        let z = || Key::Character("z".into());
+        let f = || Key::Character("f".into());
+
+        // Tab moves focus unless Ctrl is held.
+        assert_eq!(
+            find_default(Modifiers::ALT, tab(), Code::Tab),
+            Some(Action::FocusNext)
+        );
+        assert_eq!(
+            find_default(Modifiers::SHIFT | Modifiers::LOGO, tab(), Code::Tab),
+            Some(Action::FocusPrevious)
+        );
+        assert_eq!(find_default(Modifiers::CTRL, tab(), Code::Tab), None);
+
+        // Escape ignores modifiers.
+        assert_eq!(
+            find_default(
+                Modifiers::CTRL | Modifiers::SHIFT,
+                Key::Named(Named::Escape),
+                Code::Escape
+            ),
+            Some(Action::Escape)
+        );
+
+        // Search matches with extra modifiers, but still needs Ctrl.
+        assert_eq!(
+            find_default(Modifiers::CTRL | Modifiers::SHIFT, f(), Code::KeyF),
+            Some(Action::Search)
+        );
+        assert_eq!(find_default(Modifiers::SHIFT, f(), Code::KeyF), None);
+
+        // An exact binding wins over an extra modifier, and undo is not lenient.
+        assert_eq!(
+            find_default(Modifiers::CTRL | Modifiers::SHIFT, z(), Code::KeyZ),
+            Some(Action::Redo)
+        );
+        assert_eq!(
+            find_default(Modifiers::CTRL | Modifiers::ALT, z(), Code::KeyZ),
+            None
+        );
+    }
+}
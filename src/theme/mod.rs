@@ -6,6 +6,7 @@
 #[cfg(feature = "xdg-portal")]
 pub mod portal;
 pub mod style;
+pub mod transition;
 
 use cosmic_config::{CosmicConfigEntry, config_subscription};
 use cosmic_theme::{Component, LayeredTheme, Spacing, ThemeMode};
@@ -194,6 +195,18 @@ impl ThemeType {
         }
     }
 
+    /// The colors of the theme.
+    #[inline]
+    pub fn cosmic(&self) -> &cosmic_theme::Theme {
+        match self {
+            Self::Dark => &COSMIC_DARK,
+            Self::Light => &COSMIC_LIGHT,
+            Self::HighContrastDark => &COSMIC_HC_DARK,
+            Self::HighContrastLight => &COSMIC_HC_LIGHT,
+            Self::Custom(t) | Self::System { theme: t, .. } => t.as_ref(),
+        }
+    }
+
     #[inline]
     /// Prefer dark or light theme.
     /// If `None`, the system preference is used.
@@ -214,13 +227,7 @@ pub struct Theme {
 impl Theme {
     #[inline]
     pub fn cosmic(&self) -> &cosmic_theme::Theme {
-        match self.theme_type {
-            ThemeType::Dark => &COSMIC_DARK,
-            ThemeType::Light => &COSMIC_LIGHT,
-            ThemeType::HighContrastDark => &COSMIC_HC_DARK,
-            ThemeType::HighContrastLight => &COSMIC_HC_LIGHT,
-            ThemeType::Custom(ref t) | ThemeType::System { theme: ref t, .. } => t.as_ref(),
-        }
+        self.theme_type.cosmic()
     }
 
     #[inline]
@@ -0,0 +1,71 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Animated transitions between system themes.
+
+use super::{CosmicTheme, Theme, ThemeType};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// An animated transition from the active theme to a new theme.
+///
+/// The colors of each frame are interpolated in OKLab and eased with
+/// [`smootherstep`](crate::anim::smootherstep).
+#[derive(Debug, Clone)]
+pub struct Transition {
+    from: Arc<CosmicTheme>,
+    to: ThemeType,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Transition {
+    /// Begins a transition of `theme` to `to`.
+    ///
+    /// The theme is set at once, and `None` is returned, when reduced motion is
+    /// preferred, the transition duration is zero, or the colors are unchanged.
+    pub fn start(theme: &mut Theme, to: ThemeType) -> Option<Self> {
+        let duration = crate::config::theme_transition_duration();
+        if crate::config::reduce_motion() || duration.is_zero() || theme.cosmic() == to.cosmic() {
+            theme.set_theme(to);
+            return None;
+        }
+
+        let transition = Self {
+            from: Arc::new(theme.cosmic().clone()),
+            to,
+            start: Instant::now(),
+            duration,
+        };
+        transition.frame(theme, transition.start);
+        Some(transition)
+    }
+
+    /// The theme which the transition ends with.
+    #[must_use]
+    pub fn target(&self) -> &ThemeType {
+        &self.to
+    }
+
+    /// Sets `theme` to the frame of the transition at `now`.
+    ///
+    /// Returns `true` once the transition has finished.
+    pub fn frame(&self, theme: &mut Theme, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            theme.set_theme(self.to.clone());
+            return true;
+        }
+
+        let t = crate::anim::smootherstep(elapsed.as_secs_f32() / self.duration.as_secs_f32());
+        let interpolated = Arc::new(self.from.interpolate(self.to.cosmic(), t));
+        theme.set_theme(match self.to {
+            ThemeType::System { prefer_dark, .. } => ThemeType::System {
+                prefer_dark,
+                theme: interpolated,
+            },
+            _ => ThemeType::Custom(interpolated),
+        });
+        false
+    }
+}
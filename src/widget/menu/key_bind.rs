@@ -1,6 +1,9 @@
-use iced_core::keyboard::key::{Code, Physical};
+use iced_core::keyboard::key::{Code, Named, Physical};
 use iced_core::keyboard::{Key, Modifiers};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::fmt;
+use std::str::FromStr;
 
 /// Represents the modifier keys on a keyboard.
 ///
@@ -17,6 +20,17 @@ pub enum Modifier {
     Shift,
 }
 
+impl Modifier {
+    /// Every modifier, in the order in which they are displayed.
+    pub const ALL: [Self; 4] = [Self::Super, Self::Ctrl, Self::Alt, Self::Shift];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|modifier| format!("{modifier:?}").eq_ignore_ascii_case(name))
+    }
+}
+
 /// Represents a combination of a key and modifiers.
 /// It is used to define keyboard shortcuts.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -62,6 +76,46 @@ impl KeyBind {
             && modifiers.shift() == self.modifiers.contains(&Modifier::Shift)
     }
 
+    /// The keys of the binding as labels of keycaps, using symbols for the keys which
+    /// have them, such as `["Ctrl", "⇧", "T"]`.
+    #[must_use]
+    pub fn symbols(&self) -> Vec<Cow<'static, str>> {
+        let mut symbols: Vec<Cow<'static, str>> = self
+            .modifiers
+            .iter()
+            .map(|modifier| {
+                Cow::Borrowed(match modifier {
+                    Modifier::Super => "Super",
+                    Modifier::Ctrl => "Ctrl",
+                    Modifier::Alt => "Alt",
+                    Modifier::Shift => "⇧",
+                })
+            })
+            .collect();
+
+        symbols.push(match &self.key {
+            Key::Character(c) if c.as_str() == " " => Cow::Borrowed("Space"),
+            Key::Character(c) => Cow::Owned(c.to_uppercase()),
+            Key::Named(named) => match named {
+                Named::ArrowDown => Cow::Borrowed("↓"),
+                Named::ArrowLeft => Cow::Borrowed("←"),
+                Named::ArrowRight => Cow::Borrowed("→"),
+                Named::ArrowUp => Cow::Borrowed("↑"),
+                Named::Backspace => Cow::Borrowed("⌫"),
+                Named::Delete => Cow::Borrowed("⌦"),
+                Named::Enter => Cow::Borrowed("↵"),
+                Named::Escape => Cow::Borrowed("Esc"),
+                Named::PageDown => Cow::Borrowed("PgDn"),
+                Named::PageUp => Cow::Borrowed("PgUp"),
+                Named::Tab => Cow::Borrowed("⇥"),
+                named => Cow::Owned(format!("{named:?}")),
+            },
+            other => Cow::Owned(format!("{other:?}")),
+        });
+
+        symbols
+    }
+
     fn key_eq(&self, key: &Key) -> bool {
         match (key, &self.key) {
             // CapsLock and Shift change the case of Key::Character, so we compare these in a case insensitive way
@@ -71,6 +125,55 @@ impl KeyBind {
     }
 }
 
+/// The value of the first key binding which matches a key press, for the shortcuts of
+/// applications and the keyboard navigation alike.
+///
+/// A binding which matches the modifiers exactly is preferred. Otherwise the modifiers
+/// of `extra_modifiers` which the binding does not use are ignored. Symbols typed with
+/// Shift, such as `?`, are bound without it, as in `Ctrl + ?`.
+pub(crate) fn find<'a, A: 'a>(
+    key_binds: impl Iterator<Item = (&'a KeyBind, &'a A)> + Clone,
+    extra_modifiers: impl Fn(&A) -> Modifiers,
+    modifiers: Modifiers,
+    key: &Key,
+    modified_key: &Key,
+    physical_key: Option<&Physical>,
+) -> Option<&'a A> {
+    let shifted_symbol = modifiers.shift()
+        && matches!(modified_key, Key::Character(c) if !c.chars().any(char::is_alphabetic))
+        && modified_key != key;
+
+    let matches = |key_bind: &KeyBind, modifiers: Modifiers| {
+        key_bind.matches(modifiers, key, physical_key)
+            || (shifted_symbol
+                && key_bind.matches(modifiers.difference(Modifiers::SHIFT), modified_key, None))
+    };
+
+    key_binds
+        .clone()
+        .find(|(key_bind, _)| matches(key_bind, modifiers))
+        .or_else(|| {
+            key_binds.clone().find(|(key_bind, value)| {
+                let required =
+                    key_bind
+                        .modifiers
+                        .iter()
+                        .fold(Modifiers::empty(), |required, modifier| {
+                            required
+                                | match modifier {
+                                    Modifier::Super => Modifiers::LOGO,
+                                    Modifier::Ctrl => Modifiers::CTRL,
+                                    Modifier::Alt => Modifiers::ALT,
+                                    Modifier::Shift => Modifiers::SHIFT,
+                                }
+                        });
+                let ignored = extra_modifiers(value).difference(required);
+                matches(key_bind, modifiers.difference(ignored))
+            })
+        })
+        .map(|(_, value)| value)
+}
+
 fn is_latin_shortcut_key(key: &Key) -> bool {
     let Key::Character(s) = key else {
         return false;
@@ -154,6 +257,115 @@ impl fmt::Display for KeyBind {
     }
 }
 
+/// An error from parsing a [`KeyBind`].
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ParseKeyBindError {
+    /// There is no key after the modifiers.
+    #[error("missing key")]
+    MissingKey,
+    /// The key is not a single character or a known key name.
+    #[error("unknown key: {0}")]
+    UnknownKey(String),
+}
+
+/// Parses the format of [`KeyBind`]'s `Display`, such as `Ctrl + Shift + T` or `F11`.
+///
+/// Modifier and key names are case insensitive.
+impl FromStr for KeyBind {
+    type Err = ParseKeyBindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Vec::new();
+        let mut rest = s.trim();
+        while let Some((name, tail)) = rest.split_once('+')
+            && let Some(modifier) = Modifier::from_name(name.trim())
+        {
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+            rest = tail.trim();
+        }
+        modifiers.sort();
+
+        let mut chars = rest.chars();
+        let key = match (chars.next(), chars.next()) {
+            (None, _) => return Err(ParseKeyBindError::MissingKey),
+            (Some(c), None) => Key::Character(c.to_lowercase().to_string().into()),
+            _ if rest.eq_ignore_ascii_case("space") => Key::Character(" ".into()),
+            _ => Key::Named(
+                named_key(rest).ok_or_else(|| ParseKeyBindError::UnknownKey(rest.to_owned()))?,
+            ),
+        };
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl Serialize for KeyBind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The named keys which can be parsed in a [`KeyBind`].
+fn named_key(name: &str) -> Option<Named> {
+    const NAMED: [Named; 41] = [
+        Named::Enter,
+        Named::Tab,
+        Named::Backspace,
+        Named::Delete,
+        Named::Insert,
+        Named::Escape,
+        Named::Home,
+        Named::End,
+        Named::PageUp,
+        Named::PageDown,
+        Named::ArrowUp,
+        Named::ArrowDown,
+        Named::ArrowLeft,
+        Named::ArrowRight,
+        Named::PrintScreen,
+        Named::Pause,
+        Named::ContextMenu,
+        Named::F1,
+        Named::F2,
+        Named::F3,
+        Named::F4,
+        Named::F5,
+        Named::F6,
+        Named::F7,
+        Named::F8,
+        Named::F9,
+        Named::F10,
+        Named::F11,
+        Named::F12,
+        Named::F13,
+        Named::F14,
+        Named::F15,
+        Named::F16,
+        Named::F17,
+        Named::F18,
+        Named::F19,
+        Named::F20,
+        Named::F21,
+        Named::F22,
+        Named::F23,
+        Named::F24,
+    ];
+
+    NAMED
+        .into_iter()
+        .find(|named| format!("{named:?}").eq_ignore_ascii_case(name))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -229,6 +441,38 @@ mod test {
         ));
     }
 
+    #[test]
+    fn parse() {
+        assert_eq!("Ctrl + W".parse(), Ok(bind_ctrl_w()));
+        assert_eq!("ctrl+w".parse(), Ok(bind_ctrl_w()));
+
+        let bind = KeyBind {
+            modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+            key: Key::Character("+".into()),
+        };
+        assert_eq!(bind.to_string().parse(), Ok(bind.clone()));
+        assert_eq!("Shift + Ctrl + +".parse(), Ok(bind));
+
+        let bind = KeyBind {
+            modifiers: vec![],
+            key: Key::Named(Named::F11),
+        };
+        assert_eq!(bind.to_string().parse(), Ok(bind));
+
+        assert_eq!(
+            "Ctrl + Space".parse::<KeyBind>().map(|bind| bind.key),
+            Ok(Key::Character(" ".into()))
+        );
+        assert_eq!(
+            "Ctrl + ".parse::<KeyBind>(),
+            Err(ParseKeyBindError::MissingKey)
+        );
+        assert_eq!(
+            "Ctrl + Hyper".parse::<KeyBind>(),
+            Err(ParseKeyBindError::UnknownKey("Hyper".into()))
+        );
+    }
+
     #[test]
     fn ctrl_a_no_fallback_to_french_azerty_q() {
         let bind = KeyBind {
@@ -242,4 +486,16 @@ mod test {
             Some(&Physical::Code(Code::KeyA)),
         ));
     }
+
+    #[test]
+    fn symbols() {
+        let bind: KeyBind = "Ctrl + Shift + t".parse().unwrap();
+        assert_eq!(bind.symbols(), ["Ctrl", "⇧", "T"]);
+
+        let bind: KeyBind = "Alt + ArrowLeft".parse().unwrap();
+        assert_eq!(bind.symbols(), ["Alt", "←"]);
+
+        let bind: KeyBind = "Super + F11".parse().unwrap();
+        assert_eq!(bind.symbols(), ["Super", "F11"]);
+    }
 }
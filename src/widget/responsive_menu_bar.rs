@@ -12,6 +12,7 @@ pub fn responsive_menu_bar() -> ResponsiveMenuBar {
     ResponsiveMenuBar::default()
 }
 
+#[derive(Clone, Copy)]
 pub struct ResponsiveMenuBar {
     collapsed_item_width: ItemWidth,
     item_width: ItemWidth,
@@ -3,7 +3,7 @@
 
 use std::borrow::Cow;
 
-use crate::widget::{FlexRow, Row, column, container, flex_row, list, row, text};
+use crate::widget::{FlexRow, Row, column, container, flex_row, icon, list, row, text};
 use crate::{Element, Theme, theme};
 use derive_setters::Setters;
 use iced_core::Length;
@@ -84,6 +84,7 @@ pub fn builder<'a, Message: 'static>(title: impl Into<Cow<'a, str>>) -> Item<'a,
         title: title.into(),
         description: None,
         icon: None,
+        locked: None,
     }
 }
 
@@ -100,9 +101,31 @@ pub struct Item<'a, Message> {
     /// A custom icon to display before the text.
     #[setters(strip_option, into)]
     icon: Option<Element<'a, Message>>,
+
+    /// Explains why the item is locked, which disables its control.
+    #[setters(strip_option, into)]
+    locked: Option<Cow<'a, str>>,
 }
 
 impl<'a, Message: Clone + 'static> Item<'a, Message> {
+    /// Locks the item if the config key is enforced by the administrator.
+    pub fn locked_by(self, config: &cosmic_config::Config, key: &str) -> Self {
+        if config.is_locked(key) {
+            self.locked(crate::fl!("setting-locked"))
+        } else {
+            self
+        }
+    }
+
+    /// Whether the item is locked.
+    ///
+    /// The built-in togglers and checkboxes are disabled when locked, but custom
+    /// controls must be disabled by the caller.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some()
+    }
+
     /// Assigns a control to the item.
     pub fn control(self, widget: impl Into<Element<'a, Message>>) -> Row<'a, Message, Theme> {
         item_row(self.control_(widget.into()))
@@ -114,11 +137,21 @@ impl<'a, Message: Clone + 'static> Item<'a, Message> {
     }
 
     fn label(self) -> Element<'a, Message> {
-        if let Some(description) = self.description {
-            column::with_capacity(2)
+        if self.description.is_some() || self.locked.is_some() {
+            column::with_capacity(3)
                 .spacing(2)
                 .push(text::body(self.title).wrapping(Wrapping::Word))
-                .push(text::caption(description).wrapping(Wrapping::Word))
+                .push_maybe(
+                    self.description
+                        .map(|description| text::caption(description).wrapping(Wrapping::Word)),
+                )
+                .push_maybe(self.locked.map(|locked| {
+                    row::with_capacity(2)
+                        .spacing(theme::spacing().space_xxs)
+                        .align_y(iced::Alignment::Center)
+                        .push(icon::from_name("changes-prevent-symbolic").size(14))
+                        .push(text::caption(locked).wrapping(Wrapping::Word))
+                }))
                 .width(Length::Fill)
                 .into()
         } else {
@@ -146,6 +179,10 @@ impl<'a, Message: Clone + 'static> Item<'a, Message> {
         is_checked: bool,
         message: impl Fn(bool) -> Message + 'static,
     ) -> list::ListButton<'a, Message> {
+        if self.is_locked() {
+            return self.toggler_maybe(is_checked, None::<fn(bool) -> Message>);
+        }
+
         let on_press = message(!is_checked);
         list::button(
             self.control(
@@ -162,6 +199,7 @@ impl<'a, Message: Clone + 'static> Item<'a, Message> {
         is_checked: bool,
         message: Option<impl Fn(bool) -> Message + 'static>,
     ) -> list::ListButton<'a, Message> {
+        let message = message.filter(|_| !self.is_locked());
         let on_press = message.as_ref().map(|f| f(!is_checked));
         list::button(
             self.control(
@@ -178,6 +216,10 @@ impl<'a, Message: Clone + 'static> Item<'a, Message> {
         is_checked: bool,
         message: impl Fn(bool) -> Message + 'static,
     ) -> list::ListButton<'a, Message> {
+        if self.is_locked() {
+            return self.checkbox_maybe(is_checked, None::<fn(bool) -> Message>);
+        }
+
         let on_press = message(!is_checked);
         list::button(
             self.control_start(
@@ -194,6 +236,7 @@ impl<'a, Message: Clone + 'static> Item<'a, Message> {
         is_checked: bool,
         message: Option<impl Fn(bool) -> Message + 'static>,
     ) -> list::ListButton<'a, Message> {
+        let message = message.filter(|_| !self.is_locked());
         let on_press = message.as_ref().map(|f| f(!is_checked));
         list::button(
             self.control_start(
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub mod item;
+pub mod schema;
 pub mod section;
 
 pub use self::item::{flex_item, flex_item_row, item, item_row};
@@ -0,0 +1,213 @@
+// Copyright 2026 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Settings sections generated from the schema of a config entry.
+
+use std::sync::Arc;
+
+use cosmic_config::Config;
+use cosmic_config::schema::{Key, Schema, Shape, Value};
+
+use super::item;
+use super::section::{self, Section};
+use crate::fl;
+use crate::widget::{dropdown, spin_button, text, text_input};
+
+/// A change made to a key through a section generated by [`section()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub key: &'static str,
+    pub value: Value,
+}
+
+impl Change {
+    /// Checks the value against the schema, and writes it to the config in a transaction.
+    pub fn apply(&self, schema: &Schema, config: &Config) -> Result<(), cosmic_config::Error> {
+        schema.set(config, self.key, &self.value)
+    }
+}
+
+type OnChange<Message> = Arc<dyn Fn(Change) -> Message + Send + Sync>;
+
+/// A section with a control for each key of a schema, showing the values of `config`.
+///
+/// Booleans are shown as togglers, numbers as spin buttons, enums and keys with
+/// `one_of` options as dropdowns, and text as text inputs. Keys of other types
+/// are skipped. Each [`Change`] should be applied to the config by the caller.
+///
+/// Spin buttons are bounded by `#[validate(range(..))]`, or else by the numeric type
+/// of the key, so that an unsigned key cannot be set below zero.
+///
+/// Titles and descriptions are the doc comments of the schema, and the options of an
+/// enum are the names of its variants in words, such as `Dark mode` for `DarkMode`.
+/// Neither is translated; use [`section_with_labels`] to label the options.
+///
+/// ```ignore
+/// Message::Setting(change) => {
+///     if let Err(why) = change.apply(&Prefs::schema(), &self.config) {
+///         tracing::error!(?why, "failed to apply setting");
+///     }
+/// }
+/// ```
+pub fn section<'a, Message: Clone + 'static>(
+    schema: &Schema,
+    config: &Config,
+    on_change: impl Fn(Change) -> Message + Send + Sync + 'static,
+) -> Section<'a, Message> {
+    section_with_labels(schema, config, |_, _| None, on_change)
+}
+
+/// A [`section()`] whose enum options are labelled by `labels`, such as with `fl!`.
+///
+/// `labels` is given the name of the key and of the variant, and options for which
+/// it returns `None` are labelled with the name of the variant in words.
+///
+/// ```ignore
+/// settings::schema::section_with_labels(
+///     &Prefs::schema(),
+///     &self.config,
+///     |key, variant| match (key, variant) {
+///         ("theme", "DarkMode") => Some(fl!("dark-mode")),
+///         _ => None,
+///     },
+///     Message::Setting,
+/// )
+/// ```
+pub fn section_with_labels<'a, Message: Clone + 'static>(
+    schema: &Schema,
+    config: &Config,
+    labels: impl Fn(&str, &str) -> Option<String>,
+    on_change: impl Fn(Change) -> Message + Send + Sync + 'static,
+) -> Section<'a, Message> {
+    let on_change: OnChange<Message> = Arc::new(on_change);
+    schema
+        .keys
+        .iter()
+        .fold(section::with_capacity(schema.keys.len()), |section, key| {
+            add_key(section, key, config, &labels, on_change.clone())
+        })
+}
+
+fn add_key<'a, Message: Clone + 'static>(
+    section: Section<'a, Message>,
+    key: &Key,
+    config: &Config,
+    labels: &dyn Fn(&str, &str) -> Option<String>,
+    on_change: OnChange<Message>,
+) -> Section<'a, Message> {
+    let Some(value) = config.get_value(key).ok().or_else(|| key.default.clone()) else {
+        return section;
+    };
+
+    let name = key.name;
+    let change = move |value: Value| on_change(Change { key: name, value });
+    let label = |value: &Value| match value {
+        Value::Variant(variant) => labels(name, variant).unwrap_or_else(|| humanize(variant)),
+        value => label(value),
+    };
+
+    let mut item = item::builder(key.title()).locked_by(config, name);
+    if let Some(description) = key.description() {
+        item = item.description(description);
+    }
+
+    // Locked keys show their value in place of a control.
+    if item.is_locked() && !matches!(value, Value::Bool(_)) {
+        return section.add(item.control(text::body(label(&value))));
+    }
+
+    let options = match &key.shape {
+        _ if !key.options.is_empty() => key.options.clone(),
+        Shape::Enum { variants, .. } => variants
+            .iter()
+            .map(|variant| Value::Variant((*variant).to_owned()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if !options.is_empty() {
+        let labels = options.iter().map(label).collect::<Vec<_>>();
+        let selected = options.iter().position(|option| *option == value);
+        return section.add(item.control(dropdown(labels, selected, move |index| {
+            change(options[index].clone())
+        })));
+    }
+
+    match value {
+        Value::Bool(value) => {
+            section.add(item.toggler(value, move |value| change(Value::Bool(value))))
+        }
+
+        Value::Integer(value) => {
+            let (min, max) = match key.shape {
+                Shape::Integer { min, max } => (min, max),
+                _ => (i64::MIN, i64::MAX),
+            };
+            let min = key.min.map_or(min, |range| (range as i64).max(min));
+            let max = key.max.map_or(max, |range| (range as i64).min(max));
+            section.add(item.control(spin_button(
+                value.to_string(),
+                #[cfg(feature = "a11y")]
+                key.title(),
+                value,
+                1,
+                min,
+                max,
+                move |value| change(Value::Integer(value)),
+            )))
+        }
+
+        Value::Float(value) => {
+            let (min, max) = match key.shape {
+                Shape::Float { min, max } => (min, max),
+                _ => (f64::MIN, f64::MAX),
+            };
+            let min = key.min.map_or(min, |range| range.max(min));
+            let max = key.max.map_or(max, |range| range.min(max));
+            section.add(item.control(spin_button(
+                format!("{value:.1}"),
+                #[cfg(feature = "a11y")]
+                key.title(),
+                value,
+                0.1,
+                min,
+                max,
+                move |value| change(Value::Float(value)),
+            )))
+        }
+
+        Value::Text(value) => section.add(
+            item.control(text_input("", value).on_input(move |value| change(Value::Text(value)))),
+        ),
+
+        Value::Variant(_) => section,
+    }
+}
+
+/// The label of a value, with variants in words.
+fn label(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => fl!("setting-on"),
+        Value::Bool(false) => fl!("setting-off"),
+        Value::Integer(value) => value.to_string(),
+        Value::Float(value) => format!("{value:.1}"),
+        Value::Text(value) => value.clone(),
+        Value::Variant(variant) => humanize(variant),
+    }
+}
+
+/// The name of a variant in words, such as `Dark mode` for `DarkMode`.
+fn humanize(variant: &str) -> String {
+    let mut words = String::with_capacity(variant.len() + 4);
+    for (i, c) in variant.chars().enumerate() {
+        if i == 0 {
+            words.push(c);
+        } else if c.is_uppercase() {
+            words.push(' ');
+            words.extend(c.to_lowercase());
+        } else {
+            words.push(if c == '_' { ' ' } else { c });
+        }
+    }
+    words
+}